@@ -1,3 +1,5 @@
+use crate::HistoryItemId;
+use chrono::{DateTime, Utc};
 use nu_ansi_term::Style;
 use std::ops::Range;
 
@@ -74,6 +76,21 @@ pub trait Completer: Send {
 }
 
 /// Suggestion returned by the Completer
+///
+/// Fields get added over time, so completers should fill in the ones they don't set from
+/// [`Suggestion::default`] to keep compiling:
+///
+/// ```
+/// use reedline::{Span, Suggestion};
+///
+/// let suggestion = Suggestion {
+///     value: "batman".into(),
+///     span: Span::new(0, 3),
+///     append_whitespace: true,
+///     ..Suggestion::default()
+/// };
+/// assert_eq!(suggestion.history_match, None);
+/// ```
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Suggestion {
     /// String replacement that will be introduced to the the buffer
@@ -90,4 +107,36 @@ pub struct Suggestion {
     /// Whether to append a space after selecting this suggestion.
     /// This helps to avoid that a completer repeats the complete suggestion.
     pub append_whitespace: bool,
+    /// Whether to submit the line right after selecting this suggestion from a menu, as if
+    /// Enter was pressed again. Completers opt in for suggestions that are complete command
+    /// lines.
+    pub accept: bool,
+    /// Optional byte offsets of the characters in the value that matched the typed text.
+    /// Menus can highlight them.
+    pub match_indices: Option<Vec<usize>>,
+    /// Where the suggested history entry came from, for menus previewing it. Only filled in
+    /// by history menus asked to, see
+    /// [`MenuBuilder::with_history_match_info`](crate::MenuBuilder::with_history_match_info)
+    pub history_match: Option<HistoryMatch>,
+}
+
+/// Recorded details of the history entry behind a [`Suggestion`]
+///
+/// Taken from the occurrence of the command line that is suggested, the newest one unless
+/// the menu lists the entries oldest first. The histories not recording a detail leave it
+/// `None`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HistoryMatch {
+    /// Id of the entry in the history
+    pub id: Option<HistoryItemId>,
+    /// When the command was run
+    pub start_timestamp: Option<DateTime<Utc>>,
+    /// Directory the command was run in
+    pub cwd: Option<String>,
+    /// Host the command was run on
+    pub hostname: Option<String>,
+    /// Exit status of the command
+    pub exit_status: Option<i64>,
+    /// How many times the command line is in the history, among the entries the menu pulls
+    pub occurrences: usize,
 }
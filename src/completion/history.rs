@@ -1,15 +1,98 @@
-use std::ops::Deref;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use chrono::Local;
 
 use crate::{
-    history::SearchQuery, menu_functions::parse_selection_char, Completer, History, Span,
-    Suggestion,
+    history::{SearchDirection, SearchQuery, SessionFilter},
+    menu_functions::parse_selection_char,
+    Completer, History, HistoryItem, HistoryMatch, Span, Suggestion,
 };
 
-const SELECTION_CHAR: char = '!';
-
 // The HistoryCompleter is created just before updating the menu
 // It pulls data from the object that contains access to the History
-pub(crate) struct HistoryCompleter<'menu>(&'menu dyn History);
+pub(crate) struct HistoryCompleter<'menu> {
+    history: &'menu dyn History,
+    // Marks the index of the entry to select at the end of the search text
+    selection_char: char,
+    // Match entries containing the search text as a subsequence, best matches first
+    fuzzy: bool,
+    // Rank entries by how often and how recently they were used
+    frecency_half_life: Option<usize>,
+    // Most entries to pull from the history
+    max_results: Option<usize>,
+    // Report the characters matching the search text in the suggestions
+    highlight_matches: bool,
+    // Return every occurrence of the matching entries
+    keep_duplicates: bool,
+    // Describe the entries with the time they were run
+    show_timestamp: bool,
+    // Describe the entries with the directory they were run in
+    show_cwd: bool,
+    // Sessions to pull the entries from, relative to the active one of the history
+    session_filter: SessionFilter,
+    // Only pull the entries run in this directory or below it
+    cwd_scope: Option<String>,
+    // Only pull the entries that succeeded, or failed
+    exit_successful: Option<bool>,
+    // Pull the entries without an exit status when filtering on it
+    keep_unknown_exit_status: bool,
+    // Only pull the entries run on this host
+    hostname: Option<String>,
+    // Leave out the entries containing any of these
+    excluded_substrings: Vec<String>,
+    // Order of the entries matched by substring or tokens, newest first if backward
+    direction: SearchDirection,
+    // Submit the line right after selecting an entry that is exactly the search text
+    accept_exact_matches: bool,
+    // Attach the recorded details of the entries to the suggestions
+    match_info: bool,
+    // Answers while the history is still searching
+    last_results: Option<LastHistoryResults>,
+}
+
+/// Shared handle on the last entries and count of a history menu, shown again while a slow
+/// history is still searching for the next ones
+#[derive(Clone, Default)]
+pub(crate) struct LastHistoryResults {
+    items: Arc<Mutex<Vec<HistoryItem>>>,
+    count: Arc<AtomicUsize>,
+}
+
+/// A [`HistoryCompleter`] for the history menus, which collect the entries a page at a time
+/// with [`Completer::partial_complete`] instead of completing their common prefix
+pub(crate) struct HistoryMenuCompleter<'menu>(pub HistoryCompleter<'menu>);
+
+impl<'menu> Completer for HistoryMenuCompleter<'menu> {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        self.0.complete(line, pos)
+    }
+
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        start: usize,
+        offset: usize,
+    ) -> Vec<Suggestion> {
+        self.0.page(line, pos, start, offset)
+    }
+
+    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+        self.0.total_completions(line, pos)
+    }
+}
+
+impl LastHistoryResults {
+    fn items(&self) -> MutexGuard<'_, Vec<HistoryItem>> {
+        self.items.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
 
 // Safe to implement Send since the Historycompleter should only be used when
 // updating the menu and that must happen in the same thread
@@ -17,58 +100,599 @@ unsafe impl<'menu> Send for HistoryCompleter<'menu> {}
 
 impl<'menu> Completer for HistoryCompleter<'menu> {
     fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
-        let parsed = parse_selection_char(line, SELECTION_CHAR);
-        let values = self
-            .0
-            .search(SearchQuery::all_that_contain_rev(
-                parsed.remainder.to_string(),
-            ))
-            .expect("todo: error handling");
-
-        values
+        let line = text_before_cursor(line, pos);
+        let items = self.search(line, None);
+        let items = self.or_last_items(items);
+        let occurrences = self.occurrences(&items);
+        items
             .into_iter()
-            .map(|value| self.create_suggestion(line, pos, value.command_line.deref()))
+            .map(|item| self.create_suggestion(line, pos, item, occurrences.as_ref()))
             .collect()
     }
 
-    // TODO: Implement `fn partial_complete()`
+    /// Completes the longest prefix shared by the matching entries, an entry that is the
+    /// only match completely. Nothing if no entry matches, or the entries don't share more
+    /// than the typed text, so the menu is left to pick one
+    fn partial_complete(
+        &mut self,
+        line: &str,
+        pos: usize,
+        _start: usize,
+        _offset: usize,
+    ) -> Vec<Suggestion> {
+        let line = text_before_cursor(line, pos);
+        let typed = parse_selection_char(line, self.selection_char).remainder;
+        // Nothing to complete from while a slow history is still searching
+        let items = self.search(line, None).unwrap_or_default();
+        let value = match items.as_slice() {
+            [] => return Vec::new(),
+            [item] => item.command_line.clone(),
+            [first, rest @ ..] => {
+                let prefix = rest
+                    .iter()
+                    .fold(first.command_line.as_str(), |prefix, item| {
+                        common_prefix(prefix, &item.command_line)
+                    });
+                if prefix.len() <= typed.len() || !prefix.starts_with(typed) {
+                    return Vec::new();
+                }
+                prefix.to_string()
+            }
+        };
+        vec![Suggestion {
+            value,
+            span: Span {
+                start: pos.saturating_sub(line.len()),
+                end: pos,
+            },
+            ..Suggestion::default()
+        }]
+    }
 
-    fn total_completions(&mut self, line: &str, _pos: usize) -> usize {
-        let parsed = parse_selection_char(line, SELECTION_CHAR);
-        let count = self
-            .0
-            .count(SearchQuery::all_that_contain_rev(
-                parsed.remainder.to_string(),
-            ))
-            .expect("todo: error handling");
-        count as usize
+    fn total_completions(&mut self, line: &str, pos: usize) -> usize {
+        let line = text_before_cursor(line, pos);
+        let count = self.count(line);
+        match (count, &self.last_results) {
+            (Some(count), Some(last)) => {
+                last.count.store(count, Ordering::Relaxed);
+                count
+            }
+            (Some(count), None) => count,
+            (None, Some(last)) => last.count.load(Ordering::Relaxed),
+            (None, None) => 0,
+        }
     }
 }
 
 impl<'menu> HistoryCompleter<'menu> {
-    pub fn new(history: &'menu dyn History) -> Self {
-        Self(history)
+    pub fn new(history: &'menu dyn History, selection_char: char) -> Self {
+        Self {
+            history,
+            selection_char,
+            fuzzy: false,
+            frecency_half_life: None,
+            max_results: None,
+            highlight_matches: false,
+            keep_duplicates: false,
+            show_timestamp: false,
+            show_cwd: false,
+            session_filter: SessionFilter::All,
+            cwd_scope: None,
+            exit_successful: None,
+            keep_unknown_exit_status: false,
+            hostname: None,
+            excluded_substrings: Vec::new(),
+            direction: SearchDirection::Backward,
+            accept_exact_matches: false,
+            match_info: false,
+            last_results: None,
+        }
+    }
+
+    /// Match the entries fuzzily instead of by substring
+    pub fn with_fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    /// Rank the entries by frecency instead of recency
+    ///
+    /// Every occurrence of an entry adds to its score, with a weight halving for every
+    /// `half_life` newer matching entries. A short half-life favors recent entries, a long
+    /// one frequent entries.
+    pub fn with_frecency(mut self, half_life: Option<usize>) -> Self {
+        self.frecency_half_life = half_life;
+        self
+    }
+
+    /// Return at most `max_results` entries, the best ones in the order of the matching mode
+    pub fn with_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Set the [`Suggestion::match_indices`] of the characters matching the search text, for
+    /// the menu to highlight them
+    pub fn with_highlight_matches(mut self, highlight_matches: bool) -> Self {
+        self.highlight_matches = highlight_matches;
+        self
+    }
+
+    /// Return every occurrence of the matching entries instead of only the most recent one,
+    /// described with the time they were run if the history records it
+    pub fn with_duplicates(mut self, keep_duplicates: bool) -> Self {
+        self.keep_duplicates = keep_duplicates;
+        self
+    }
+
+    /// Describe the suggestions with the time the entries were run, if the history records it
+    pub fn with_timestamp(mut self, show_timestamp: bool) -> Self {
+        self.show_timestamp = show_timestamp;
+        self
+    }
+
+    /// Describe the suggestions with the directory the entries were run in, if the history
+    /// records it
+    pub fn with_cwd(mut self, show_cwd: bool) -> Self {
+        self.show_cwd = show_cwd;
+        self
+    }
+
+    /// Only pull the entries of the active session of the history, or those of the other
+    /// sessions
+    pub fn with_session_filter(mut self, session_filter: SessionFilter) -> Self {
+        self.session_filter = session_filter;
+        self
+    }
+
+    /// Only pull the entries run in the directory `cwd` or below it. Entries that don't
+    /// record their directory are left out
+    pub fn with_cwd_scope(mut self, cwd: Option<String>) -> Self {
+        self.cwd_scope = cwd;
+        self
+    }
+
+    /// Only pull the entries that exited successfully if `Some(true)`, or those that failed if
+    /// `Some(false)`
+    pub fn with_exit_successful(mut self, exit_successful: Option<bool>) -> Self {
+        self.exit_successful = exit_successful;
+        self
+    }
+
+    /// Also pull the entries without a recorded exit status when filtering on it, as the
+    /// histories not recording it have none
+    pub fn with_unknown_exit_status(mut self, keep_unknown_exit_status: bool) -> Self {
+        self.keep_unknown_exit_status = keep_unknown_exit_status;
+        self
+    }
+
+    /// Only pull the entries run on the host `hostname`, leaving out those that don't record
+    /// their host. Pulls the entries of every host if `None`
+    pub fn with_hostname(mut self, hostname: Option<String>) -> Self {
+        self.hostname = hostname;
+        self
+    }
+
+    /// Leave out the entries containing any of `excluded_substrings`, like `--force` when
+    /// completing `git` commands
+    pub fn with_excluded_substrings(mut self, excluded_substrings: Vec<String>) -> Self {
+        self.excluded_substrings = excluded_substrings;
+        self
+    }
+
+    /// Return the entries matched by substring newest first if [`SearchDirection::Backward`],
+    /// or oldest first if [`SearchDirection::Forward`], keeping the newest or the oldest
+    /// occurrence of the repeated entries respectively. Matches by tokens or fuzzy matches
+    /// follow the direction among equally good matches
+    pub fn with_direction(mut self, direction: SearchDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Mark the entries that are exactly the search text as [`Suggestion::accept`], so
+    /// selecting them submits the line without another Enter
+    pub fn with_accept_exact_matches(mut self, accept_exact_matches: bool) -> Self {
+        self.accept_exact_matches = accept_exact_matches;
+        self
+    }
+
+    /// Attach the recorded details of the entries and how often they occur as
+    /// [`Suggestion::history_match`]. Counting the occurrences takes one more query, they are
+    /// left out while a slow history is still counting them
+    pub fn with_match_info(mut self, match_info: bool) -> Self {
+        self.match_info = match_info;
+        self
     }
 
-    fn create_suggestion(&self, line: &str, pos: usize, value: &str) -> Suggestion {
+    /// Keep the entries and the count of the completions in `last_results`, to answer with
+    /// them instead while a slow history is still searching, see [`History::try_search`]
+    pub fn with_last_results(mut self, last_results: LastHistoryResults) -> Self {
+        self.last_results = Some(last_results);
+        self
+    }
+
+    /// The `offset` entries from the `start`-th one on, for the list menu paging through them
+    fn page(&mut self, line: &str, pos: usize, start: usize, offset: usize) -> Vec<Suggestion> {
+        let line = text_before_cursor(line, pos);
+        // Only the entries up to the end of the requested page are pulled from the history
+        let limit = i64::try_from(start.saturating_add(offset)).unwrap_or(i64::MAX);
+        let items = self
+            .search(line, Some(limit))
+            .map(|items| items.into_iter().skip(start).collect());
+        let items = self.or_last_items(items);
+        let occurrences = self.occurrences(&items);
+        items
+            .into_iter()
+            .map(|item| self.create_suggestion(line, pos, item, occurrences.as_ref()))
+            .collect()
+    }
+
+    /// The `items` found, kept as the last results, or the last results if the history is
+    /// still searching
+    fn or_last_items(&self, items: Option<Vec<HistoryItem>>) -> Vec<HistoryItem> {
+        match (items, &self.last_results) {
+            (Some(items), Some(last)) => {
+                last.items().clone_from(&items);
+                items
+            }
+            (Some(items), None) => items,
+            (None, Some(last)) => last.items().clone(),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    /// Number of entries containing the text before the selection char, `None` while the
+    /// history is still counting them
+    fn count(&self, line: &str) -> Option<usize> {
+        let parsed = parse_selection_char(line, self.selection_char);
+        if self.fuzzy
+            || self.frecency_half_life.is_some()
+            || parsed.remainder.split_whitespace().nth(1).is_some()
+        {
+            // Ranked searches are deduplicated by the completer itself
+            return self.search(line, None).map(|items| items.len());
+        }
+        let mut query = self.restrict(SearchQuery::all_that_contain_rev(
+            parsed.remainder.to_string(),
+        ));
+        query.filter.keep_duplicates = self.keep_duplicates;
+        query.filter.distinct = !self.keep_duplicates;
+        let count = match self.history.try_count(query) {
+            Ok(count) => count? as usize,
+            Err(_) => 0,
+        };
+        Some(self.max_results.map_or(count, |max| max.min(count)))
+    }
+
+    /// The items of the `query`, none if the history fails and `None` while it is still
+    /// searching
+    fn try_search(&self, query: SearchQuery) -> Option<Vec<HistoryItem>> {
+        // A failing history backend leaves the menu empty instead of crashing the editor
+        self.history
+            .try_search(query)
+            .unwrap_or_else(|_| Some(Vec::new()))
+    }
+
+    /// The first `limit` entries in the configured direction containing the text before the
+    /// selection char, `None` while the history is still searching
+    fn search(&self, line: &str, limit: Option<i64>) -> Option<Vec<HistoryItem>> {
+        let parsed = parse_selection_char(line, self.selection_char);
+        let limit = self
+            .max_results
+            .map(|max| i64::try_from(max).unwrap_or(i64::MAX))
+            .into_iter()
+            .chain(limit)
+            .min();
+        if self.fuzzy {
+            return self.fuzzy_search(parsed.remainder, limit);
+        }
+        let tokens = parsed.remainder.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() > 1 {
+            return self.tokens_search(&tokens, limit);
+        }
+        if let Some(half_life) = self.frecency_half_life {
+            return self.frecency_search(parsed.remainder, half_life, limit);
+        }
+        let query = match self.direction {
+            SearchDirection::Backward => {
+                SearchQuery::all_that_contain_rev(parsed.remainder.to_string())
+            }
+            SearchDirection::Forward => SearchQuery::all_that_contain(parsed.remainder.to_string()),
+        };
+        self.run(SearchQuery { limit, ..query })
+    }
+
+    /// Restricts the `query` to the configured sessions, host, directory and exit status,
+    /// leaving out the excluded entries. Without a session filter the session is left unset,
+    /// so the query sees the entries of every session
+    fn restrict(&self, mut query: SearchQuery) -> SearchQuery {
+        if self.session_filter != SessionFilter::All {
+            query.filter.session = self.history.session();
+            query.filter.session_filter = self.session_filter;
+        }
+        query.filter.cwd_prefix = self.cwd_scope.clone();
+        query.filter.exit_successful = self.exit_successful;
+        query.filter.keep_unknown_exit_status = self.keep_unknown_exit_status;
+        query.filter.hostname = self.hostname.clone();
+        query.filter.excluded_substrings = self.excluded_substrings.clone();
+        query
+    }
+
+    /// Runs the `query`, keeping the duplicates if configured so
+    fn run(&self, query: SearchQuery) -> Option<Vec<HistoryItem>> {
+        let mut query = self.restrict(query);
+        query.filter.keep_duplicates = self.keep_duplicates;
+        query.filter.distinct = !self.keep_duplicates;
+        let mut items = self.try_search(query)?;
+        if !self.keep_duplicates {
+            // For the histories returning every occurrence despite `distinct`
+            let mut seen_command_lines = HashSet::new();
+            items.retain(|item| seen_command_lines.insert(item.command_line.clone()));
+        }
+        Some(items)
+    }
+
+    /// The `limit` best entries containing the characters of `pattern` in order, in the
+    /// configured direction among equally good matches
+    fn fuzzy_search(&self, pattern: &str, limit: Option<i64>) -> Option<Vec<HistoryItem>> {
+        let mut matches = self
+            .run(SearchQuery::everything(self.direction, None))?
+            .into_iter()
+            .filter_map(|item| {
+                fuzzy_match(pattern, &item.command_line).map(|(score, _)| (score, item))
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        Some(
+            matches
+                .into_iter()
+                .map(|(_, item)| item)
+                .take(limit)
+                .collect(),
+        )
+    }
+
+    /// The `limit` entries containing all of the `tokens`, those with the tokens closest
+    /// together and in the typed order first, in the configured direction among equally good
+    /// matches
+    fn tokens_search(&self, tokens: &[&str], limit: Option<i64>) -> Option<Vec<HistoryItem>> {
+        let query = SearchQuery {
+            direction: self.direction,
+            ..SearchQuery::all_with_tokens_rev(tokens.iter().map(|t| t.to_string()).collect())
+        };
+        let mut matches = self.run(query)?;
+        matches.sort_by_key(|item| token_spread(tokens, &item.command_line));
+
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        Some(matches.into_iter().take(limit).collect())
+    }
+
+    /// The `limit` entries containing `text` with the highest frecency, the newer entry
+    /// first among equally ranked ones
+    fn frecency_search(
+        &self,
+        text: &str,
+        half_life: usize,
+        limit: Option<i64>,
+    ) -> Option<Vec<HistoryItem>> {
+        let half_life = half_life.max(1) as f64;
+        let mut scores = HashMap::new();
+        let mut matches = Vec::new();
+        // Every occurrence counts
+        let mut query = self.restrict(SearchQuery::all_that_contain_rev(text.to_string()));
+        query.filter.keep_duplicates = true;
+        let items = self.try_search(query)?;
+        for (age, item) in items.into_iter().enumerate() {
+            let weight = 0.5_f64.powf(age as f64 / half_life);
+            match scores.entry(item.command_line.clone()) {
+                Entry::Occupied(mut score) => *score.get_mut() += weight,
+                Entry::Vacant(score) => {
+                    score.insert(weight);
+                    matches.push(item);
+                }
+            }
+        }
+        matches.sort_by(|a, b| scores[&b.command_line].total_cmp(&scores[&a.command_line]));
+
+        let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        Some(matches.into_iter().take(limit).collect())
+    }
+
+    fn create_suggestion(
+        &self,
+        line: &str,
+        pos: usize,
+        item: HistoryItem,
+        occurrences: Option<&HashMap<String, usize>>,
+    ) -> Suggestion {
+        // The entry replaces the search text together with a trailing selection marker, with
+        // byte offsets just like the insertion point of the editor
         let span = Span {
-            start: pos - line.len(),
+            start: pos.saturating_sub(line.len()),
             end: pos,
         };
 
+        let description = self.describe(&item);
+        let match_indices = self.match_indices(line, &item.command_line);
+        let accept = self.accept_exact_matches
+            && parse_selection_char(line, self.selection_char).remainder == item.command_line;
+        let history_match = occurrences.map(|occurrences| Self::match_info(&item, occurrences));
+
         Suggestion {
-            value: value.to_string(),
-            description: None,
+            value: item.command_line,
+            description,
             style: None,
             extra: None,
             span,
             append_whitespace: false,
+            accept,
+            match_indices,
+            history_match,
         }
     }
+
+    /// How many times the command lines of the `items` are among the entries the completer
+    /// pulls, counted with a single query if the details of the entries are attached. `None`
+    /// if they aren't, or while the history is still searching
+    fn occurrences(&self, items: &[HistoryItem]) -> Option<HashMap<String, usize>> {
+        if !self.match_info {
+            return None;
+        }
+        let mut occurrences = items
+            .iter()
+            .map(|item| (item.command_line.clone(), 0))
+            .collect::<HashMap<_, _>>();
+        if items.is_empty() {
+            return Some(occurrences);
+        }
+        let mut query = self.restrict(SearchQuery::everything(SearchDirection::Backward, None));
+        query.filter.keep_duplicates = true;
+        for item in self.try_search(query)? {
+            if let Some(count) = occurrences.get_mut(&item.command_line) {
+                *count += 1;
+            }
+        }
+        Some(occurrences)
+    }
+
+    /// The recorded details of the `item`, with the `occurrences` of its command line
+    fn match_info(item: &HistoryItem, occurrences: &HashMap<String, usize>) -> HistoryMatch {
+        HistoryMatch {
+            id: item.id,
+            start_timestamp: item.start_timestamp,
+            cwd: item.cwd.clone(),
+            hostname: item.hostname.clone(),
+            exit_status: item.exit_status,
+            occurrences: occurrences.get(&item.command_line).copied().unwrap_or(0),
+        }
+    }
+
+    /// The recorded metadata of the `item` that is shown, repeated entries always being told
+    /// apart by the time they were run
+    fn describe(&self, item: &HistoryItem) -> Option<String> {
+        let timestamp = item
+            .start_timestamp
+            .filter(|_| self.show_timestamp || self.keep_duplicates)
+            .map(|timestamp| {
+                timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            });
+        let cwd = item.cwd.clone().filter(|_| self.show_cwd);
+
+        match (timestamp, cwd) {
+            (Some(timestamp), Some(cwd)) => Some(format!("{timestamp} {cwd}")),
+            (timestamp, cwd) => timestamp.or(cwd),
+        }
+    }
+
+    /// Byte offsets of the characters of `value` matched by the search text in `line`
+    fn match_indices(&self, line: &str, value: &str) -> Option<Vec<usize>> {
+        if !self.highlight_matches {
+            return None;
+        }
+        let parsed = parse_selection_char(line, self.selection_char);
+        if self.fuzzy {
+            return fuzzy_match(parsed.remainder, value).map(|(_, indices)| indices);
+        }
+        let mut indices = Vec::new();
+        for token in parsed.remainder.split_whitespace() {
+            if let Some(start) = value.find(token) {
+                let end = start + token.len();
+                indices.extend(
+                    value[start..end]
+                        .char_indices()
+                        .map(|(offset, _)| start + offset),
+                );
+            }
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        Some(indices)
+    }
+}
+
+/// The text completed for the cursor at `pos`
+///
+/// The menus pass the text ending at the cursor, which may start further into the buffer,
+/// while other callers pass the whole line
+fn text_before_cursor(line: &str, pos: usize) -> &str {
+    if pos < line.len() {
+        line.get(..pos).unwrap_or(line)
+    } else {
+        line
+    }
+}
+
+/// The longest prefix `a` shares with `b`
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let end = a
+        .char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or_else(|| a.len().min(b.len()), |((offset, _), _)| offset);
+    &a[..end]
+}
+
+/// Score of `candidate` containing the characters of `pattern` in order, ignoring case,
+/// together with the byte offsets of the matched characters
+///
+/// Consecutive characters and characters starting a word score higher, skipped characters
+/// lower. Returns `None` if `candidate` doesn't match.
+fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let mut pattern = pattern.chars().peekable();
+    let mut score = 0;
+    let mut indices = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for (offset, c) in candidate.char_indices() {
+        let expected = match pattern.peek() {
+            Some(&expected) => expected,
+            None => break,
+        };
+        if expected.to_lowercase().eq(c.to_lowercase()) {
+            score += 1;
+            if previous_matched {
+                score += 2;
+            }
+            if previous.map_or(true, |previous| !previous.is_alphanumeric()) {
+                score += 3;
+            }
+            previous_matched = true;
+            indices.push(offset);
+            let _ = pattern.next();
+        } else {
+            score -= 1;
+            previous_matched = false;
+        }
+        previous = Some(c);
+    }
+    pattern.peek().is_none().then_some((score, indices))
+}
+
+/// Whether the first occurrences of the `tokens` in `candidate` are out of order and how
+/// many bytes they span
+fn token_spread(tokens: &[&str], candidate: &str) -> (bool, usize) {
+    let positions = tokens
+        .iter()
+        .filter_map(|token| {
+            candidate
+                .find(token)
+                .map(|start| (start, start + token.len()))
+        })
+        .collect::<Vec<_>>();
+    let out_of_order = positions.windows(2).any(|pair| pair[0].0 > pair[1].0);
+    let start = positions.iter().map(|(start, _)| *start).min().unwrap_or(0);
+    let end = positions.iter().map(|(_, end)| *end).max().unwrap_or(0);
+    (out_of_order, end - start)
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::{TimeZone, Utc};
     use rstest::rstest;
 
     use super::*;
@@ -101,7 +725,31 @@ mod tests {
         for history_item in history_items {
             history.save(new_history_item(history_item))?;
         }
-        let mut sut = HistoryCompleter::new(&history);
+        let mut sut = HistoryCompleter::new(&history, '!');
+        let actual: Vec<String> = sut
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(vec![], "any", vec![])]
+    #[case(vec!["old match","recent match","between","recent match"], "match", vec!["old match","recent match"])]
+    #[case(vec!["a","b","c","a","b","c"], "", vec!["a","b","c"])]
+    #[case(vec!["c","b","a","c"], "", vec!["c","b","a"])]
+    fn complete_forward_doesnt_return_duplicates(
+        #[case] history_items: Vec<&str>,
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(history_items.len())?;
+        for history_item in history_items {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!').with_direction(SearchDirection::Forward);
         let actual: Vec<String> = sut
             .complete(line, line.len())
             .into_iter()
@@ -110,4 +758,816 @@ mod tests {
         assert_eq!(actual, expected);
         Ok(())
     }
+
+    #[rstest]
+    #[case(vec![], "any", vec![])]
+    #[case(vec!["old match","recent match","between","recent match"], "match", vec!["recent match","recent match","old match"])]
+    #[case(vec!["a","b","c","a","b","c"], "", vec!["c","b","a","c","b","a"])]
+    fn complete_with_duplicates_returns_every_occurrence(
+        #[case] history_items: Vec<&str>,
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(history_items.len())?;
+        for history_item in history_items {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!').with_duplicates(true);
+        let suggestions = sut.complete(line, line.len());
+        let actual: Vec<&str> = suggestions
+            .iter()
+            .map(|suggestion| suggestion.value.as_str())
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions(line, line.len()), expected.len());
+        // The file backed history doesn't record when the entries were run
+        assert!(suggestions
+            .iter()
+            .all(|suggestion| suggestion.description.is_none()));
+        Ok(())
+    }
+
+    #[test]
+    fn complete_attaches_match_info_when_asked() -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        for command_line in ["ls", "cd foo", "ls", "ls -l"] {
+            history.save(new_history_item(command_line))?;
+        }
+
+        let mut sut = HistoryCompleter::new(&history, '!');
+        assert!(sut
+            .complete("ls", 2)
+            .iter()
+            .all(|suggestion| suggestion.history_match.is_none()));
+
+        let mut sut = HistoryCompleter::new(&history, '!').with_match_info(true);
+        let actual: Vec<(String, HistoryMatch)> = sut
+            .complete("ls", 2)
+            .into_iter()
+            .map(|suggestion| (suggestion.value, suggestion.history_match.unwrap()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "ls -l".to_string(),
+                    HistoryMatch {
+                        id: Some(HistoryItemId::new(3)),
+                        occurrences: 1,
+                        ..HistoryMatch::default()
+                    }
+                ),
+                (
+                    "ls".to_string(),
+                    HistoryMatch {
+                        id: Some(HistoryItemId::new(2)),
+                        occurrences: 2,
+                        ..HistoryMatch::default()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            sut.page("ls", 2, 1, 1)[0].history_match,
+            Some(HistoryMatch {
+                id: Some(HistoryItemId::new(2)),
+                occurrences: 2,
+                ..HistoryMatch::default()
+            })
+        );
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn match_info_keeps_the_recorded_details() -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        let started = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        for (cwd, exit_status) in [("/tmp", 1), ("/home/me", 0)] {
+            history.save(HistoryItem {
+                start_timestamp: Some(started),
+                cwd: Some(cwd.to_string()),
+                hostname: Some("laptop".to_string()),
+                exit_status: Some(exit_status),
+                ..new_history_item("make")
+            })?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!').with_match_info(true);
+
+        let suggestions = sut.complete("ma", 2);
+        assert_eq!(suggestions.len(), 1);
+        let history_match = suggestions[0].history_match.clone().unwrap();
+        assert_eq!(history_match.start_timestamp, Some(started));
+        assert_eq!(history_match.cwd.as_deref(), Some("/home/me"));
+        assert_eq!(history_match.hostname.as_deref(), Some("laptop"));
+        assert_eq!(history_match.exit_status, Some(0));
+        assert_eq!(history_match.occurrences, 2);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("git", vec!["git pull", "git status"])]
+    #[case("git pu", vec!["git pull"])]
+    #[case("", vec!["ls", "git pull", "git status"])]
+    fn complete_leaves_out_excluded_entries(
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        for command_line in [
+            "git status",
+            "git push --force",
+            "git pull",
+            "git push -f --FORCE-with-lease",
+            "ls",
+        ] {
+            history.save(new_history_item(command_line))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!')
+            .with_excluded_substrings(vec!["--force".to_string(), " -f".to_string()]);
+
+        let actual: Vec<String> = sut
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions(line, line.len()), expected.len());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(true, "ls", vec![("ls -l", false), ("ls", true)])]
+    #[case(true, "ls -l", vec![("ls -l", true)])]
+    #[case(false, "ls", vec![("ls -l", false), ("ls", false)])]
+    fn exact_matches_are_accepted(
+        #[case] accept_exact_matches: bool,
+        #[case] line: &str,
+        #[case] expected: Vec<(&str, bool)>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(2)?;
+        history.save(new_history_item("ls"))?;
+        history.save(new_history_item("ls -l"))?;
+        let mut sut =
+            HistoryCompleter::new(&history, '!').with_accept_exact_matches(accept_exact_matches);
+
+        let suggestions = sut.complete(line, line.len());
+        let actual: Vec<(&str, bool)> = suggestions
+            .iter()
+            .map(|suggestion| (suggestion.value.as_str(), suggestion.accept))
+            .collect();
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(false, false, true, true, None)]
+    #[case(true, false, true, true, Some("{timestamp}"))]
+    #[case(false, true, true, true, Some("/home/me"))]
+    #[case(true, true, true, true, Some("{timestamp} /home/me"))]
+    #[case(true, true, false, true, Some("/home/me"))]
+    #[case(true, false, false, true, None)]
+    #[case(true, true, false, false, None)]
+    fn suggestion_describes_metadata(
+        #[case] show_timestamp: bool,
+        #[case] show_cwd: bool,
+        #[case] has_timestamp: bool,
+        #[case] has_cwd: bool,
+        #[case] expected: Option<&str>,
+    ) -> Result<()> {
+        let timestamp = Utc.timestamp_millis_opt(1_684_315_800_000).unwrap();
+        let item = HistoryItem {
+            start_timestamp: has_timestamp.then_some(timestamp),
+            cwd: has_cwd.then(|| "/home/me".to_string()),
+            ..new_history_item("ls")
+        };
+        let history = FileBackedHistory::new(1)?;
+        let sut = HistoryCompleter::new(&history, '!')
+            .with_timestamp(show_timestamp)
+            .with_cwd(show_cwd);
+
+        let formatted = timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let expected = expected.map(|expected| expected.replace("{timestamp}", &formatted));
+        assert_eq!(
+            sut.create_suggestion("l", 1, item, None).description,
+            expected
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case('!', "match#", vec![])]
+    #[case('#', "match#", vec!["a match"])]
+    #[case('#', "match!", vec![])]
+    fn complete_uses_selection_char(
+        #[case] selection_char: char,
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(1)?;
+        history.save(new_history_item("a match"))?;
+        let mut sut = HistoryCompleter::new(&history, selection_char);
+        let actual: Vec<String> = sut
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(0, 2, vec!["d", "c"])]
+    #[case(1, 2, vec!["c", "b"])]
+    #[case(3, 2, vec!["a"])]
+    #[case(4, 2, vec![])]
+    fn page_returns_a_page(
+        #[case] start: usize,
+        #[case] offset: usize,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(4)?;
+        for history_item in ["a", "b", "c", "d"] {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!');
+        let actual: Vec<String> = sut
+            .page("", 0, start, offset)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("git pu", 3, vec!["gitk", "git push"], 0)]
+    #[case("git pu!", 6, vec!["git push"], 0)]
+    #[case("k", 4, vec!["gitk"], 3)]
+    fn complete_before_cursor(
+        #[case] line: &str,
+        #[case] pos: usize,
+        #[case] expected: Vec<&str>,
+        #[case] expected_start: usize,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(2)?;
+        history.save(new_history_item("git push"))?;
+        history.save(new_history_item("gitk"))?;
+        let mut sut = HistoryCompleter::new(&history, '!');
+        let suggestions = sut.complete(line, pos);
+        let actual: Vec<&str> = suggestions
+            .iter()
+            .map(|suggestion| suggestion.value.as_str())
+            .collect();
+        assert_eq!(actual, expected);
+        assert!(suggestions
+            .iter()
+            .all(|suggestion| suggestion.span == Span::new(expected_start, pos)));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("日本", "日本", 6, "日本語")]
+    #[case("é caf", "caf", 6, "é café")]
+    #[case("café au", "café", 5, "café au")]
+    fn complete_multibyte_replaces_on_boundaries(
+        #[case] buffer: &str,
+        #[case] line: &str,
+        #[case] pos: usize,
+        #[case] expected: &str,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(2)?;
+        history.save(new_history_item("café"))?;
+        history.save(new_history_item("日本語"))?;
+        let mut sut = HistoryCompleter::new(&history, '!');
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+
+        let suggestion = sut.complete(line, pos).into_iter().next();
+        menu_functions::replace_in_buffer(suggestion, &mut editor);
+
+        assert_eq!(editor.get_buffer(), expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("gco", "git checkout origin", Some(vec![0, 4, 9]))]
+    #[case("GCO", "git checkout origin", Some(vec![0, 4, 9]))]
+    #[case("ogc", "git checkout origin", None)]
+    #[case("", "anything", Some(vec![]))]
+    #[case("日語", "日本語", Some(vec![0, 6]))]
+    fn fuzzy_match_matches_subsequences(
+        #[case] pattern: &str,
+        #[case] candidate: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) {
+        assert_eq!(
+            fuzzy_match(pattern, candidate).map(|(_, indices)| indices),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(false, false, "check", None)]
+    #[case(false, true, "check", Some(vec![4, 5, 6, 7, 8]))]
+    #[case(false, true, "origin chec!", Some(vec![4, 5, 6, 7, 13, 14, 15, 16, 17, 18]))]
+    #[case(true, true, "gco", Some(vec![0, 4, 9]))]
+    fn complete_reports_match_indices(
+        #[case] fuzzy: bool,
+        #[case] highlight_matches: bool,
+        #[case] line: &str,
+        #[case] expected: Option<Vec<usize>>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        history.save(new_history_item("git checkout origin"))?;
+        let mut sut = HistoryCompleter::new(&history, '!')
+            .with_fuzzy(fuzzy)
+            .with_highlight_matches(highlight_matches);
+
+        let suggestions = sut.complete(line, line.len());
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].match_indices, expected);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("origin git", vec!["origin/main git", "git push origin main", "git fetch origin"])]
+    #[case("git push origin", vec!["git push origin main"])]
+    #[case("main  origin", vec!["origin/main git", "git push origin main"])]
+    #[case("git fetch", vec!["git fetch origin"])]
+    fn complete_all_tokens_ranked(#[case] line: &str, #[case] expected: Vec<&str>) -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        for history_item in [
+            "git push origin main",
+            "origin/main git",
+            "git fetch origin",
+            "origin/main git",
+        ] {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!');
+        let actual: Vec<String> = sut
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions(line, line.len()), expected.len());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("gco", vec!["git checkout origin"])]
+    #[case("gc", vec!["gcc", "git checkout origin"])]
+    #[case("gp", vec!["git push"])]
+    #[case("", vec!["go", "gcc", "git checkout origin", "git push"])]
+    fn fuzzy_complete_ranks_without_duplicates(
+        #[case] line: &str,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        for history_item in [
+            "git checkout origin",
+            "git push",
+            "git checkout origin",
+            "gcc",
+            "go",
+        ] {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!').with_fuzzy(true);
+        let actual: Vec<String> = sut
+            .complete(line, line.len())
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions(line, line.len()), expected.len());
+        Ok(())
+    }
+
+    #[test]
+    fn fuzzy_complete_follows_the_direction_among_ties() -> Result<()> {
+        let mut history = FileBackedHistory::new(5)?;
+        for history_item in ["ga", "gb"] {
+            history.save(new_history_item(history_item))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!')
+            .with_fuzzy(true)
+            .with_direction(SearchDirection::Forward);
+        let actual: Vec<String> = sut
+            .complete("g", 1)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, vec!["ga", "gb"]);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn max_results_caps_large_history(#[case] fuzzy: bool) -> Result<()> {
+        let mut history = FileBackedHistory::new(5000)?;
+        for i in 0..5000 {
+            history.save(new_history_item(&format!("command {i}")))?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!')
+            .with_fuzzy(fuzzy)
+            .with_max_results(Some(200));
+
+        let suggestions = sut.complete("command", 7);
+        assert_eq!(suggestions.len(), 200);
+        assert_eq!(suggestions[0].value, "command 4999");
+        assert_eq!(sut.total_completions("command", 7), 200);
+        assert_eq!(sut.page("command", 7, 190, 20).len(), 10);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(Some(1), vec!["cargo build", "cargo test", "ls"])]
+    #[case(Some(100), vec!["cargo test", "cargo build", "ls"])]
+    fn frecency_ranks_frequent_entries(
+        #[case] half_life: Option<usize>,
+        #[case] expected: Vec<&str>,
+    ) {
+        let history = RecordedHistory(vec![
+            "cargo test",
+            "cargo test",
+            "cargo test",
+            "ls",
+            "cargo test",
+            "cargo build",
+        ]);
+        let mut sut = HistoryCompleter::new(&history, '!').with_frecency(half_life);
+        let actual: Vec<String> = sut
+            .complete("", 0)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions("", 0), expected.len());
+    }
+
+    /// Keeps repeated command lines, unlike the [`FileBackedHistory`]
+    struct RecordedHistory(Vec<&'static str>);
+
+    impl History for RecordedHistory {
+        fn save(&mut self, _h: HistoryItem) -> Result<HistoryItem> {
+            unimplemented!()
+        }
+
+        fn load(&self, _id: HistoryItemId) -> Result<HistoryItem> {
+            unimplemented!()
+        }
+
+        fn count(&self, query: SearchQuery) -> Result<i64> {
+            Ok(self.search(query)?.len() as i64)
+        }
+
+        fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+            let contains = match query.filter.command_line {
+                Some(CommandLineSearch::Substring(contains)) => contains,
+                _ => String::new(),
+            };
+            Ok(self
+                .0
+                .iter()
+                .rev()
+                .filter(|command_line| command_line.contains(&contains))
+                .map(|command_line| new_history_item(command_line))
+                .collect())
+        }
+
+        fn update(
+            &mut self,
+            _id: HistoryItemId,
+            _updater: &dyn Fn(HistoryItem) -> HistoryItem,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            unimplemented!()
+        }
+
+        fn delete(&mut self, _h: HistoryItemId) -> Result<()> {
+            unimplemented!()
+        }
+
+        fn sync(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn session(&self) -> Option<HistorySessionId> {
+            None
+        }
+    }
+
+    struct FailingHistory;
+
+    impl History for FailingHistory {
+        fn save(&mut self, _h: HistoryItem) -> Result<HistoryItem> {
+            Err(history_error())
+        }
+
+        fn load(&self, _id: HistoryItemId) -> Result<HistoryItem> {
+            Err(history_error())
+        }
+
+        fn count(&self, _query: SearchQuery) -> Result<i64> {
+            Err(history_error())
+        }
+
+        fn search(&self, _query: SearchQuery) -> Result<Vec<HistoryItem>> {
+            Err(history_error())
+        }
+
+        fn update(
+            &mut self,
+            _id: HistoryItemId,
+            _updater: &dyn Fn(HistoryItem) -> HistoryItem,
+        ) -> Result<()> {
+            Err(history_error())
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            Err(history_error())
+        }
+
+        fn delete(&mut self, _h: HistoryItemId) -> Result<()> {
+            Err(history_error())
+        }
+
+        fn sync(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn session(&self) -> Option<HistorySessionId> {
+            None
+        }
+    }
+
+    fn history_error() -> ReedlineError {
+        ReedlineError(ReedlineErrorVariants::OtherHistoryError("unavailable"))
+    }
+
+    #[test]
+    fn failing_history_gives_no_completions() {
+        let history = FailingHistory;
+        let mut sut = HistoryCompleter::new(&history, '!');
+
+        assert_eq!(sut.complete("any", 3), vec![]);
+        assert_eq!(sut.total_completions("any", 3), 0);
+    }
+
+    #[rstest]
+    #[case("git", Some("git "))]
+    #[case("push", Some("git push"))]
+    #[case("commit", None)]
+    #[case("git ", None)]
+    #[case("none", None)]
+    #[case("", None)]
+    fn partial_complete_to_common_prefix(#[case] line: &str, #[case] expected: Option<&str>) {
+        let mut history = FileBackedHistory::new(3).unwrap();
+        for command_line in ["git commit", "svn commit", "git push"] {
+            history
+                .save(HistoryItem::from_command_line(command_line))
+                .unwrap();
+        }
+        let mut sut = HistoryCompleter::new(&history, '!');
+
+        let suggestions = sut.partial_complete(line, line.len(), 0, 0);
+
+        let expected = expected.map(|value| Suggestion {
+            value: value.to_string(),
+            span: Span::new(0, line.len()),
+            ..Suggestion::default()
+        });
+        assert_eq!(suggestions, expected.into_iter().collect::<Vec<_>>());
+    }
+
+    /// Only answers its searches and counts right away while `ready`, like a slow history
+    struct SlowHistory {
+        history: FileBackedHistory,
+        ready: std::cell::Cell<bool>,
+    }
+
+    impl History for SlowHistory {
+        fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+            self.history.save(h)
+        }
+
+        fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+            self.history.load(id)
+        }
+
+        fn count(&self, query: SearchQuery) -> Result<i64> {
+            self.history.count(query)
+        }
+
+        fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+            self.history.search(query)
+        }
+
+        fn try_search(&self, query: SearchQuery) -> Result<Option<Vec<HistoryItem>>> {
+            self.ready.get().then(|| self.search(query)).transpose()
+        }
+
+        fn try_count(&self, query: SearchQuery) -> Result<Option<i64>> {
+            self.ready.get().then(|| self.count(query)).transpose()
+        }
+
+        fn update(
+            &mut self,
+            id: HistoryItemId,
+            updater: &dyn Fn(HistoryItem) -> HistoryItem,
+        ) -> Result<()> {
+            self.history.update(id, updater)
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.history.clear()
+        }
+
+        fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+            self.history.delete(h)
+        }
+
+        fn sync(&mut self) -> std::io::Result<()> {
+            self.history.sync()
+        }
+
+        fn session(&self) -> Option<HistorySessionId> {
+            self.history.session()
+        }
+    }
+
+    #[test]
+    fn slow_history_gives_the_last_completions() {
+        let mut history = SlowHistory {
+            history: FileBackedHistory::default(),
+            ready: std::cell::Cell::new(true),
+        };
+        for command_line in ["ls", "cd foo"] {
+            history
+                .save(HistoryItem::from_command_line(command_line))
+                .unwrap();
+        }
+        let mut sut =
+            HistoryCompleter::new(&history, '!').with_last_results(LastHistoryResults::default());
+        let values = |suggestions: Vec<Suggestion>| {
+            suggestions
+                .into_iter()
+                .map(|suggestion| suggestion.value)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(sut.complete("ls", 2)), vec!["ls"]);
+        assert_eq!(sut.total_completions("ls", 2), 1);
+
+        history.ready.set(false);
+        assert_eq!(values(sut.complete("cd", 2)), vec!["ls"]);
+        assert_eq!(sut.total_completions("cd", 2), 1);
+
+        history.ready.set(true);
+        assert_eq!(values(sut.complete("cd", 2)), vec!["cd foo"]);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[rstest]
+    #[case(SessionFilter::All, vec!["ls -a", "ls -l", "ls"])]
+    #[case(SessionFilter::Only, vec!["ls -a", "ls"])]
+    #[case(SessionFilter::Others, vec!["ls -l"])]
+    fn complete_filters_sessions(
+        #[case] session_filter: SessionFilter,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = SqliteBackedHistory::with_file(
+            dir.path().join("history.db"),
+            Some(HistorySessionId::new(1)),
+            None,
+        )?;
+        for (session, command_line) in [(1, "ls"), (2, "ls -l"), (1, "ls -a")] {
+            history.save(HistoryItem {
+                session_id: Some(HistorySessionId::new(session)),
+                ..new_history_item(command_line)
+            })?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!').with_session_filter(session_filter);
+
+        let actual: Vec<String> = sut
+            .complete("ls", 2)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions("ls", 2), expected.len());
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[rstest]
+    #[case(None, vec!["ls -a", "ls -l", "ls"])]
+    #[case(Some("/home/me"), vec!["ls -l", "ls"])]
+    #[case(Some("/home/me/project"), vec!["ls -l"])]
+    fn complete_filters_cwd(
+        #[case] cwd_scope: Option<&str>,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        for (cwd, command_line) in [
+            (Some("/home/me"), "ls"),
+            (Some("/home/me/project"), "ls -l"),
+            (None, "ls -a"),
+        ] {
+            history.save(HistoryItem {
+                cwd: cwd.map(str::to_string),
+                ..new_history_item(command_line)
+            })?;
+        }
+        let mut sut =
+            HistoryCompleter::new(&history, '!').with_cwd_scope(cwd_scope.map(str::to_string));
+
+        let actual: Vec<String> = sut
+            .complete("ls", 2)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions("ls", 2), expected.len());
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[rstest]
+    #[case(None, false, vec!["make", "make test", "make run"])]
+    #[case(Some(true), false, vec!["make test"])]
+    #[case(Some(false), false, vec!["make run"])]
+    #[case(Some(true), true, vec!["make", "make test"])]
+    fn complete_filters_exit_status(
+        #[case] exit_successful: Option<bool>,
+        #[case] keep_unknown_exit_status: bool,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        for (exit_status, command_line) in [
+            (Some(1), "make run"),
+            (Some(0), "make test"),
+            (None, "make"),
+        ] {
+            history.save(HistoryItem {
+                exit_status,
+                ..new_history_item(command_line)
+            })?;
+        }
+        let mut sut = HistoryCompleter::new(&history, '!')
+            .with_exit_successful(exit_successful)
+            .with_unknown_exit_status(keep_unknown_exit_status);
+
+        let actual: Vec<String> = sut
+            .complete("make", 4)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions("make", 4), expected.len());
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[rstest]
+    #[case(None, vec!["ssh c", "ssh b", "ssh a"])]
+    #[case(Some("laptop"), vec!["ssh a"])]
+    #[case(Some("server"), vec!["ssh b"])]
+    fn complete_filters_hostname(
+        #[case] hostname: Option<&str>,
+        #[case] expected: Vec<&str>,
+    ) -> Result<()> {
+        let mut history = SqliteBackedHistory::in_memory()?;
+        for (hostname, command_line) in [
+            (Some("laptop"), "ssh a"),
+            (Some("server"), "ssh b"),
+            (None, "ssh c"),
+        ] {
+            history.save(HistoryItem {
+                hostname: hostname.map(str::to_string),
+                ..new_history_item(command_line)
+            })?;
+        }
+        let mut sut =
+            HistoryCompleter::new(&history, '!').with_hostname(hostname.map(str::to_string));
+
+        let actual: Vec<String> = sut
+            .complete("ssh", 3)
+            .into_iter()
+            .map(|suggestion| suggestion.value)
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(sut.total_completions("ssh", 3), expected.len());
+        Ok(())
+    }
 }
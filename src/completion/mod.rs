@@ -2,5 +2,5 @@ mod base;
 mod default;
 pub(crate) mod history;
 
-pub use base::{Completer, Span, Suggestion};
+pub use base::{Completer, HistoryMatch, Span, Suggestion};
 pub use default::DefaultCompleter;
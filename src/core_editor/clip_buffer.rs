@@ -1,11 +1,61 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+};
+use thiserror::Error;
+
+/// Failure to store content in a [`Clipboard`]
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[cfg(feature = "system_clipboard")]
+    /// The system clipboard rejected the content
+    #[error("system clipboard error: {0}")]
+    System(String),
+
+    #[cfg(feature = "osc52_clipboard")]
+    /// The encoded content is longer than the terminals accept, so it is only kept in the editor
+    #[error("terminal clipboard limit exceeded: {len} encoded bytes, at most {max_len}")]
+    TooLarge {
+        /// Length of the encoded content
+        len: usize,
+        /// Longest encoded content the terminals accept
+        max_len: usize,
+    },
+
+    /// I/O error while persisting the content
+    #[error("I/O error: {0}")]
+    IOError(#[from] std::io::Error),
+}
+
 /// Defines an interface to interact with a Clipboard for cut and paste.
 ///
 /// Mutable reference requirements are stricter than always necessary, but the currently used system clipboard API demands them for exclusive access.
 pub trait Clipboard: Send {
     fn set(&mut self, content: &str, mode: ClipboardMode);
 
+    /// Like `set`, but reporting if the content couldn't be stored
+    ///
+    /// Backends that can fail should override it and implement `set` on top of it.
+    fn try_set(&mut self, content: &str, mode: ClipboardMode) -> Result<(), ClipboardError> {
+        self.set(content, mode);
+        Ok(())
+    }
+
     fn get(&mut self) -> (String, ClipboardMode);
 
+    /// Borrow the content if the backend keeps it, sparing the copy made by `get`
+    fn get_ref(&self) -> Option<(&str, ClipboardMode)> {
+        None
+    }
+
+    /// The mode the content would be pasted with
+    ///
+    /// Backends keeping the content should override it to answer without copying it.
+    fn mode(&mut self) -> ClipboardMode {
+        self.get().1
+    }
+
     fn clear(&mut self) {
         self.set("", ClipboardMode::Normal);
     }
@@ -13,6 +63,13 @@ pub trait Clipboard: Send {
     fn len(&mut self) -> usize {
         self.get().0.len()
     }
+
+    /// Check if there is nothing to paste
+    ///
+    /// Backends should override it if they can answer without copying the content.
+    fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
 }
 
 /// Determines how the content in the clipboard should be inserted
@@ -23,6 +80,106 @@ pub enum ClipboardMode {
     Normal,
     /// As new lines below or above
     Lines,
+    /// As a rectangle, every line at the same column of the following lines
+    Block,
+}
+
+/// How text pasted in [`ClipboardMode::Normal`] or through the terminal's bracketed paste is
+/// cleaned of line breaks and other control characters, for hosts with single-line prompts or
+/// wary of pasted escape sequences
+///
+/// Linewise and blockwise pastes keep their line structure.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PasteSanitizer {
+    /// Paste the text as it is
+    #[default]
+    Keep,
+    /// Drop the line breaks and control characters, tabs excepted
+    Strip,
+    /// Replace every line break and control character, tabs excepted, with the character.
+    /// A `\r\n` line break counts as one
+    Replace(char),
+}
+
+impl PasteSanitizer {
+    /// The `text` cleaned according to the policy, borrowed if there is nothing to clean
+    pub fn sanitize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let replacement = match self {
+            PasteSanitizer::Keep => return Cow::Borrowed(text),
+            PasteSanitizer::Strip => None,
+            PasteSanitizer::Replace(replacement) => Some(*replacement),
+        };
+        let is_unsafe = |c: char| c.is_control() && c != '\t';
+        if !text.chars().any(is_unsafe) {
+            return Cow::Borrowed(text);
+        }
+
+        let mut sanitized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if !is_unsafe(c) {
+                sanitized.push(c);
+                continue;
+            }
+            if c == '\r' && chars.peek() == Some(&'\n') {
+                chars.next();
+            }
+            sanitized.extend(replacement);
+        }
+        Cow::Owned(sanitized)
+    }
+}
+
+/// Cut `content` down to at most `max_size` bytes, keeping it valid UTF-8
+fn truncate_to_size(content: &str, max_size: Option<usize>) -> &str {
+    match max_size {
+        Some(max_size) if content.len() > max_size => {
+            let mut end = max_size;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            &content[..end]
+        }
+        _ => content,
+    }
+}
+
+/// Remembers the mode of the last content yanked into a clipboard shared with other
+/// applications, so that content copied elsewhere is pasted in [`ClipboardMode::Normal`]
+///
+/// Only a hash of the content is kept. As `arboard` exposes no change counter, text copied
+/// elsewhere is detected by observing different content: the record is dropped then, and
+/// identical text copied elsewhere afterwards no longer inherits the mode.
+#[cfg(any(feature = "system_clipboard", test))]
+#[derive(Default)]
+struct YankTracker {
+    last_yank: Option<(u64, ClipboardMode)>,
+}
+
+#[cfg(any(feature = "system_clipboard", test))]
+impl YankTracker {
+    fn hash(content: &str) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Note that `content` was put into the clipboard by the editor
+    fn record(&mut self, content: &str, mode: ClipboardMode) {
+        self.last_yank = Some((Self::hash(content), mode));
+    }
+
+    /// The mode to paste the current clipboard `content` with
+    fn observe(&mut self, content: &str) -> ClipboardMode {
+        match self.last_yank {
+            Some((hash, mode)) if hash == Self::hash(content) => mode,
+            _ => {
+                self.last_yank = None;
+                ClipboardMode::Normal
+            }
+        }
+    }
 }
 
 /// Simple buffer that provides a clipboard only usable within the application/library.
@@ -48,6 +205,439 @@ impl Clipboard for LocalClipboard {
     fn get(&mut self) -> (String, ClipboardMode) {
         (self.content.clone(), self.mode)
     }
+
+    fn get_ref(&self) -> Option<(&str, ClipboardMode)> {
+        Some((&self.content, self.mode))
+    }
+
+    fn mode(&mut self) -> ClipboardMode {
+        self.mode
+    }
+
+    fn len(&mut self) -> usize {
+        self.content.len()
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.content.is_empty()
+    }
+}
+
+/// Local clipboard whose content survives restarts by being written to a file on every change.
+///
+/// File format: the mode (`normal`, `lines` or `block`) on the first line, followed by the content.
+/// A missing or malformed file starts the clipboard empty.
+pub struct FileBackedClipboard {
+    content: String,
+    mode: ClipboardMode,
+    file: PathBuf,
+}
+
+impl FileBackedClipboard {
+    /// Creates a clipboard restoring its content from `file` if it can be read
+    pub fn with_file(file: PathBuf) -> Self {
+        let (content, mode) = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|text| Self::decode(&text))
+            .unwrap_or_default();
+        Self {
+            content,
+            mode,
+            file,
+        }
+    }
+
+    fn encode(content: &str, mode: ClipboardMode) -> String {
+        let mode = match mode {
+            ClipboardMode::Normal => "normal",
+            ClipboardMode::Lines => "lines",
+            ClipboardMode::Block => "block",
+        };
+        format!("{mode}\n{content}")
+    }
+
+    fn decode(text: &str) -> Option<(String, ClipboardMode)> {
+        let (mode, content) = text.split_once('\n')?;
+        let mode = match mode {
+            "normal" => ClipboardMode::Normal,
+            "lines" => ClipboardMode::Lines,
+            "block" => ClipboardMode::Block,
+            _ => return None,
+        };
+        Some((content.to_owned(), mode))
+    }
+
+    /// Writes to a sibling temporary file first and renames it over the target,
+    /// so a concurrent reader or a crash never sees a partial write
+    fn persist(&self) -> std::io::Result<()> {
+        if let Some(base_dir) = self.file.parent() {
+            std::fs::create_dir_all(base_dir)?;
+        }
+        let mut temp_name = self.file.clone().into_os_string();
+        temp_name.push(".tmp");
+        let temp_file = PathBuf::from(temp_name);
+        std::fs::write(&temp_file, Self::encode(&self.content, self.mode))?;
+        std::fs::rename(&temp_file, &self.file)
+    }
+}
+
+impl Clipboard for FileBackedClipboard {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        let _res = self.try_set(content, mode);
+    }
+
+    fn try_set(&mut self, content: &str, mode: ClipboardMode) -> Result<(), ClipboardError> {
+        self.content = content.to_owned();
+        self.mode = mode;
+        Ok(self.persist()?)
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        (self.content.clone(), self.mode)
+    }
+
+    fn get_ref(&self) -> Option<(&str, ClipboardMode)> {
+        Some((&self.content, self.mode))
+    }
+
+    fn mode(&mut self) -> ClipboardMode {
+        self.mode
+    }
+
+    fn len(&mut self) -> usize {
+        self.content.len()
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.content.is_empty()
+    }
+}
+
+/// Setter called by a [`CallbackClipboard`] with every cut or copied content
+type SetCallback = Box<dyn FnMut(&str, ClipboardMode) + Send>;
+/// Getter called by a [`CallbackClipboard`] for the content to paste
+type GetCallback = Box<dyn FnMut() -> (String, ClipboardMode) + Send>;
+
+/// Clipboard handing its content to closures supplied by the application, to route cut and
+/// copied text through arbitrary code
+pub struct CallbackClipboard {
+    set: SetCallback,
+    get: GetCallback,
+}
+
+impl CallbackClipboard {
+    /// Creates a clipboard calling `set` with every cut or copied content and `get` for the
+    /// content to paste
+    pub fn new(
+        set: impl FnMut(&str, ClipboardMode) + Send + 'static,
+        get: impl FnMut() -> (String, ClipboardMode) + Send + 'static,
+    ) -> Self {
+        Self {
+            set: Box::new(set),
+            get: Box::new(get),
+        }
+    }
+}
+
+impl Clipboard for CallbackClipboard {
+    fn set(&mut self, content: &str, mode: ClipboardMode) {
+        (self.set)(content, mode);
+    }
+
+    fn get(&mut self) -> (String, ClipboardMode) {
+        (self.get)()
+    }
+}
+
+/// Default number of entries remembered by a [`KillRing`]
+pub const KILL_RING_SIZE: usize = 60;
+
+/// The most recently cut or copied entries, newest first, for Emacs style `yank-pop`
+pub struct KillRing {
+    entries: VecDeque<(String, ClipboardMode)>,
+    capacity: usize,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        Self::new(KILL_RING_SIZE)
+    }
+}
+
+impl KillRing {
+    /// Creates a ring keeping at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Add `content` as the newest entry, dropping the oldest one if the ring is full
+    ///
+    /// Empty content is ignored, so clearing a clipboard leaves the ring untouched.
+    pub fn push(&mut self, content: &str, mode: ClipboardMode) {
+        if content.is_empty() || self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((content.to_owned(), mode));
+    }
+
+    /// The `n`th newest entry, `0` being the latest
+    pub fn get_nth(&self, n: usize) -> Option<(&str, ClipboardMode)> {
+        self.entries
+            .get(n)
+            .map(|(content, mode)| (content.as_str(), *mode))
+    }
+
+    /// Change the number of kept entries, dropping the oldest ones that don't fit
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all entries
+    #[allow(dead_code)]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Vi-style registers layered on top of a [`Clipboard`]
+///
+/// The wrapped clipboard acts as the unnamed register and mirrors every cut or copy.
+/// Additionally copies go to `"0`, cuts rotate through the ring `"1`-`"9`, and
+/// `"a`-`"z` can be targeted explicitly with [`Registers::select`] (`"A`-`"Z` append).
+/// `"+` goes to the system clipboard, by default the wrapped clipboard itself, and `"*` to the
+/// primary selection, or the system clipboard where there is none.
+/// Every cut or copy is also pushed onto a [`KillRing`].
+pub struct Registers {
+    unnamed: Box<dyn Clipboard>,
+    primary_selection: Option<Box<dyn Clipboard>>,
+    // `None` while `"+` shares the unnamed clipboard
+    system_clipboard: Option<Box<dyn Clipboard>>,
+    kill_ring: KillRing,
+    registers: HashMap<char, (String, ClipboardMode)>,
+    selected: Option<char>,
+    last_error: Option<ClipboardError>,
+    max_size: Option<usize>,
+}
+
+impl Registers {
+    pub fn new(unnamed: Box<dyn Clipboard>) -> Self {
+        Self {
+            unnamed,
+            primary_selection: None,
+            system_clipboard: None,
+            kill_ring: KillRing::default(),
+            registers: HashMap::new(),
+            selected: None,
+            last_error: None,
+            max_size: None,
+        }
+    }
+
+    /// Also put every cut or copy into the `primary_selection`, if any
+    pub fn with_primary_selection(mut self, primary_selection: Option<Box<dyn Clipboard>>) -> Self {
+        self.primary_selection = primary_selection;
+        self
+    }
+
+    /// Back `"+` by `system_clipboard` instead of the unnamed clipboard
+    #[allow(dead_code)]
+    pub fn with_system_clipboard(mut self, system_clipboard: Box<dyn Clipboard>) -> Self {
+        self.system_clipboard = Some(system_clipboard);
+        self
+    }
+
+    /// Replace the clipboard backing the unnamed register, keeping all other content
+    ///
+    /// `"+` keeps the clipboard it shared with the unnamed register.
+    pub fn set_unnamed(&mut self, unnamed: Box<dyn Clipboard>) {
+        let previous = std::mem::replace(&mut self.unnamed, unnamed);
+        self.system_clipboard.get_or_insert(previous);
+    }
+
+    /// Truncate cut and copied content larger than `max_size` bytes before it is stored in any
+    /// register or clipboard, unlimited if `None` (the default)
+    pub fn set_max_size(&mut self, max_size: Option<usize>) {
+        self.max_size = max_size;
+    }
+
+    /// Entries previously cut or copied, newest first
+    pub fn kill_ring(&self) -> &KillRing {
+        &self.kill_ring
+    }
+
+    pub fn kill_ring_mut(&mut self) -> &mut KillRing {
+        &mut self.kill_ring
+    }
+
+    /// Check if `register` names a register that can be selected
+    pub fn is_valid_name(register: char) -> bool {
+        matches!(register, '"' | '+' | '*') || register.is_ascii_alphanumeric()
+    }
+
+    /// Target `register` with the next cut, copy or paste
+    pub fn select(&mut self, register: char) {
+        self.selected = Some(register).filter(|r| Self::is_valid_name(*r) && *r != '"');
+    }
+
+    /// Go back to using the unnamed register
+    pub fn deselect(&mut self) {
+        self.selected = None;
+    }
+
+    /// Store a copied `content` in the selected register or `"0`
+    pub fn copy(&mut self, content: &str, mode: ClipboardMode) {
+        let content = truncate_to_size(content, self.max_size);
+        match self.selected.take() {
+            Some(register) => self.write(register, content, mode),
+            None => {
+                self.registers.insert('0', (content.to_owned(), mode));
+            }
+        }
+        self.set_clipboards(content, mode);
+    }
+
+    /// Store a cut `content` in the selected register or push it onto the ring `"1`-`"9`
+    pub fn cut(&mut self, content: &str, mode: ClipboardMode) {
+        let content = truncate_to_size(content, self.max_size);
+        match self.selected.take() {
+            Some(register) => self.write(register, content, mode),
+            None => {
+                for i in (1..9).rev() {
+                    let from = char::from_digit(i, 10).expect("single digit");
+                    let to = char::from_digit(i + 1, 10).expect("single digit");
+                    if let Some(entry) = self.registers.remove(&from) {
+                        self.registers.insert(to, entry);
+                    }
+                }
+                self.registers.insert('1', (content.to_owned(), mode));
+            }
+        }
+        self.set_clipboards(content, mode);
+    }
+
+    /// Content of the selected register, or of the unnamed one if none is selected
+    pub fn get(&mut self) -> (String, ClipboardMode) {
+        match self.selected.take() {
+            Some(register @ ('+' | '*')) => self.clipboard(register).get(),
+            Some(register) => self
+                .registers
+                .get(&register.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default(),
+            None => self.unnamed.get(),
+        }
+    }
+
+    /// Check if the selected register, or the unnamed one if none is selected, holds nothing
+    /// to paste, keeping the selection
+    pub fn is_empty(&mut self) -> bool {
+        match self.selected {
+            Some(register @ ('+' | '*')) => self.clipboard(register).is_empty(),
+            Some(register) => self
+                .registers
+                .get(&register.to_ascii_lowercase())
+                .map_or(true, |(content, _)| content.is_empty()),
+            None => self.unnamed.is_empty(),
+        }
+    }
+
+    /// Mode the selected register, or the unnamed one if none is selected, would be pasted
+    /// with, keeping the selection
+    pub fn mode(&mut self) -> ClipboardMode {
+        match self.selected {
+            Some(register @ ('+' | '*')) => self.clipboard(register).mode(),
+            Some(register) => self
+                .registers
+                .get(&register.to_ascii_lowercase())
+                .map_or(ClipboardMode::Normal, |(_, mode)| *mode),
+            None => self.unnamed.mode(),
+        }
+    }
+
+    /// Like [`Registers::get`], but borrowing the content where possible
+    pub fn get_ref(&mut self) -> (Cow<'_, str>, ClipboardMode) {
+        match self.selected.take() {
+            Some(register @ ('+' | '*')) => {
+                let (content, mode) = self.clipboard(register).get();
+                (Cow::Owned(content), mode)
+            }
+            Some(register) => self.registers.get(&register.to_ascii_lowercase()).map_or(
+                (Cow::Borrowed(""), ClipboardMode::Normal),
+                |(content, mode)| (Cow::Borrowed(content), *mode),
+            ),
+            None => {
+                // Checked up front, as the borrow would otherwise outlive the fallback
+                if self.unnamed.get_ref().is_none() {
+                    let (content, mode) = self.unnamed.get();
+                    return (Cow::Owned(content), mode);
+                }
+                self.unnamed.get_ref().map_or(
+                    (Cow::Borrowed(""), ClipboardMode::Normal),
+                    |(content, mode)| (Cow::Borrowed(content), mode),
+                )
+            }
+        }
+    }
+
+    /// The error of the latest cut or copy that the unnamed clipboard failed to store
+    pub fn take_error(&mut self) -> Option<ClipboardError> {
+        self.last_error.take()
+    }
+
+    fn set_clipboards(&mut self, content: &str, mode: ClipboardMode) {
+        self.kill_ring.push(content, mode);
+        self.last_error = self.unnamed.try_set(content, mode).err();
+        if let Some(primary_selection) = &mut self.primary_selection {
+            primary_selection.set(content, mode);
+        }
+    }
+
+    /// The clipboard backing `"+` or `"*`
+    fn clipboard(&mut self, register: char) -> &mut Box<dyn Clipboard> {
+        match (
+            register,
+            &mut self.primary_selection,
+            &mut self.system_clipboard,
+        ) {
+            ('*', Some(primary_selection), _) => primary_selection,
+            (_, _, Some(system_clipboard)) => system_clipboard,
+            (_, _, None) => &mut self.unnamed,
+        }
+    }
+
+    fn write(&mut self, register: char, content: &str, mode: ClipboardMode) {
+        if matches!(register, '+' | '*') {
+            self.clipboard(register).set(content, mode);
+        } else if register.is_ascii_uppercase() {
+            let entry = self
+                .registers
+                .entry(register.to_ascii_lowercase())
+                .or_default();
+            entry.0.push_str(content);
+            let kept = truncate_to_size(&entry.0, self.max_size).len();
+            entry.0.truncate(kept);
+            if let ClipboardMode::Lines = mode {
+                entry.1 = mode;
+            }
+        } else {
+            self.registers.insert(register, (content.to_owned(), mode));
+        }
+    }
 }
 
 #[cfg(feature = "system_clipboard")]
@@ -60,7 +650,13 @@ pub use system_clipboard::SystemClipboard;
 /// accessed, it will default to [`LocalClipboard`].
 ///
 /// Disabled -> [`LocalClipboard`], which supports cutting and pasting limited to the [`crate::Reedline`] instance
+///
+/// Over SSH the `osc52_clipboard` feature takes precedence and returns an `Osc52Clipboard`.
 pub fn get_default_clipboard() -> Box<dyn Clipboard> {
+    #[cfg(feature = "osc52_clipboard")]
+    if Osc52Clipboard::is_remote_session() {
+        return Box::new(Osc52Clipboard::new());
+    }
     SystemClipboard::new().map_or_else(
         |_e| Box::new(LocalClipboard::new()) as Box<dyn Clipboard>,
         |cb| Box::new(cb),
@@ -74,10 +670,41 @@ pub fn get_default_clipboard() -> Box<dyn Clipboard> {
 /// accessed, it will default to [`LocalClipboard`].
 ///
 /// Disabled -> [`LocalClipboard`], which supports cutting and pasting limited to the [`crate::Reedline`] instance
+///
+/// Over SSH the `osc52_clipboard` feature takes precedence and returns an `Osc52Clipboard`.
 pub fn get_default_clipboard() -> Box<dyn Clipboard> {
+    #[cfg(feature = "osc52_clipboard")]
+    if Osc52Clipboard::is_remote_session() {
+        return Box::new(Osc52Clipboard::new());
+    }
     Box::new(LocalClipboard::new())
 }
 
+#[cfg(feature = "system_clipboard")]
+pub use system_clipboard::PrimarySelectionClipboard;
+
+#[cfg(feature = "system_clipboard")]
+/// Helper to get the primary selection based on the `system_clipboard` feature flag:
+///
+/// Enabled -> [`PrimarySelectionClipboard`], which is only kept within the
+/// [`crate::Reedline`] instance on platforms without a primary selection.
+///
+/// Disabled -> `None`, as the [`LocalClipboard`] already keeps the content.
+pub fn get_primary_selection_clipboard() -> Option<Box<dyn Clipboard>> {
+    Some(Box::new(PrimarySelectionClipboard::new()))
+}
+
+#[cfg(not(feature = "system_clipboard"))]
+/// Helper to get the primary selection based on the `system_clipboard` feature flag:
+///
+/// Enabled -> `PrimarySelectionClipboard`, which is only kept within the
+/// [`crate::Reedline`] instance on platforms without a primary selection.
+///
+/// Disabled -> `None`, as the [`LocalClipboard`] already keeps the content.
+pub fn get_primary_selection_clipboard() -> Option<Box<dyn Clipboard>> {
+    None
+}
+
 #[cfg(feature = "system_clipboard")]
 mod system_clipboard {
     use super::*;
@@ -88,43 +715,337 @@ mod system_clipboard {
     /// Requires that the feature `system_clipboard` is enabled
     pub struct SystemClipboard {
         cb: Arboard,
-        local_copy: String,
-        mode: ClipboardMode,
+        yank: YankTracker,
     }
 
     impl SystemClipboard {
         pub fn new() -> Result<Self, arboard::Error> {
             Ok(SystemClipboard {
                 cb: Arboard::new()?,
+                yank: YankTracker::default(),
+            })
+        }
+    }
+
+    impl Clipboard for SystemClipboard {
+        fn set(&mut self, content: &str, mode: ClipboardMode) {
+            let _ = self.try_set(content, mode);
+        }
+
+        fn try_set(&mut self, content: &str, mode: ClipboardMode) -> Result<(), ClipboardError> {
+            self.yank.record(content, mode);
+            self.cb
+                .set_text(content)
+                .map_err(|err| ClipboardError::System(err.to_string()))
+        }
+
+        fn get(&mut self) -> (String, ClipboardMode) {
+            let system_content = self.cb.get_text().unwrap_or_default();
+            // Content copied by other applications defaults to direct insertion
+            let mode = self.yank.observe(&system_content);
+            (system_content, mode)
+        }
+
+        fn mode(&mut self) -> ClipboardMode {
+            // The content has to be fetched to tell if it still is the last yank
+            let system_content = self.cb.get_text().unwrap_or_default();
+            self.yank.observe(&system_content)
+        }
+
+        fn is_empty(&mut self) -> bool {
+            // The system clipboard can only be asked for its whole text, but at least the
+            // comparison with the last yank is skipped
+            self.cb.get_text().map_or(true, |text| text.is_empty())
+        }
+    }
+
+    /// The primary selection of X11 and Wayland, pasted with a middle click
+    ///
+    /// Where there is no primary selection, or it can't be accessed, the content is only kept
+    /// like in a [`LocalClipboard`].
+    ///
+    /// Requires that the feature `system_clipboard` is enabled
+    pub struct PrimarySelectionClipboard {
+        selection: PrimarySelection,
+    }
+
+    enum PrimarySelection {
+        System { cb: Arboard, yank: YankTracker },
+        Local(LocalClipboard),
+    }
+
+    impl PrimarySelectionClipboard {
+        pub fn new() -> Self {
+            let selection = match Arboard::new() {
+                Ok(cb) if primary::is_supported() => PrimarySelection::System {
+                    cb,
+                    yank: YankTracker::default(),
+                },
+                _ => PrimarySelection::Local(LocalClipboard::new()),
+            };
+            PrimarySelectionClipboard { selection }
+        }
+    }
+
+    impl Default for PrimarySelectionClipboard {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Clipboard for PrimarySelectionClipboard {
+        fn set(&mut self, content: &str, mode: ClipboardMode) {
+            match &mut self.selection {
+                PrimarySelection::System { cb, yank } => {
+                    yank.record(content, mode);
+                    primary::set_text(cb, content);
+                }
+                PrimarySelection::Local(local) => local.set(content, mode),
+            }
+        }
+
+        fn get(&mut self) -> (String, ClipboardMode) {
+            match &mut self.selection {
+                PrimarySelection::System { cb, yank } => {
+                    let system_content = primary::get_text(cb).unwrap_or_default();
+                    // Text selected in other applications defaults to direct insertion
+                    let mode = yank.observe(&system_content);
+                    (system_content, mode)
+                }
+                PrimarySelection::Local(local) => local.get(),
+            }
+        }
+
+        fn get_ref(&self) -> Option<(&str, ClipboardMode)> {
+            match &self.selection {
+                PrimarySelection::System { .. } => None,
+                PrimarySelection::Local(local) => local.get_ref(),
+            }
+        }
+
+        fn mode(&mut self) -> ClipboardMode {
+            match &mut self.selection {
+                PrimarySelection::System { .. } => self.get().1,
+                PrimarySelection::Local(local) => local.mode(),
+            }
+        }
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    mod primary {
+        use arboard::{Clipboard as Arboard, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+        pub fn is_supported() -> bool {
+            true
+        }
+
+        pub fn set_text(cb: &mut Arboard, content: &str) {
+            let _ = cb
+                .set()
+                .clipboard(LinuxClipboardKind::Primary)
+                .text(content);
+        }
+
+        pub fn get_text(cb: &mut Arboard) -> Option<String> {
+            cb.get().clipboard(LinuxClipboardKind::Primary).text().ok()
+        }
+    }
+
+    #[cfg(not(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    )))]
+    mod primary {
+        use arboard::Clipboard as Arboard;
+
+        pub fn is_supported() -> bool {
+            false
+        }
+
+        pub fn set_text(_cb: &mut Arboard, _content: &str) {}
+
+        pub fn get_text(_cb: &mut Arboard) -> Option<String> {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "osc52_clipboard")]
+pub use osc52_clipboard::Osc52Clipboard;
+
+#[cfg(feature = "osc52_clipboard")]
+mod osc52_clipboard {
+    use super::*;
+    use std::io::Write;
+
+    /// Longest encoded content sent to the terminal, as accepted by xterm. Longer copies
+    /// only stay available within the editor.
+    const MAX_LEN: usize = 100_000;
+    /// Screen passes at most 768 bytes per escape sequence through, so the sequence is sent
+    /// in pieces
+    const SCREEN_CHUNK_LEN: usize = 76;
+
+    /// Terminal multiplexer the escape sequences have to be passed through
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub(super) enum Multiplexer {
+        None,
+        Tmux,
+        Screen,
+    }
+
+    /// Copies to the clipboard of the terminal emulator with the OSC 52 escape sequence
+    ///
+    /// Works over SSH as the terminal on the local machine receives the sequence. As reading
+    /// the clipboard that way is rarely supported, pasting uses the last copy in the editor.
+    /// The sequence goes to stderr like the painted prompt, so it reaches the terminal even
+    /// when stdout is redirected.
+    ///
+    /// Requires that the feature `osc52_clipboard` is enabled
+    pub struct Osc52Clipboard {
+        local_copy: String,
+        mode: ClipboardMode,
+        multiplexer: Multiplexer,
+        terminal: Box<dyn Write + Send>,
+    }
+
+    impl Osc52Clipboard {
+        pub fn new() -> Self {
+            let multiplexer = if std::env::var_os("TMUX").is_some() {
+                Multiplexer::Tmux
+            } else if std::env::var_os("STY").is_some() {
+                Multiplexer::Screen
+            } else {
+                Multiplexer::None
+            };
+            Self::with_multiplexer(multiplexer)
+        }
+
+        pub(super) fn with_multiplexer(multiplexer: Multiplexer) -> Self {
+            Self::with_terminal(multiplexer, Box::new(std::io::stderr()))
+        }
+
+        /// Send the escape sequences to `terminal` instead of stderr
+        pub(super) fn with_terminal(
+            multiplexer: Multiplexer,
+            terminal: Box<dyn Write + Send>,
+        ) -> Self {
+            Osc52Clipboard {
                 local_copy: String::new(),
                 mode: ClipboardMode::Normal,
+                multiplexer,
+                terminal,
+            }
+        }
+
+        /// Check if the editor runs in an SSH session
+        pub fn is_remote_session() -> bool {
+            std::env::var_os("SSH_TTY").is_some() || std::env::var_os("SSH_CONNECTION").is_some()
+        }
+
+        /// The escape sequence setting the clipboard to `content`, failing if it is too long
+        pub(super) fn sequence(&self, content: &str) -> Result<String, ClipboardError> {
+            let encoded = base64_encode(content.as_bytes());
+            if encoded.len() > MAX_LEN {
+                return Err(ClipboardError::TooLarge {
+                    len: encoded.len(),
+                    max_len: MAX_LEN,
+                });
+            }
+            let osc = format!("\x1b]52;c;{encoded}\x07");
+            Ok(match self.multiplexer {
+                Multiplexer::None => osc,
+                Multiplexer::Tmux => {
+                    let escaped = osc.replace('\x1b', "\x1b\x1b");
+                    format!("\x1bPtmux;{escaped}\x1b\\")
+                }
+                Multiplexer::Screen => osc
+                    .as_bytes()
+                    .chunks(SCREEN_CHUNK_LEN)
+                    .map(|chunk| format!("\x1bP{}\x1b\\", String::from_utf8_lossy(chunk)))
+                    .collect(),
             })
         }
     }
 
-    impl Clipboard for SystemClipboard {
+    impl Default for Osc52Clipboard {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Clipboard for Osc52Clipboard {
         fn set(&mut self, content: &str, mode: ClipboardMode) {
+            let _ = self.try_set(content, mode);
+        }
+
+        /// Keeps the copy in the editor even if the terminal can't be sent it
+        fn try_set(&mut self, content: &str, mode: ClipboardMode) -> Result<(), ClipboardError> {
             self.local_copy = content.to_owned();
-            let _ = self.cb.set_text(content);
             self.mode = mode;
+            let sequence = self.sequence(content)?;
+            self.terminal.write_all(sequence.as_bytes())?;
+            self.terminal.flush()?;
+            Ok(())
         }
 
         fn get(&mut self) -> (String, ClipboardMode) {
-            let system_content = self.cb.get_text().unwrap_or_default();
-            if system_content == self.local_copy {
-                // We assume the content was yanked inside the line editor and the last yank determined the mode.
-                (system_content, self.mode)
-            } else {
-                // Content has changed, default to direct insertion.
-                (system_content, ClipboardMode::Normal)
+            (self.local_copy.clone(), self.mode)
+        }
+
+        fn get_ref(&self) -> Option<(&str, ClipboardMode)> {
+            Some((&self.local_copy, self.mode))
+        }
+
+        fn mode(&mut self) -> ClipboardMode {
+            self.mode
+        }
+
+        fn len(&mut self) -> usize {
+            self.local_copy.len()
+        }
+
+        fn is_empty(&mut self) -> bool {
+            self.local_copy.is_empty()
+        }
+    }
+
+    /// Standard base64 with padding
+    pub(super) fn base64_encode(input: &[u8]) -> String {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::with_capacity((input.len() + 2) / 3 * 4);
+        for chunk in input.chunks(3) {
+            let bytes = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let triple = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
             }
         }
+        encoded
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_default_clipboard, ClipboardMode};
+    use super::{
+        get_default_clipboard, CallbackClipboard, Clipboard, ClipboardError, ClipboardMode,
+        FileBackedClipboard, KillRing, LocalClipboard, Registers, YankTracker,
+    };
+    use std::{
+        borrow::Cow,
+        sync::{Arc, Mutex},
+    };
     #[test]
     fn reads_back() {
         let mut cb = get_default_clipboard();
@@ -134,12 +1055,416 @@ mod tests {
         // Actual test
         cb.set("test", ClipboardMode::Normal);
         assert_eq!(cb.len(), 4);
+        assert!(!cb.is_empty());
         assert_eq!(cb.get().0, "test".to_owned());
         cb.clear();
         assert_eq!(cb.get().0, String::new());
+        assert!(cb.is_empty());
 
         // Restore!
 
         cb.set(&previous_state, ClipboardMode::Normal);
     }
+
+    #[test]
+    fn file_backed_clipboard_persists_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("nested").join("clipboard");
+
+        let mut cb = FileBackedClipboard::with_file(file.clone());
+        assert!(cb.is_empty());
+        cb.set("first\nsecond", ClipboardMode::Lines);
+
+        let mut restored = FileBackedClipboard::with_file(file.clone());
+        let (content, mode) = restored.get();
+        assert_eq!(content, "first\nsecond");
+        assert!(matches!(mode, ClipboardMode::Lines));
+        assert!(!file.with_file_name("clipboard.tmp").exists());
+    }
+
+    #[test]
+    fn mode_matches_content() {
+        let mut local = LocalClipboard::new();
+        assert!(matches!(local.mode(), ClipboardMode::Normal));
+        local.set("line\n", ClipboardMode::Lines);
+        assert!(matches!(local.mode(), ClipboardMode::Lines));
+
+        // Backends without an override fall back to `get`
+        let mut callback =
+            CallbackClipboard::new(|_, _| {}, || ("block".to_owned(), ClipboardMode::Block));
+        assert!(matches!(callback.mode(), ClipboardMode::Block));
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    #[test]
+    fn system_clipboard_mode_of_own_yank() {
+        // Headless machines have no system clipboard to test against
+        let mut cb = match super::SystemClipboard::new() {
+            Ok(cb) => cb,
+            Err(_) => return,
+        };
+        cb.set("line\n", ClipboardMode::Lines);
+        assert!(matches!(cb.mode(), ClipboardMode::Lines));
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    #[test]
+    fn system_clipboard_mode_of_changed_content() {
+        let (mut cb, mut other_application) =
+            match (super::SystemClipboard::new(), super::SystemClipboard::new()) {
+                (Ok(cb), Ok(other_application)) => (cb, other_application),
+                _ => return,
+            };
+        cb.set("line\n", ClipboardMode::Lines);
+        other_application.set("other", ClipboardMode::Lines);
+        assert!(matches!(cb.mode(), ClipboardMode::Normal));
+    }
+
+    #[cfg(feature = "system_clipboard")]
+    #[test]
+    fn primary_selection_mode_of_own_yank() {
+        // Holds for the system primary selection and the local fallback alike
+        let mut cb = super::PrimarySelectionClipboard::new();
+        cb.set("line\n", ClipboardMode::Lines);
+        let (content, mode) = cb.get();
+        assert_eq!(content, "line\n");
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[test]
+    fn callback_clipboard_routes_content() {
+        use std::sync::{Arc, Mutex};
+
+        let yanks = Arc::new(Mutex::new(Vec::new()));
+        let mut cb = CallbackClipboard::new(
+            {
+                let yanks = Arc::clone(&yanks);
+                move |content, _mode| yanks.lock().unwrap().push(content.to_owned())
+            },
+            || ("pasted".to_owned(), ClipboardMode::Lines),
+        );
+        cb.set("first", ClipboardMode::Normal);
+        cb.set("second", ClipboardMode::Normal);
+
+        assert_eq!(*yanks.lock().unwrap(), vec!["first", "second"]);
+        let (content, mode) = cb.get();
+        assert_eq!(content, "pasted");
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[test]
+    fn file_backed_clipboard_ignores_malformed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("clipboard");
+        std::fs::write(&file, "no mode line").unwrap();
+        assert!(FileBackedClipboard::with_file(file.clone()).is_empty());
+
+        std::fs::write(&file, "unknown\ncontent").unwrap();
+        assert!(FileBackedClipboard::with_file(file).is_empty());
+    }
+
+    #[test]
+    fn yank_tracker_ignores_identical_external_copies() {
+        let mut tracker = YankTracker::default();
+        tracker.record("line\n", ClipboardMode::Lines);
+        assert!(matches!(tracker.observe("line\n"), ClipboardMode::Lines));
+        assert!(matches!(tracker.observe("line\n"), ClipboardMode::Lines));
+
+        // Another application copied something, then the same text again
+        assert!(matches!(tracker.observe("other"), ClipboardMode::Normal));
+        assert!(matches!(tracker.observe("line\n"), ClipboardMode::Normal));
+
+        tracker.record("line\n", ClipboardMode::Lines);
+        assert!(matches!(tracker.observe(""), ClipboardMode::Normal));
+        assert!(matches!(tracker.observe("line\n"), ClipboardMode::Normal));
+    }
+
+    #[test]
+    fn kill_ring_keeps_latest_entries() {
+        let mut ring = KillRing::new(2);
+        ring.push("first", ClipboardMode::Normal);
+        ring.push("", ClipboardMode::Normal);
+        ring.push("second", ClipboardMode::Lines);
+        ring.push("third", ClipboardMode::Normal);
+
+        assert_eq!(ring.len(), 2);
+        assert!(matches!(
+            ring.get_nth(0),
+            Some(("third", ClipboardMode::Normal))
+        ));
+        assert!(matches!(
+            ring.get_nth(1),
+            Some(("second", ClipboardMode::Lines))
+        ));
+        assert!(ring.get_nth(2).is_none());
+
+        ring.set_capacity(1);
+        assert_eq!(ring.len(), 1);
+        ring.clear();
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn registers_push_onto_kill_ring() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.cut("cut", ClipboardMode::Normal);
+        registers.select('a');
+        registers.copy("copied", ClipboardMode::Normal);
+        registers.unnamed.clear();
+
+        assert_eq!(registers.kill_ring().len(), 2);
+        assert!(matches!(
+            registers.kill_ring().get_nth(1),
+            Some(("cut", ClipboardMode::Normal))
+        ));
+    }
+
+    #[test]
+    fn registers_report_failed_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory can't be replaced by the clipboard file
+        let file = dir.path().join("clipboard");
+        std::fs::create_dir(&file).unwrap();
+        std::fs::write(file.join("occupied"), "").unwrap();
+
+        let mut registers = Registers::new(Box::new(FileBackedClipboard::with_file(file)));
+        registers.copy("content", ClipboardMode::Normal);
+        assert!(matches!(
+            registers.take_error(),
+            Some(ClipboardError::IOError(_))
+        ));
+        assert!(registers.take_error().is_none());
+        assert_eq!(registers.get().0, "content");
+
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.copy("content", ClipboardMode::Normal);
+        assert!(registers.take_error().is_none());
+    }
+
+    #[test]
+    fn registers_truncate_for_any_clipboard() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.set_max_size(Some(4));
+        // Installed after the limit was set, like a clipboard of a later builder
+        let yanks = Arc::new(Mutex::new(Vec::new()));
+        registers.set_unnamed(Box::new(CallbackClipboard::new(
+            {
+                let yanks = Arc::clone(&yanks);
+                move |content, _mode| yanks.lock().unwrap().push(content.to_owned())
+            },
+            || (String::new(), ClipboardMode::Normal),
+        )));
+
+        registers.copy("aé日本", ClipboardMode::Normal);
+        registers.select('a');
+        registers.copy("abc", ClipboardMode::Normal);
+        registers.select('A');
+        registers.cut("def", ClipboardMode::Normal);
+
+        assert_eq!(*yanks.lock().unwrap(), vec!["aé", "abc", "def"]);
+        registers.select('a');
+        assert_eq!(registers.get().0, "abcd");
+    }
+
+    #[test]
+    fn registers_keep_named_and_numbered_content() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+
+        registers.copy("yanked", ClipboardMode::Normal);
+        registers.cut("first", ClipboardMode::Normal);
+        registers.cut("second", ClipboardMode::Lines);
+        registers.select('a');
+        registers.copy("named", ClipboardMode::Normal);
+        registers.select('A');
+        registers.cut(" appended", ClipboardMode::Normal);
+
+        assert_eq!(registers.get().0, " appended");
+        registers.select('a');
+        assert_eq!(registers.get().0, "named appended");
+        registers.select('0');
+        assert_eq!(registers.get().0, "yanked");
+        registers.select('1');
+        assert_eq!(registers.get().0, "second");
+        registers.select('2');
+        assert_eq!(registers.get().0, "first");
+        registers.select('z');
+        assert_eq!(registers.get().0, "");
+    }
+
+    #[test]
+    fn registers_tell_the_mode_keeping_the_selection() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.select('a');
+        registers.copy("line\n", ClipboardMode::Lines);
+        registers.cut("cut", ClipboardMode::Normal);
+
+        assert!(matches!(registers.mode(), ClipboardMode::Normal));
+        registers.select('a');
+        assert!(matches!(registers.mode(), ClipboardMode::Lines));
+        assert_eq!(registers.get().0, "line\n");
+        registers.select('z');
+        assert!(matches!(registers.mode(), ClipboardMode::Normal));
+    }
+
+    #[test]
+    fn registers_borrow_local_content() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.select('a');
+        registers.copy("named", ClipboardMode::Normal);
+        registers.cut("cut", ClipboardMode::Lines);
+
+        let (content, mode) = registers.get_ref();
+        assert!(matches!(content, Cow::Borrowed("cut")));
+        assert!(matches!(mode, ClipboardMode::Lines));
+        registers.select('a');
+        assert!(matches!(registers.get_ref().0, Cow::Borrowed("named")));
+        registers.select('b');
+        assert!(matches!(registers.get_ref().0, Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn registers_mirror_to_primary_selection() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()))
+            .with_primary_selection(Some(Box::new(LocalClipboard::new())));
+
+        registers.copy("yanked", ClipboardMode::Normal);
+        assert_eq!(
+            registers.primary_selection.as_mut().unwrap().get().0,
+            "yanked"
+        );
+        registers.select('a');
+        registers.cut("cut", ClipboardMode::Lines);
+        assert_eq!(registers.primary_selection.as_mut().unwrap().get().0, "cut");
+        assert_eq!(registers.get().0, "cut");
+    }
+
+    #[test]
+    fn registers_route_plus_and_star_to_the_clipboards() {
+        let system_writes = Arc::new(Mutex::new(Vec::<String>::new()));
+        let system_clipboard = CallbackClipboard::new(
+            {
+                let system_writes = Arc::clone(&system_writes);
+                move |content, _mode| system_writes.lock().unwrap().push(content.to_owned())
+            },
+            || ("from the system".to_string(), ClipboardMode::Normal),
+        );
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()))
+            .with_system_clipboard(Box::new(system_clipboard));
+
+        registers.select('+');
+        registers.copy("yanked", ClipboardMode::Normal);
+        assert_eq!(*system_writes.lock().unwrap(), ["yanked"]);
+        registers.copy("unnamed", ClipboardMode::Normal);
+        assert_eq!(*system_writes.lock().unwrap(), ["yanked"]);
+        registers.select('+');
+        assert_eq!(registers.get().0, "from the system");
+        assert_eq!(registers.get().0, "unnamed");
+
+        // Without a primary selection `"*` is the system clipboard as well
+        registers.select('*');
+        registers.cut("cut", ClipboardMode::Lines);
+        assert_eq!(*system_writes.lock().unwrap(), ["yanked", "cut"]);
+
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()))
+            .with_primary_selection(Some(Box::new(LocalClipboard::new())));
+        registers.select('*');
+        registers.copy("selected", ClipboardMode::Normal);
+        registers.select('*');
+        assert!(matches!(registers.get_ref().0, Cow::Owned(content) if content == "selected"));
+        // Shares the unnamed clipboard, which is the system clipboard by default
+        registers.select('+');
+        assert_eq!(registers.get().0, "selected");
+        registers.select('+');
+        registers.copy("system", ClipboardMode::Lines);
+        let (content, mode) = registers.get();
+        assert_eq!(content, "system");
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[test]
+    fn registers_keep_plus_on_the_replaced_unnamed_clipboard() {
+        let mut registers = Registers::new(Box::new(LocalClipboard::new()));
+        registers.copy("system", ClipboardMode::Normal);
+        registers.set_unnamed(Box::new(LocalClipboard::new()));
+
+        registers.copy("unnamed", ClipboardMode::Normal);
+        registers.select('+');
+        assert_eq!(registers.get().0, "system");
+        assert_eq!(registers.get().0, "unnamed");
+    }
+
+    #[cfg(feature = "osc52_clipboard")]
+    #[test]
+    fn osc52_encodes_content() {
+        use super::osc52_clipboard::{base64_encode, Multiplexer};
+        use super::Osc52Clipboard;
+
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode("日本".as_bytes()), "5pel5pys");
+
+        let cb = Osc52Clipboard::with_multiplexer(Multiplexer::None);
+        assert_eq!(cb.sequence("foo").unwrap(), "\x1b]52;c;Zm9v\x07");
+        assert!(matches!(
+            cb.sequence(&"x".repeat(75_001)),
+            Err(ClipboardError::TooLarge {
+                len: 100_004,
+                max_len: 100_000
+            })
+        ));
+
+        let cb = Osc52Clipboard::with_multiplexer(Multiplexer::Tmux);
+        assert_eq!(
+            cb.sequence("foo").unwrap(),
+            "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\"
+        );
+
+        let cb = Osc52Clipboard::with_multiplexer(Multiplexer::Screen);
+        let sequence = cb.sequence(&"x".repeat(150)).unwrap();
+        let chunks: Vec<&str> = sequence.split_inclusive("\x1b\\").collect();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|chunk| chunk.starts_with("\x1bP")));
+        assert_eq!(
+            sequence.replace("\x1bP", "").replace("\x1b\\", ""),
+            format!(
+                "\x1b]52;c;{}\x07",
+                base64_encode("x".repeat(150).as_bytes())
+            )
+        );
+    }
+
+    #[cfg(feature = "osc52_clipboard")]
+    #[test]
+    fn osc52_writes_to_the_terminal() {
+        use super::osc52_clipboard::Multiplexer;
+        use super::Osc52Clipboard;
+
+        struct Terminal(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Terminal {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let mut cb = Osc52Clipboard::with_terminal(
+            Multiplexer::None,
+            Box::new(Terminal(Arc::clone(&written))),
+        );
+        cb.try_set("foo", ClipboardMode::Normal).unwrap();
+        assert_eq!(*written.lock().unwrap(), b"\x1b]52;c;Zm9v\x07");
+
+        assert!(matches!(
+            cb.try_set(&"x".repeat(75_001), ClipboardMode::Normal),
+            Err(ClipboardError::TooLarge { .. })
+        ));
+        assert_eq!(cb.get().0.len(), 75_001);
+        assert_eq!(written.lock().unwrap().len(), 12);
+    }
 }
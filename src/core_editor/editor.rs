@@ -1,6 +1,17 @@
-use super::{edit_stack::EditStack, Clipboard, ClipboardMode, LineBuffer};
-use crate::enums::{EditType, UndoBehavior};
-use crate::{core_editor::get_default_clipboard, EditCommand};
+use super::{
+    edit_stack::EditStack, Clipboard, ClipboardError, ClipboardMode, LineBuffer, PasteSanitizer,
+    Registers,
+};
+use crate::enums::{EditType, TextObject, TextObjectKind, UndoBehavior, WordCharacters};
+use crate::{
+    core_editor::{get_default_clipboard, get_primary_selection_clipboard},
+    EditCommand,
+};
+use std::{collections::HashMap, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Mark holding the cursor position before the last jump, also reachable as `` ` `` like in vim
+const JUMP_MARK: char = '\'';
 
 /// Stateful editor executing changes to the underlying [`LineBuffer`]
 ///
@@ -8,20 +19,45 @@ use crate::{core_editor::get_default_clipboard, EditCommand};
 /// the undo/redo history and has facilities for cut/copy/yank/paste
 pub struct Editor {
     line_buffer: LineBuffer,
-    cut_buffer: Box<dyn Clipboard>,
+    cut_buffer: Registers,
     edit_stack: EditStack<LineBuffer>,
     last_undo_behavior: UndoBehavior,
     selection_anchor: Option<usize>,
+    // the selection also covers the grapheme at its end, like vi's visual mode
+    inclusive_selection: bool,
+    marks: HashMap<char, usize>,
+    // graphemes replaced by consecutive `OverwriteChar`, `None` where it appended instead
+    overwritten: Vec<Option<String>>,
+    // text inserted by the last paste and the kill ring entry it came from, for `YankPop`
+    last_yank: Option<(Range<usize>, usize)>,
+    // cleans the text pasted in `ClipboardMode::Normal`
+    paste_sanitizer: PasteSanitizer,
+    // open undo group, and whether an edit in it has created its undo point yet
+    undo_group: Option<bool>,
+    // a text object to cut wasn't found since the flag was last taken
+    missed_text_object: bool,
+    // applied to every line buffer swapped in, by undo or from the history
+    word_characters: WordCharacters,
 }
 
 impl Default for Editor {
     fn default() -> Self {
         Editor {
             line_buffer: LineBuffer::new(),
-            cut_buffer: get_default_clipboard(),
+            // `"+` shares the default clipboard, so it pastes the mode of the unnamed yanks
+            cut_buffer: Registers::new(get_default_clipboard())
+                .with_primary_selection(get_primary_selection_clipboard()),
             edit_stack: EditStack::new(),
             last_undo_behavior: UndoBehavior::CreateUndoPoint,
             selection_anchor: None,
+            inclusive_selection: false,
+            marks: HashMap::new(),
+            overwritten: Vec::new(),
+            last_yank: None,
+            paste_sanitizer: PasteSanitizer::Keep,
+            undo_group: None,
+            missed_text_object: false,
+            word_characters: WordCharacters::default(),
         }
     }
 }
@@ -36,10 +72,57 @@ impl Editor {
     /// [`UndoBehavior`] specifies how this change should be reflected on the undo stack.
     pub(crate) fn set_line_buffer(&mut self, line_buffer: LineBuffer, undo_behavior: UndoBehavior) {
         self.line_buffer = line_buffer;
+        self.line_buffer
+            .set_word_characters(self.word_characters.clone());
         self.update_undo_state(undo_behavior);
     }
 
+    /// Set the characters changing the word boundaries of the word motions and edits
+    pub(crate) fn set_word_characters(&mut self, word_characters: WordCharacters) {
+        self.line_buffer
+            .set_word_characters(word_characters.clone());
+        self.word_characters = word_characters;
+    }
+
+    /// Replace the clipboard backing the unnamed register
+    pub(crate) fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.cut_buffer.set_unnamed(clipboard);
+    }
+
+    /// Truncate cut and copied text larger than `max_size` bytes, whichever clipboard is set
+    pub(crate) fn set_clipboard_max_size(&mut self, max_size: Option<usize>) {
+        self.cut_buffer.set_max_size(max_size);
+    }
+
+    /// Set how the text pasted in [`ClipboardMode::Normal`] is cleaned
+    pub(crate) fn set_paste_sanitizer(&mut self, paste_sanitizer: PasteSanitizer) {
+        self.paste_sanitizer = paste_sanitizer;
+    }
+
+    /// Insert a bracketed paste from the terminal as one edit, cleaned like a clipboard paste
+    /// and with the terminal's remaining carriage returns as newlines
+    pub(crate) fn insert_pasted(&self, text: &str) -> EditCommand {
+        let text = self.paste_sanitizer.sanitize(text);
+        EditCommand::InsertString(text.replace("\r\n", "\n").replace('\r', "\n"))
+    }
+
+    /// The error of the latest cut or copy the clipboard failed to store, cleared by the call
+    pub(crate) fn take_clipboard_error(&mut self) -> Option<ClipboardError> {
+        self.cut_buffer.take_error()
+    }
+
+    /// Whether a text object to cut wasn't found in the buffer since the last call
+    pub(crate) fn take_missed_text_object(&mut self) -> bool {
+        std::mem::take(&mut self.missed_text_object)
+    }
+
+    /// Set how many cut or copied entries `YankPop` can cycle through
+    pub(crate) fn set_kill_ring_size(&mut self, size: usize) {
+        self.cut_buffer.kill_ring_mut().set_capacity(size);
+    }
+
     pub(crate) fn run_edit_command(&mut self, command: &EditCommand) {
+        let jump_start = Self::is_jump(command).then(|| self.insertion_point());
         match command {
             EditCommand::MoveToStart { select } => self.move_to_start(*select),
             EditCommand::MoveToLineStart { select } => self.move_to_line_start(*select),
@@ -48,6 +131,36 @@ impl Editor {
             EditCommand::MoveToPosition { position, select } => {
                 self.move_to_position(*position, *select)
             }
+            EditCommand::MoveCursorLineUp { select } => {
+                self.update_selection_anchor(*select);
+                self.line_buffer.move_line_up();
+            }
+            EditCommand::MoveCursorLineDown { select } => {
+                self.update_selection_anchor(*select);
+                self.line_buffer.move_line_down();
+            }
+            EditCommand::MoveSentenceRight { select } => {
+                self.move_to_position(self.line_buffer.sentence_right_index(), *select)
+            }
+            EditCommand::MoveSentenceLeft { select } => {
+                self.move_to_position(self.line_buffer.sentence_left_index(), *select)
+            }
+            EditCommand::MoveParagraphRight { select } => {
+                self.move_to_position(self.line_buffer.paragraph_right_index(), *select)
+            }
+            EditCommand::MoveParagraphLeft { select } => {
+                self.move_to_position(self.line_buffer.paragraph_left_index(), *select)
+            }
+            EditCommand::MoveToLine { line, select } => self.move_to_line(*line, *select),
+            EditCommand::MoveToMatchingBracket { select } => self.move_to_matching_bracket(*select),
+            EditCommand::MoveToMark { mark, select } => self.move_to_mark(*mark, false, *select),
+            EditCommand::MoveToMarkLine { mark, select } => self.move_to_mark(*mark, true, *select),
+            EditCommand::SelectRegister(register) => self.cut_buffer.select(*register),
+            EditCommand::InsertRegister(register) => self.insert_register(*register),
+            EditCommand::SetMark(mark) => {
+                self.marks
+                    .insert(Self::mark_name(*mark), self.insertion_point());
+            }
             EditCommand::MoveLeft { select } => self.move_left(*select),
             EditCommand::MoveRight { select } => self.move_right(*select),
             EditCommand::MoveWordLeft { select } => self.move_word_left(*select),
@@ -59,12 +172,20 @@ impl Editor {
             }
             EditCommand::MoveWordRightEnd { select } => self.move_word_right_end(*select),
             EditCommand::MoveBigWordRightEnd { select } => self.move_big_word_right_end(*select),
+            EditCommand::MoveWordLeftEnd { select } => self.move_word_left_end(*select),
+            EditCommand::MoveBigWordLeftEnd { select } => self.move_big_word_left_end(*select),
             EditCommand::InsertChar(c) => self.insert_char(*c),
             EditCommand::Complete => {}
             EditCommand::InsertString(str) => self.insert_str(str),
             EditCommand::InsertNewline => self.insert_newline(),
+            EditCommand::OpenLine { above } => self.line_buffer.open_line(*above),
+            EditCommand::DuplicateLine(count) => self.line_buffer.duplicate_line(*count),
+            EditCommand::JoinLines { smart } => self.line_buffer.join_lines(*smart),
             EditCommand::ReplaceChar(chr) => self.replace_char(*chr),
             EditCommand::ReplaceChars(n_chars, str) => self.replace_chars(*n_chars, str),
+            EditCommand::ReplaceCharsInLine { c, count } => self.replace_chars_in_line(*c, *count),
+            EditCommand::OverwriteChar(chr) => self.overwrite_char(*chr),
+            EditCommand::RestoreOverwrittenChar => self.restore_overwritten_char(),
             EditCommand::Backspace => self.backspace(),
             EditCommand::Delete => self.delete(),
             EditCommand::CutChar => self.cut_char(),
@@ -73,6 +194,7 @@ impl Editor {
             EditCommand::Clear => self.line_buffer.clear(),
             EditCommand::ClearToLineEnd => self.line_buffer.clear_to_line_end(),
             EditCommand::CutCurrentLine => self.cut_current_line(),
+            EditCommand::ChangeLine { keep_indent } => self.change_line(*keep_indent),
             EditCommand::CutFromStart => self.cut_from_start(),
             EditCommand::CutFromLineStart => self.cut_from_line_start(),
             EditCommand::CutToEnd => self.cut_from_end(),
@@ -85,14 +207,59 @@ impl Editor {
             EditCommand::CutBigWordRightToNext => self.cut_big_word_right_to_next(),
             EditCommand::PasteCutBufferBefore => self.insert_cut_buffer_before(),
             EditCommand::PasteCutBufferAfter => self.insert_cut_buffer_after(),
+            EditCommand::PasteCutBufferBeforeTimes(count) => {
+                self.insert_cut_buffer_times(*count, false);
+            }
+            EditCommand::PasteCutBufferAfterTimes(count) => {
+                self.insert_cut_buffer_times(*count, true);
+            }
+            EditCommand::YankPop => self.yank_pop(),
             EditCommand::UppercaseWord => self.line_buffer.uppercase_word(),
             EditCommand::LowercaseWord => self.line_buffer.lowercase_word(),
             EditCommand::SwitchcaseChar => self.line_buffer.switchcase_char(),
+            EditCommand::IndentLines(indent) => self.change_selected_lines(|line| {
+                if line.trim_end_matches(['\r', '\n']).is_empty() {
+                    line.to_string()
+                } else {
+                    format!("{indent}{line}")
+                }
+            }),
+            EditCommand::DedentLines(indent) => self.change_selected_lines(|line| {
+                let spaces = line.len() - line.trim_start_matches(' ').len();
+                line.strip_prefix(indent.as_str())
+                    .or_else(|| line.strip_prefix('\t'))
+                    .unwrap_or(&line[spaces.min(indent.len())..])
+                    .to_string()
+            }),
+            EditCommand::UppercaseSelection => self.change_case_selection(str::to_uppercase),
+            EditCommand::LowercaseSelection => self.change_case_selection(str::to_lowercase),
+            EditCommand::SwitchcaseSelection => self.change_case_selection(|text| {
+                text.chars()
+                    .flat_map(|c| {
+                        if c.is_uppercase() {
+                            c.to_lowercase().collect::<Vec<_>>()
+                        } else {
+                            c.to_uppercase().collect::<Vec<_>>()
+                        }
+                    })
+                    .collect()
+            }),
             EditCommand::CapitalizeChar => self.line_buffer.capitalize_char(),
+            EditCommand::UppercaseLine => self.line_buffer.uppercase_line(),
+            EditCommand::LowercaseLine => self.line_buffer.lowercase_line(),
+            EditCommand::CapitalizeLine => self.line_buffer.capitalize_line(),
             EditCommand::SwapWords => self.line_buffer.swap_words(),
             EditCommand::SwapGraphemes => self.line_buffer.swap_graphemes(),
+            EditCommand::MoveLineUp => self.line_buffer.swap_line_up(),
+            EditCommand::MoveLineDown => self.line_buffer.swap_line_down(),
+            EditCommand::TransposeChars => self.line_buffer.transpose_chars(),
+            EditCommand::TransposeWords => self.line_buffer.transpose_words(),
             EditCommand::Undo => self.undo(),
             EditCommand::Redo => self.redo(),
+            EditCommand::BeginUndoGroup => {
+                self.undo_group.get_or_insert(false);
+            }
+            EditCommand::EndUndoGroup => self.end_undo_group(),
             EditCommand::CutRightUntil(c) => self.cut_right_until_char(*c, false, true),
             EditCommand::CutRightBefore(c) => self.cut_right_until_char(*c, true, true),
             EditCommand::MoveRightUntil { c, select } => {
@@ -110,17 +277,97 @@ impl Editor {
                 self.move_left_until_char(*c, true, true, *select)
             }
             EditCommand::SelectAll => self.select_all(),
+            EditCommand::StartInclusiveSelection => {
+                self.update_selection_anchor(true);
+                self.inclusive_selection = true;
+            }
             EditCommand::CutSelection => self.cut_selection(),
             EditCommand::CopySelection => self.copy_selection(),
+            EditCommand::CutSelectedLines => self.cut_selected_lines(),
+            EditCommand::PasteCutBufferOverSelection => self.paste_over_selection(false),
+            EditCommand::PasteCutBufferOverSelectedLines => self.paste_over_selection(true),
+            EditCommand::CopySelectedLines => self.copy_selected_lines(),
+            EditCommand::YankSelection => self.yank_selection(false),
+            EditCommand::YankSelectedLines => self.yank_selection(true),
+            EditCommand::CopySelectedBlock => self.copy_selected_block(),
+            EditCommand::ClearSelection => self.selection_anchor = None,
+            EditCommand::CopyFromStart => self.copy_from_start(),
+            EditCommand::CopyFromLineStart => self.copy_from_line_start(),
+            EditCommand::CopyToEnd => self.copy_from_end(),
+            EditCommand::CopyToLineEnd => self.copy_to_line_end(),
+            EditCommand::CopyCurrentLine => self.copy_current_line(),
+            EditCommand::CopyLines(count) => self.copy_lines(*count),
+            EditCommand::CopyWordLeft => self.copy_word_left(),
+            EditCommand::CopyBigWordLeft => self.copy_big_word_left(),
+            EditCommand::CopyWordRight => self.copy_word_right(),
+            EditCommand::CopyBigWordRight => self.copy_big_word_right(),
+            EditCommand::CopyWordRightToNext => self.copy_word_right_to_next(),
+            EditCommand::CopyBigWordRightToNext => self.copy_big_word_right_to_next(),
+            EditCommand::CopyLeft => self.copy_left(),
+            EditCommand::CopyRight => self.copy_right(),
+            EditCommand::CutTextObject { text_object } => self.cut_text_object(*text_object),
+            EditCommand::CopyTextObject { text_object } => self.copy_text_object(*text_object),
+            EditCommand::SelectTextObject { text_object } => self.select_text_object(*text_object),
+            EditCommand::CutToMatchingBracket => self.cut_to_matching_bracket(),
+            EditCommand::CutIndentBlock(indent) => self.cut_indent_block(indent),
+            EditCommand::CutAll => self.cut_all(),
+            EditCommand::CopyAll => self.copy_all(),
+            EditCommand::CopyToMatchingBracket => self.copy_to_matching_bracket(),
+            EditCommand::CopyRightUntil(c) => self.copy_right_until_char(*c, false, true),
+            EditCommand::CopyRightBefore(c) => self.copy_right_until_char(*c, true, true),
+            EditCommand::CopyLeftUntil(c) => self.copy_left_until_char(*c, false, true),
+            EditCommand::CopyLeftBefore(c) => self.copy_left_until_char(*c, true, true),
+        }
+        if let Some(start) = jump_start.filter(|start| *start != self.insertion_point()) {
+            // Jumping back to the mark swaps it with the position left
+            self.marks.insert(JUMP_MARK, start);
         }
-        if !matches!(command.edit_type(), EditType::MoveCursor { select: true }) {
+        // The selection is kept for the command a register is selected for, or an undo group
+        // is opened for
+        if !matches!(command.edit_type(), EditType::MoveCursor { select: true })
+            && !matches!(
+                command,
+                EditCommand::SelectRegister(_)
+                    | EditCommand::BeginUndoGroup
+                    | EditCommand::EndUndoGroup
+            )
+        {
             self.selection_anchor = None;
         }
+        if self.selection_anchor.is_none() {
+            self.inclusive_selection = false;
+        }
+        if !matches!(
+            command,
+            EditCommand::OverwriteChar(_) | EditCommand::RestoreOverwrittenChar
+        ) {
+            self.overwritten.clear();
+        }
+        if !matches!(
+            command,
+            EditCommand::PasteCutBufferBefore | EditCommand::YankPop
+        ) {
+            self.last_yank = None;
+        }
+        if !matches!(command, EditCommand::SelectRegister(_)) {
+            // A register only applies to the command directly following its selection
+            self.cut_buffer.deselect();
+        }
         if let EditType::MoveCursor { select: true } = command.edit_type() {}
+        if command.edit_type() == EditType::NoOp {
+            // Copying leaves the buffer untouched, so there is nothing to undo
+            return;
+        }
+
+        if matches!(command.edit_type(), EditType::EditText | EditType::UndoRedo) {
+            self.shift_marks();
+        }
 
         let new_undo_behavior = match (command, command.edit_type()) {
             (_, EditType::MoveCursor { .. }) => UndoBehavior::MoveCursor,
-            (EditCommand::InsertChar(c), EditType::EditText) => UndoBehavior::InsertCharacter(*c),
+            (EditCommand::InsertChar(c) | EditCommand::OverwriteChar(c), EditType::EditText) => {
+                UndoBehavior::InsertCharacter(*c)
+            }
             (EditCommand::Delete, EditType::EditText) => {
                 let deleted_char = self.edit_stack.current().grapheme_right().chars().next();
                 UndoBehavior::Delete(deleted_char)
@@ -148,6 +395,155 @@ impl Editor {
         self.line_buffer.set_insertion_point(position)
     }
 
+    fn move_to_line(&mut self, line: usize, select: bool) {
+        self.update_selection_anchor(select);
+        self.line_buffer.move_to_line(line.saturating_sub(1));
+    }
+
+    fn move_to_matching_bracket(&mut self, select: bool) {
+        if let Some(position) = self.line_buffer.matching_bracket_index() {
+            self.update_selection_anchor(select);
+            self.line_buffer.set_insertion_point(position);
+        }
+    }
+
+    /// Range from the insertion point to the matching bracket, including both ends
+    fn matching_bracket_range(&self) -> Option<Range<usize>> {
+        let position = self.line_buffer.matching_bracket_index()?;
+        let insertion_point = self.insertion_point();
+        // Brackets are single byte characters
+        Some(if position >= insertion_point {
+            insertion_point..position + 1
+        } else {
+            position..self.line_buffer.grapheme_right_index()
+        })
+    }
+
+    fn cut_to_matching_bracket(&mut self) {
+        if let Some(range) = self.matching_bracket_range() {
+            let cut_slice = &self.line_buffer.get_buffer()[range.clone()];
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
+            self.line_buffer.set_insertion_point(range.start);
+            self.line_buffer.clear_range(range);
+        }
+    }
+
+    fn cut_indent_block(&mut self, indent: &str) {
+        let range = self.line_buffer.indent_block_range(indent);
+        let cut_slice = &self.line_buffer.get_buffer()[range.clone()];
+        if !cut_slice.is_empty() {
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Lines);
+            self.line_buffer.set_insertion_point(range.start);
+            self.line_buffer.clear_range(range);
+        }
+    }
+
+    /// The whole buffer as clipboard lines, ending with a line break. `None` if it is empty
+    fn all_lines(&self) -> Option<String> {
+        match self.line_buffer.get_buffer() {
+            "" => None,
+            buffer if buffer.ends_with('\n') => Some(buffer.to_string()),
+            buffer => Some(format!("{buffer}\n")),
+        }
+    }
+
+    fn cut_all(&mut self) {
+        if let Some(lines) = self.all_lines() {
+            self.cut_buffer.cut(&lines, ClipboardMode::Lines);
+            self.line_buffer.clear();
+        }
+    }
+
+    fn copy_all(&mut self) {
+        if let Some(lines) = self.all_lines() {
+            self.cut_buffer.copy(&lines, ClipboardMode::Lines);
+            self.line_buffer.move_to_start();
+        }
+    }
+
+    fn copy_to_matching_bracket(&mut self) {
+        if let Some(range) = self.matching_bracket_range() {
+            self.copy_range(range, ClipboardMode::Normal);
+        }
+    }
+
+    /// Whether the command moves the cursor far enough to be remembered in the
+    /// [`JUMP_MARK`], like vim's jumps
+    fn is_jump(command: &EditCommand) -> bool {
+        matches!(
+            command,
+            EditCommand::MoveToStart { .. }
+                | EditCommand::MoveToEnd { .. }
+                | EditCommand::MoveToLine { .. }
+                | EditCommand::MoveToMatchingBracket { .. }
+                | EditCommand::MoveToMark { .. }
+                | EditCommand::MoveToMarkLine { .. }
+        )
+    }
+
+    fn mark_name(mark: char) -> char {
+        if mark == '`' {
+            JUMP_MARK
+        } else {
+            mark
+        }
+    }
+
+    fn move_to_mark(&mut self, mark: char, line_start: bool, select: bool) {
+        // Jumping to an unset mark (or one invalidated by replacing the buffer) does nothing,
+        // except that marks past the end of a shorter buffer stay usable at its end
+        if let Some(&position) = self.marks.get(&Self::mark_name(mark)) {
+            let position = position.min(self.get_buffer().len());
+            if self.get_buffer().is_char_boundary(position) {
+                self.update_selection_anchor(select);
+                self.line_buffer.set_insertion_point(position);
+                if line_start {
+                    self.line_buffer.move_to_line_start();
+                }
+            }
+        }
+    }
+
+    /// Keep the marks on the same text after an edit by shifting the marks
+    /// behind the changed region and collapsing those inside it to its start
+    fn shift_marks(&mut self) {
+        if self.marks.is_empty() {
+            return;
+        }
+        let old = self.edit_stack.current().get_buffer();
+        let new = self.line_buffer.get_buffer();
+
+        let prefix = old
+            .char_indices()
+            .zip(new.chars())
+            .find(|((_, old_c), new_c)| old_c != new_c)
+            .map_or(old.len().min(new.len()), |((i, _), _)| i);
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let suffix = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(old_c, new_c)| old_c == new_c)
+            .map(|(c, _)| c.len_utf8())
+            .scan(0, |total, len| {
+                *total += len;
+                Some(*total)
+            })
+            .take_while(|total| *total <= max_suffix)
+            .last()
+            .unwrap_or(0);
+
+        let old_end = old.len() - suffix;
+        let new_end = new.len() - suffix;
+        for position in self.marks.values_mut() {
+            if *position >= old_end {
+                *position = *position - old_end + new_end;
+            } else if *position > prefix {
+                *position = prefix;
+            }
+        }
+    }
+
     pub(crate) fn move_line_up(&mut self) {
         self.line_buffer.move_line_up();
         self.update_undo_state(UndoBehavior::MoveCursor);
@@ -201,6 +597,7 @@ impl Editor {
 
     pub(crate) fn reset_undo_stack(&mut self) {
         self.edit_stack.reset();
+        self.undo_group = None;
     }
 
     pub(crate) fn move_to_start(&mut self, select: bool) {
@@ -224,13 +621,26 @@ impl Editor {
     }
 
     fn undo(&mut self) {
+        self.undo_group = None;
         let val = self.edit_stack.undo();
         self.line_buffer = val.clone();
+        self.line_buffer
+            .set_word_characters(self.word_characters.clone());
     }
 
     fn redo(&mut self) {
+        self.undo_group = None;
         let val = self.edit_stack.redo();
         self.line_buffer = val.clone();
+        self.line_buffer
+            .set_word_characters(self.word_characters.clone());
+    }
+
+    fn end_undo_group(&mut self) {
+        if self.undo_group.take() == Some(true) {
+            // The next edit starts its own undo point instead of joining the group's
+            self.last_undo_behavior = UndoBehavior::CreateUndoPoint;
+        }
     }
 
     pub(crate) fn update_undo_state(&mut self, undo_behavior: UndoBehavior) {
@@ -238,28 +648,87 @@ impl Editor {
             self.last_undo_behavior = UndoBehavior::UndoRedo;
             return;
         }
-        if !undo_behavior.create_undo_point_after(&self.last_undo_behavior) {
+        if matches!(undo_behavior, UndoBehavior::MoveCursor)
+            && matches!(self.last_undo_behavior, UndoBehavior::UndoRedo)
+        {
+            // Moving around after an undo keeps the undone edits for redo
+            return;
+        }
+        let create_undo_point = match self.undo_group {
+            // Every further edit of the group changes its undo point
+            Some(true) => false,
+            Some(false) if !matches!(undo_behavior, UndoBehavior::MoveCursor) => {
+                self.undo_group = Some(true);
+                true
+            }
+            _ => undo_behavior.create_undo_point_after(&self.last_undo_behavior),
+        };
+        if !create_undo_point {
             self.edit_stack.undo();
         }
         self.edit_stack.insert(self.line_buffer.clone());
         self.last_undo_behavior = undo_behavior;
     }
 
+    /// Cut the current line including its line break and move to the first non-blank of the
+    /// line taking its place, like vim `dd`
+    ///
+    /// The last line takes the line break in front of it instead, so no empty line remains.
     fn cut_current_line(&mut self) {
-        let deletion_range = self.line_buffer.current_line_range();
+        let line_range = self.line_buffer.current_line_range();
+        let buffer = self.line_buffer.get_buffer();
+        if buffer.is_empty() {
+            return;
+        }
+        let line = &buffer[line_range.clone()];
+        let (deletion_range, cut_line) = if line.ends_with('\n') {
+            (line_range, line.to_string())
+        } else {
+            let before = &buffer[..line_range.start];
+            let line_break = if before.ends_with("\r\n") {
+                2
+            } else {
+                usize::from(before.ends_with('\n'))
+            };
+            (
+                line_range.start - line_break..line_range.end,
+                format!("{line}\n"),
+            )
+        };
 
-        let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
-        if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Lines);
-            self.line_buffer.set_insertion_point(deletion_range.start);
-            self.line_buffer.clear_range(deletion_range);
+        self.cut_buffer.cut(&cut_line, ClipboardMode::Lines);
+        self.line_buffer.clear_range(deletion_range.clone());
+        self.line_buffer.set_insertion_point(deletion_range.start);
+        self.line_buffer.move_to_line_start();
+        let line_start = self.line_buffer.insertion_point();
+        let rest = &self.line_buffer.get_buffer()[line_start..];
+        let indent = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        self.line_buffer.set_insertion_point(line_start + indent);
+    }
+
+    /// Cut the text of the current line as a whole line, leaving its indentation if
+    /// `keep_indent`, and move behind what remains
+    fn change_line(&mut self, keep_indent: bool) {
+        let line_start = self.line_buffer.current_line_range().start;
+        let line_end = self.line_buffer.find_current_line_end();
+        let line = &self.line_buffer.get_buffer()[line_start..line_end];
+        let indent = if keep_indent {
+            line.len() - line.trim_start_matches([' ', '\t']).len()
+        } else {
+            0
+        };
+        if !line.is_empty() {
+            self.cut_buffer
+                .cut(&format!("{line}\n"), ClipboardMode::Lines);
         }
+        self.line_buffer.clear_range(line_start + indent..line_end);
+        self.line_buffer.set_insertion_point(line_start + indent);
     }
 
     fn cut_from_start(&mut self) {
         let insertion_offset = self.line_buffer.insertion_point();
         if insertion_offset > 0 {
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[..insertion_offset],
                 ClipboardMode::Normal,
             );
@@ -273,7 +742,7 @@ impl Editor {
         let deletion_range = self.line_buffer.insertion_point()..previous_offset;
         let cut_slice = &self.line_buffer.get_buffer()[deletion_range.clone()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_range(deletion_range);
         }
     }
@@ -281,7 +750,7 @@ impl Editor {
     fn cut_from_end(&mut self) {
         let cut_slice = &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_to_end();
         }
     }
@@ -290,7 +759,7 @@ impl Editor {
         let cut_slice = &self.line_buffer.get_buffer()
             [self.line_buffer.insertion_point()..self.line_buffer.find_current_line_end()];
         if !cut_slice.is_empty() {
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_to_line_end();
         }
     }
@@ -300,7 +769,7 @@ impl Editor {
         let left_index = self.line_buffer.word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -314,7 +783,7 @@ impl Editor {
         let left_index = self.line_buffer.big_word_left_index();
         if left_index < insertion_offset {
             let cut_range = left_index..insertion_offset;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -328,7 +797,7 @@ impl Editor {
         let right_index = self.line_buffer.word_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -341,7 +810,7 @@ impl Editor {
         let right_index = self.line_buffer.next_whitespace();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -354,7 +823,7 @@ impl Editor {
         let right_index = self.line_buffer.word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -367,7 +836,7 @@ impl Editor {
         let right_index = self.line_buffer.big_word_right_start_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -380,7 +849,7 @@ impl Editor {
         let right_index = self.line_buffer.grapheme_right_index();
         if right_index > insertion_offset {
             let cut_range = insertion_offset..right_index;
-            self.cut_buffer.set(
+            self.cut_buffer.cut(
                 &self.line_buffer.get_buffer()[cut_range.clone()],
                 ClipboardMode::Normal,
             );
@@ -388,41 +857,236 @@ impl Editor {
         }
     }
 
+    /// Check if the register to paste from is empty, in which case the paste is dropped
+    /// along with the register selected for it, leaving the selected text in place like vim
+    fn nothing_to_paste(&mut self) -> bool {
+        let empty = self.cut_buffer.is_empty();
+        if empty {
+            self.cut_buffer.deselect();
+        }
+        empty
+    }
+
     fn insert_cut_buffer_before(&mut self) {
+        if self.nothing_to_paste() {
+            return;
+        }
+        self.delete_selection();
+        // The content stays borrowed from a local clipboard where it can, as pastes can be
+        // large, but line-wise pastes need their own copy
+        match self.cut_buffer.mode() {
+            ClipboardMode::Normal => {
+                let start = self.line_buffer.insertion_point();
+                let (content, _) = self.cut_buffer.get_ref();
+                self.line_buffer
+                    .insert_str(&self.paste_sanitizer.sanitize(&content));
+                self.last_yank = Some((start..self.line_buffer.insertion_point(), 0));
+            }
+            ClipboardMode::Lines => {
+                let (content, _) = self.cut_buffer.get();
+                self.insert_lines(&content, false);
+            }
+            ClipboardMode::Block => {
+                let (content, _) = self.cut_buffer.get_ref();
+                Self::insert_block(&mut self.line_buffer, &content, false);
+            }
+        }
+    }
+
+    /// Paste the cut buffer `count` times after the cursor or in front of it, a count below
+    /// two pasting once
+    ///
+    /// Like vim, the cursor ends on the last pasted character, at the first non-blank
+    /// character of the first pasted line or at the start of the pasted block.
+    fn insert_cut_buffer_times(&mut self, count: usize, after: bool) {
+        if count < 2 {
+            if after {
+                self.insert_cut_buffer_after();
+            } else {
+                self.insert_cut_buffer_before();
+            }
+            return;
+        }
+        if self.nothing_to_paste() {
+            return;
+        }
         self.delete_selection();
-        match self.cut_buffer.get() {
+        match self.cut_buffer.get_ref() {
             (content, ClipboardMode::Normal) => {
-                self.line_buffer.insert_str(&content);
+                if after {
+                    self.line_buffer.move_right();
+                }
+                self.line_buffer
+                    .insert_str(&self.paste_sanitizer.sanitize(&content).repeat(count));
+                self.line_buffer.move_left();
             }
-            (mut content, ClipboardMode::Lines) => {
-                // TODO: Simplify that?
-                self.line_buffer.move_to_line_start();
-                self.line_buffer.move_line_up();
-                if !content.ends_with('\n') {
-                    // TODO: Make sure platform requirements are met
-                    content.push('\n');
+            (content, ClipboardMode::Lines) => {
+                let mut line = content.to_string();
+                if !line.ends_with('\n') {
+                    line.push('\n');
                 }
-                self.line_buffer.insert_str(&content);
+                self.insert_lines(&line.repeat(count), after);
+            }
+            (content, ClipboardMode::Block) => {
+                // The copies of the block sit side by side
+                let width = content
+                    .lines()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0);
+                let block = content
+                    .lines()
+                    .map(|line| {
+                        let padded = format!("{line:width$}");
+                        padded.repeat(count - 1) + line
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Self::insert_block(&mut self.line_buffer, &block, after);
             }
         }
     }
 
+    /// Replace the text inserted by the preceding paste or `YankPop` with the next older
+    /// entry of the kill ring, wrapping around after the oldest one
+    fn yank_pop(&mut self) {
+        if let Some((range, n)) = self.last_yank.take() {
+            let ring = self.cut_buffer.kill_ring();
+            if ring.is_empty() {
+                return;
+            }
+            let next = (n + 1) % ring.len();
+            if let Some((content, _)) = ring.get_nth(next) {
+                let content = self.paste_sanitizer.sanitize(content);
+                self.line_buffer.replace_range(range.clone(), &content);
+                let end = range.start + content.len();
+                self.line_buffer.set_insertion_point(end);
+                self.last_yank = Some((range.start..end, next));
+            }
+        }
+    }
+
+    /// Insert the lines of the block `content` at the same column of the current and the
+    /// following lines, after the cursor or at it
+    ///
+    /// Lines too short to reach the column are padded with spaces and missing lines are
+    /// appended. Block lines followed by text are padded to the width of the block.
+    fn insert_block(line_buffer: &mut LineBuffer, content: &str, after: bool) {
+        let buffer = line_buffer.get_buffer();
+        let insertion_point = line_buffer.insertion_point();
+        let row = line_buffer.line();
+        let line_start = buffer[..insertion_point].rfind('\n').map_or(0, |i| i + 1);
+        let mut column = buffer[line_start..insertion_point].chars().count();
+        if after
+            && buffer[insertion_point..]
+                .chars()
+                .next()
+                .map_or(false, |c| c != '\n')
+        {
+            column += 1;
+        }
+
+        let width = content
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<String> = buffer.split('\n').map(String::from).collect();
+        for (i, block_line) in content.lines().enumerate() {
+            if row + i >= lines.len() {
+                lines.push(String::new());
+            }
+            let line = &mut lines[row + i];
+            let line_width = line.chars().count();
+            if line_width < column {
+                line.extend(std::iter::repeat(' ').take(column - line_width));
+            }
+            let offset = line
+                .char_indices()
+                .nth(column)
+                .map_or(line.len(), |(offset, _)| offset);
+            let mut block_line = block_line.to_string();
+            if offset < line.len() {
+                let block_line_width = block_line.chars().count();
+                block_line.extend(std::iter::repeat(' ').take(width - block_line_width));
+            }
+            line.insert_str(offset, &block_line);
+        }
+
+        let block_start = lines[..row]
+            .iter()
+            .map(|line| line.len() + 1)
+            .sum::<usize>()
+            + lines[row]
+                .char_indices()
+                .nth(column)
+                .map_or(lines[row].len(), |(offset, _)| offset);
+        line_buffer.set_buffer(lines.join("\n"));
+        line_buffer.set_insertion_point(block_start);
+    }
+
+    fn insert_register(&mut self, register: char) {
+        self.cut_buffer.select(register);
+        if self.nothing_to_paste() {
+            return;
+        }
+        let (content, _) = self.cut_buffer.get();
+        // Line-wise content is inserted inline as well
+        self.delete_selection();
+        self.line_buffer.insert_str(&content);
+    }
+
+    /// Insert whole `lines` above the current line, or below it `after`, and move to the first
+    /// non-blank of the first inserted line
+    fn insert_lines(&mut self, lines: &str, after: bool) {
+        let mut lines = lines.to_string();
+        if !lines.ends_with('\n') {
+            // TODO: Make sure platform requirements are met
+            lines.push('\n');
+        }
+        let line_range = self.line_buffer.current_line_range();
+        let is_last_line = !self.line_buffer.get_buffer()[line_range.clone()].ends_with('\n');
+        let start = if !after {
+            self.line_buffer.set_insertion_point(line_range.start);
+            self.line_buffer.insert_str(&lines);
+            line_range.start
+        } else if is_last_line {
+            // Below the last line the line break goes in front of the inserted lines
+            self.line_buffer.set_insertion_point(line_range.end);
+            self.line_buffer.insert_char('\n');
+            self.line_buffer
+                .insert_str(lines.strip_suffix('\n').unwrap_or(&lines));
+            line_range.end + 1
+        } else {
+            self.line_buffer.set_insertion_point(line_range.end);
+            self.line_buffer.insert_str(&lines);
+            line_range.end
+        };
+        let indent = lines.len() - lines.trim_start_matches([' ', '\t']).len();
+        self.line_buffer.set_insertion_point(start + indent);
+    }
+
     fn insert_cut_buffer_after(&mut self) {
+        if self.nothing_to_paste() {
+            return;
+        }
         self.delete_selection();
-        match self.cut_buffer.get() {
-            (content, ClipboardMode::Normal) => {
+        // The content stays borrowed from a local clipboard where it can, as pastes can be
+        // large, but line-wise pastes need their own copy
+        match self.cut_buffer.mode() {
+            ClipboardMode::Normal => {
                 self.line_buffer.move_right();
-                self.line_buffer.insert_str(&content);
+                let (content, _) = self.cut_buffer.get_ref();
+                self.line_buffer
+                    .insert_str(&self.paste_sanitizer.sanitize(&content));
             }
-            (mut content, ClipboardMode::Lines) => {
-                // TODO: Simplify that?
-                self.line_buffer.move_to_line_start();
-                self.line_buffer.move_line_down();
-                if !content.ends_with('\n') {
-                    // TODO: Make sure platform requirements are met
-                    content.push('\n');
-                }
-                self.line_buffer.insert_str(&content);
+            ClipboardMode::Lines => {
+                let (content, _) = self.cut_buffer.get();
+                self.insert_lines(&content, true);
+            }
+            ClipboardMode::Block => {
+                let (content, _) = self.cut_buffer.get_ref();
+                Self::insert_block(&mut self.line_buffer, &content, true);
             }
         }
     }
@@ -466,7 +1130,7 @@ impl Editor {
                 &self.line_buffer.get_buffer()[self.line_buffer.insertion_point()..index + extra];
 
             if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+                self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
 
                 if before_char {
                     self.line_buffer.delete_right_before_char(c, current_line);
@@ -486,7 +1150,7 @@ impl Editor {
                 &self.line_buffer.get_buffer()[index + extra..self.line_buffer.insertion_point()];
 
             if !cut_slice.is_empty() {
-                self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+                self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
 
                 if before_char {
                     self.line_buffer.delete_left_before_char(c, current_line);
@@ -511,6 +1175,47 @@ impl Editor {
         self.line_buffer.insert_str(string);
     }
 
+    fn replace_chars_in_line(&mut self, character: char, count: usize) {
+        let start = self.insertion_point();
+        let line_end = self.line_buffer.find_current_line_end();
+        // Like vim, nothing is replaced if the line is too short
+        let end = self.line_buffer.get_buffer()[start..line_end]
+            .grapheme_indices(true)
+            .nth(count.saturating_sub(1))
+            .map(|(offset, grapheme)| start + offset + grapheme.len());
+        if let Some(end) = end.filter(|_| count > 0) {
+            let replacement = character.to_string().repeat(count);
+            self.line_buffer.replace_range(start..end, &replacement);
+            self.line_buffer
+                .set_insertion_point(start + replacement.len());
+        }
+    }
+
+    fn overwrite_char(&mut self, character: char) {
+        let replaced = self.line_buffer.grapheme_right();
+        if replaced.is_empty() || replaced.starts_with(['\n', '\r']) {
+            self.overwritten.push(None);
+            self.line_buffer.insert_char(character);
+        } else {
+            self.overwritten.push(Some(replaced.to_string()));
+            self.replace_char(character);
+        }
+    }
+
+    fn restore_overwritten_char(&mut self) {
+        match self.overwritten.pop() {
+            Some(Some(original)) => {
+                self.line_buffer.move_left();
+                self.line_buffer.delete_right_grapheme();
+                self.line_buffer.insert_str(&original);
+                self.line_buffer.move_left();
+            }
+            Some(None) => self.line_buffer.delete_left_grapheme(),
+            // Like vim, backspacing past the start of the replacement only moves the cursor
+            None => self.line_buffer.move_left(),
+        }
+    }
+
     fn move_left(&mut self, select: bool) {
         self.update_selection_anchor(select);
         self.line_buffer.move_left();
@@ -529,7 +1234,7 @@ impl Editor {
     fn cut_selection(&mut self) {
         if let Some((start, end)) = self.get_selection() {
             let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Normal);
             self.line_buffer.clear_range_safe(start, end);
             self.selection_anchor = None;
         }
@@ -538,120 +1243,930 @@ impl Editor {
     fn copy_selection(&mut self) {
         if let Some((start, end)) = self.get_selection() {
             let cut_slice = &self.line_buffer.get_buffer()[start..end];
-            self.cut_buffer.set(cut_slice, ClipboardMode::Normal);
+            self.cut_buffer.copy(cut_slice, ClipboardMode::Normal);
         }
     }
 
-    /// If a selection is active returns the selected range, otherwise None.
-    /// The range is guaranteed to be ascending.
-    pub fn get_selection(&self) -> Option<(usize, usize)> {
-        self.selection_anchor.map(|selection_anchor| {
-            if self.insertion_point() > selection_anchor {
-                (selection_anchor, self.insertion_point())
-            } else {
-                (self.insertion_point(), selection_anchor)
-            }
+    fn change_case_selection(&mut self, change: impl Fn(&str) -> String) {
+        if let Some((start, end)) = self.get_selection() {
+            let changed = change(&self.line_buffer.get_buffer()[start..end]);
+            self.line_buffer.replace_range(start..end, &changed);
+            self.line_buffer.set_insertion_point(start);
+            self.selection_anchor = None;
+        }
+    }
+
+    /// Apply `change` to each line touched by the selection, or the current line without
+    /// one, and move to the first non-blank of the first line
+    fn change_selected_lines(&mut self, change: impl Fn(&str) -> String) {
+        let range = self
+            .selected_lines_range()
+            .unwrap_or_else(|| self.line_buffer.current_line_range());
+        let changed = self.line_buffer.get_buffer()[range.clone()]
+            .split_inclusive('\n')
+            .map(change)
+            .collect::<String>();
+        let indent = changed.len() - changed.trim_start_matches([' ', '\t']).len();
+        self.line_buffer.replace_range(range.clone(), &changed);
+        self.line_buffer.set_insertion_point(range.start + indent);
+        self.selection_anchor = None;
+    }
+
+    /// Range over all lines touched by the selection, including the newline
+    /// terminating the last of them
+    fn selected_lines_range(&self) -> Option<Range<usize>> {
+        self.get_selection().map(|(start, end)| {
+            let buffer = self.line_buffer.get_buffer();
+            let start = buffer[..start].rfind('\n').map_or(0, |offset| offset + 1);
+            let end = buffer[end..]
+                .find('\n')
+                .map_or_else(|| buffer.len(), |offset| end + offset + 1);
+            start..end
         })
     }
 
-    fn delete_selection(&mut self) {
-        if let Some((start, end)) = self.get_selection() {
-            self.line_buffer.clear_range_safe(start, end);
+    fn cut_selected_lines(&mut self) {
+        if let Some(range) = self.selected_lines_range() {
+            let cut_slice = &self.line_buffer.get_buffer()[range.clone()];
+            self.cut_buffer.cut(cut_slice, ClipboardMode::Lines);
+            self.line_buffer.set_insertion_point(range.start);
+            self.line_buffer.clear_range(range);
             self.selection_anchor = None;
         }
     }
 
-    fn backspace(&mut self) {
-        if self.selection_anchor.is_some() {
-            self.delete_selection();
+    /// Replace the selection, or the lines it touches if `lines`, with the cut buffer
+    ///
+    /// The content is taken before the replaced text is cut to the unnamed register, which
+    /// would otherwise be pasted back. Like vim, lines pasted over a part of a line go on
+    /// lines of their own, as does text pasted over whole lines
+    fn paste_over_selection(&mut self, lines: bool) {
+        let range = if lines {
+            self.selected_lines_range()
         } else {
-            self.line_buffer.delete_left_grapheme();
+            self.get_selection().map(|(start, end)| start..end)
+        };
+        let range = match range {
+            Some(range) => range,
+            None => return,
+        };
+        if self.nothing_to_paste() {
+            return;
+        }
+        let (content, mode) = self.cut_buffer.get();
+        let replaced = self.line_buffer.get_buffer()[range.clone()].to_string();
+        self.cut_buffer.cut(
+            &replaced,
+            if lines {
+                ClipboardMode::Lines
+            } else {
+                ClipboardMode::Normal
+            },
+        );
+        self.selection_anchor = None;
+        self.line_buffer.set_insertion_point(range.start);
+        self.line_buffer.clear_range(range.clone());
+
+        match (mode, lines) {
+            (ClipboardMode::Block, _) => {
+                Self::insert_block(&mut self.line_buffer, &content, false);
+            }
+            (ClipboardMode::Normal, false) => {
+                let content = self.paste_sanitizer.sanitize(&content);
+                self.line_buffer.insert_str(&content);
+                // On the last pasted character
+                if !content.is_empty() {
+                    self.line_buffer.move_left();
+                }
+            }
+            (_, lines) => {
+                let content = content.strip_suffix('\n').unwrap_or(&content);
+                let pasted = if lines {
+                    // The line break of the last replaced line is kept
+                    if replaced.ends_with('\n') {
+                        format!("{content}\n")
+                    } else {
+                        content.to_string()
+                    }
+                } else {
+                    // The replaced part of the line is split around the pasted lines
+                    format!("\n{content}\n")
+                };
+                self.line_buffer.insert_str(&pasted);
+                let start = if lines { range.start } else { range.start + 1 };
+                let indent = content.len() - content.trim_start_matches([' ', '\t']).len();
+                self.line_buffer.set_insertion_point(start + indent);
+            }
         }
     }
 
-    fn delete(&mut self) {
-        if self.selection_anchor.is_some() {
-            self.delete_selection();
-        } else {
-            self.line_buffer.delete_right_grapheme();
+    fn copy_selected_lines(&mut self) {
+        if let Some(range) = self.selected_lines_range() {
+            self.copy_range(range, ClipboardMode::Lines);
         }
     }
 
-    fn move_word_left(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_left_index(), select);
+    fn yank_selection(&mut self, lines: bool) {
+        if let Some(anchor) = self.selection_anchor {
+            if lines {
+                self.copy_selected_lines();
+            } else {
+                self.copy_selection();
+            }
+            self.line_buffer.set_insertion_point(anchor);
+        }
     }
 
-    fn move_big_word_left(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_left_index(), select);
+    fn copy_selected_block(&mut self) {
+        if let (Some((start, end)), Some(range)) =
+            (self.get_selection(), self.selected_lines_range())
+        {
+            let buffer = self.line_buffer.get_buffer();
+            let column = |offset: usize| {
+                buffer[..offset]
+                    .rsplit('\n')
+                    .next()
+                    .map_or(0, |line| line.chars().count())
+            };
+            let left = column(start).min(column(end));
+            let right = column(start).max(column(end));
+            let lines = &buffer[range];
+            let block = lines
+                .strip_suffix('\n')
+                .unwrap_or(lines)
+                .split('\n')
+                .map(|line| {
+                    line.chars()
+                        .skip(left)
+                        .take(right - left)
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.cut_buffer.copy(&block, ClipboardMode::Block);
+        }
     }
 
-    fn move_word_right(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_index(), select);
+    fn cut_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.line_buffer.text_object_range(text_object) {
+            let cut_slice = &self.line_buffer.get_buffer()[range.clone()];
+            if !cut_slice.is_empty() {
+                self.cut_buffer
+                    .cut(cut_slice, Self::text_object_clipboard_mode(text_object));
+                self.line_buffer.clear_range(range.clone());
+            }
+            // The object may lie ahead of the cursor, as quotes are searched forward
+            self.line_buffer.set_insertion_point(range.start);
+        } else {
+            self.missed_text_object = true;
+        }
     }
 
-    fn move_word_right_start(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_start_index(), select);
+    fn select_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.line_buffer.text_object_range(text_object) {
+            self.selection_anchor = Some(range.start);
+            self.line_buffer.set_insertion_point(range.end);
+        }
     }
 
-    fn move_big_word_right_start(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_right_start_index(), select);
+    fn copy_text_object(&mut self, text_object: TextObject) {
+        if let Some(range) = self.line_buffer.text_object_range(text_object) {
+            self.copy_range(range, Self::text_object_clipboard_mode(text_object));
+        }
     }
 
-    fn move_word_right_end(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.word_right_end_index(), select);
+    /// Paragraphs are made of whole lines, so they are pasted as such
+    fn text_object_clipboard_mode(text_object: TextObject) -> ClipboardMode {
+        if text_object.kind == TextObjectKind::Paragraph {
+            ClipboardMode::Lines
+        } else {
+            ClipboardMode::Normal
+        }
     }
 
-    fn move_big_word_right_end(&mut self, select: bool) {
-        self.move_to_position(self.line_buffer.big_word_right_end_index(), select);
+    fn copy_range(&mut self, range: Range<usize>, mode: ClipboardMode) {
+        let copy_slice = &self.line_buffer.get_buffer()[range];
+        if !copy_slice.is_empty() {
+            self.cut_buffer.copy(copy_slice, mode);
+        }
     }
 
-    fn insert_char(&mut self, c: char) {
-        self.delete_selection();
-        self.line_buffer.insert_char(c);
+    fn copy_from_start(&mut self) {
+        self.copy_range(0..self.insertion_point(), ClipboardMode::Normal);
     }
 
-    fn insert_str(&mut self, str: &str) {
-        self.delete_selection();
-        self.line_buffer.insert_str(str);
+    fn copy_from_line_start(&mut self) {
+        let line_start = self.line_buffer.current_line_range().start;
+        self.copy_range(line_start..self.insertion_point(), ClipboardMode::Normal);
     }
 
-    fn insert_newline(&mut self) {
-        self.delete_selection();
-        self.line_buffer.insert_newline();
+    fn copy_from_end(&mut self) {
+        self.copy_range(
+            self.insertion_point()..self.line_buffer.len(),
+            ClipboardMode::Normal,
+        );
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use pretty_assertions::assert_eq;
-    use rstest::rstest;
+    fn copy_to_line_end(&mut self) {
+        self.copy_range(
+            self.insertion_point()..self.line_buffer.find_current_line_end(),
+            ClipboardMode::Normal,
+        );
+    }
 
-    fn editor_with(buffer: &str) -> Editor {
-        let mut editor = Editor::default();
-        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
-        editor
+    fn copy_current_line(&mut self) {
+        self.copy_range(self.line_buffer.current_line_range(), ClipboardMode::Lines);
     }
 
-    #[rstest]
-    #[case("abc def ghi", 11, "abc def ")]
-    #[case("abc def-ghi", 11, "abc def-")]
-    #[case("abc def.ghi", 11, "abc ")]
+    fn copy_lines(&mut self, count: usize) {
+        let start = self.line_buffer.current_line_range().start;
+        let buffer = self.line_buffer.get_buffer();
+        let end = buffer[start..]
+            .match_indices('\n')
+            .nth(count.saturating_sub(1))
+            .map_or(buffer.len(), |(offset, _)| start + offset + 1);
+        self.copy_range(start..end, ClipboardMode::Lines);
+    }
+
+    fn copy_word_left(&mut self) {
+        let left_index = self.line_buffer.word_left_index();
+        self.copy_range(left_index..self.insertion_point(), ClipboardMode::Normal);
+    }
+
+    fn copy_big_word_left(&mut self) {
+        let left_index = self.line_buffer.big_word_left_index();
+        self.copy_range(left_index..self.insertion_point(), ClipboardMode::Normal);
+    }
+
+    fn copy_word_right(&mut self) {
+        let right_index = self.line_buffer.word_right_index();
+        self.copy_range(self.insertion_point()..right_index, ClipboardMode::Normal);
+    }
+
+    fn copy_big_word_right(&mut self) {
+        let right_index = self.line_buffer.next_whitespace();
+        self.copy_range(self.insertion_point()..right_index, ClipboardMode::Normal);
+    }
+
+    fn copy_word_right_to_next(&mut self) {
+        let right_index = self.line_buffer.word_right_start_index();
+        self.copy_range(self.insertion_point()..right_index, ClipboardMode::Normal);
+    }
+
+    fn copy_big_word_right_to_next(&mut self) {
+        let right_index = self.line_buffer.big_word_right_start_index();
+        self.copy_range(self.insertion_point()..right_index, ClipboardMode::Normal);
+    }
+
+    fn copy_left(&mut self) {
+        let left_index = self.line_buffer.grapheme_left_index();
+        self.copy_range(left_index..self.insertion_point(), ClipboardMode::Normal);
+    }
+
+    fn copy_right(&mut self) {
+        let right_index = self.line_buffer.grapheme_right_index();
+        self.copy_range(self.insertion_point()..right_index, ClipboardMode::Normal);
+    }
+
+    fn copy_right_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
+        if let Some(index) = self.line_buffer.find_char_right(c, current_line) {
+            let extra = if before_char { 0 } else { c.len_utf8() };
+            self.copy_range(self.insertion_point()..index + extra, ClipboardMode::Normal);
+        }
+    }
+
+    fn copy_left_until_char(&mut self, c: char, before_char: bool, current_line: bool) {
+        if let Some(index) = self.line_buffer.find_char_left(c, current_line) {
+            let extra = if before_char { c.len_utf8() } else { 0 };
+            self.copy_range(index + extra..self.insertion_point(), ClipboardMode::Normal);
+        }
+    }
+
+    /// If a selection is active returns the selected range, otherwise None.
+    /// The range is guaranteed to be ascending.
+    pub fn get_selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor.map(|selection_anchor| {
+            let (start, end) = if self.insertion_point() > selection_anchor {
+                (selection_anchor, self.insertion_point())
+            } else {
+                (self.insertion_point(), selection_anchor)
+            };
+            if self.inclusive_selection {
+                let end = self.line_buffer.get_buffer()[end..]
+                    .graphemes(true)
+                    .next()
+                    .map_or(end, |grapheme| end + grapheme.len());
+                (start, end)
+            } else {
+                (start, end)
+            }
+        })
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some((start, end)) = self.get_selection() {
+            self.line_buffer.clear_range_safe(start, end);
+            self.selection_anchor = None;
+        }
+    }
+
+    fn backspace(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.delete_selection();
+        } else {
+            self.line_buffer.delete_left_grapheme();
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.selection_anchor.is_some() {
+            self.delete_selection();
+        } else {
+            self.line_buffer.delete_right_grapheme();
+        }
+    }
+
+    fn move_word_left(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_left_index(), select);
+    }
+
+    fn move_big_word_left(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_left_index(), select);
+    }
+
+    fn move_word_right(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_index(), select);
+    }
+
+    fn move_word_right_start(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_start_index(), select);
+    }
+
+    fn move_big_word_right_start(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_right_start_index(), select);
+    }
+
+    fn move_word_right_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_right_end_index(), select);
+    }
+
+    fn move_big_word_right_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_right_end_index(), select);
+    }
+
+    fn move_word_left_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.word_left_end_index(), select);
+    }
+
+    fn move_big_word_left_end(&mut self, select: bool) {
+        self.move_to_position(self.line_buffer.big_word_left_end_index(), select);
+    }
+
+    fn insert_char(&mut self, c: char) {
+        self.delete_selection();
+        self.line_buffer.insert_char(c);
+    }
+
+    fn insert_str(&mut self, str: &str) {
+        self.delete_selection();
+        self.line_buffer.insert_str(str);
+    }
+
+    fn insert_newline(&mut self) {
+        self.delete_selection();
+        self.line_buffer.insert_newline();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn editor_with(buffer: &str) -> Editor {
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor
+    }
+
+    #[rstest]
+    #[case("abc def ghi", 11, "abc def ")]
+    #[case("abc def-ghi", 11, "abc def-")]
+    #[case("abc def.ghi", 11, "abc ")]
     fn test_cut_word_left(#[case] input: &str, #[case] position: usize, #[case] expected: &str) {
         let mut editor = editor_with(input);
         editor.line_buffer.set_insertion_point(position);
 
-        editor.cut_word_left();
+        editor.cut_word_left();
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("abc def ghi", 11, "abc def ")]
+    #[case("abc def-ghi", 11, "abc ")]
+    #[case("abc def.ghi", 11, "abc ")]
+    #[case("abc def gh ", 11, "abc def ")]
+    fn test_cut_big_word_left(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.cut_big_word_left();
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("hello world", 0, 'l', 1, false, "lo world")]
+    #[case("hello world", 0, 'l', 1, true, "llo world")]
+    #[ignore = "Deleting two consecutives is not implemented correctly and needs the multiplier explicitly."]
+    #[case("hello world", 0, 'l', 2, false, "o world")]
+    #[case("hello world", 0, 'h', 1, false, "hello world")]
+    #[case("hello world", 0, 'l', 3, true, "ld")]
+    #[case("hello world", 4, 'o', 1, true, "hellorld")]
+    #[case("hello world", 4, 'w', 1, false, "hellorld")]
+    #[case("hello world", 4, 'o', 1, false, "hellrld")]
+    fn test_cut_right_until_char(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] search_char: char,
+        #[case] repeat: usize,
+        #[case] before_char: bool,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        for _ in 0..repeat {
+            editor.cut_right_until_char(search_char, before_char, true);
+        }
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("abc", 1, 'X', "aXc")]
+    #[case("abc", 1, '🔄', "a🔄c")]
+    #[case("a🔄c", 1, 'X', "aXc")]
+    #[case("a🔄c", 1, '🔀', "a🔀c")]
+    fn test_replace_char(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] replacement: char,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.replace_char(replacement);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "hello world",
+        0,
+        EditCommand::CopyWordRightToNext,
+        "hhello ello world"
+    )]
+    #[case("hello world", 6, EditCommand::CopyToLineEnd, "hello wworldorld")]
+    #[case("hello world", 11, EditCommand::CopyWordLeft, "hello worldworld")]
+    #[case("hello world", 0, EditCommand::CopyRightUntil('o'), "hhelloello world")]
+    fn test_copy_then_paste(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] copy: EditCommand,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&copy);
+        assert_eq!(editor.get_buffer(), input);
+        assert_eq!(editor.insertion_point(), position);
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferAfter);
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("abc\ndef\nghi", 5, 9, "abc\n")]
+    #[case("abc\ndef\nghi", 1, 6, "ghi")]
+    #[case("abc\ndef\nghi", 6, 5, "abc\nghi")]
+    fn test_cut_selected_lines(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] position: usize,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.selection_anchor = Some(anchor);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CutSelectedLines);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case(0, EditCommand::MoveRight { select: true }, Some((0, 2)))]
+    #[case(4, EditCommand::MoveLeft { select: true }, Some((2, 5)))]
+    #[case(2, EditCommand::MoveToLineEnd { select: true }, Some((2, 5)))]
+    #[case(1, EditCommand::MoveRight { select: false }, None)]
+    fn test_inclusive_selection_covers_cursor(
+        #[case] position: usize,
+        #[case] motion: EditCommand,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        let mut editor = editor_with("aböc");
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::StartInclusiveSelection);
+        assert!(editor.get_selection().is_some());
+        editor.run_edit_command(&motion);
+
+        assert_eq!(editor.get_selection(), expected);
+        // Inclusive until the selection is cleared
+        editor.run_edit_command(&EditCommand::ClearSelection);
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::MoveRight { select: true });
+        assert_eq!(editor.get_selection(), Some((0, 1)));
+    }
+
+    #[rstest]
+    #[case(
+        "abc\ndef\nghi",
+        (5, 7),
+        "XY",
+        EditCommand::PasteCutBufferOverSelection,
+        "abc\ndXY\nghi",
+        6,
+        "ef"
+    )]
+    // Lines pasted over a part of a line go on lines of their own
+    #[case(
+        "abc\ndef\nghi",
+        (5, 7),
+        "  XY\n",
+        EditCommand::PasteCutBufferOverSelection,
+        "abc\nd\n  XY\n\nghi",
+        8,
+        "ef"
+    )]
+    // Text pasted over lines takes their place as a line
+    #[case(
+        "abc\ndef\nghi",
+        (5, 6),
+        "XY",
+        EditCommand::PasteCutBufferOverSelectedLines,
+        "abc\nXY\nghi",
+        4,
+        "def\n"
+    )]
+    #[case(
+        "abc\ndef\nghi",
+        (9, 5),
+        "X\nY\n",
+        EditCommand::PasteCutBufferOverSelectedLines,
+        "abc\nX\nY",
+        4,
+        "def\nghi"
+    )]
+    fn test_paste_over_selection(
+        #[case] input: &str,
+        #[case] selection: (usize, usize),
+        #[case] register: &str,
+        #[case] paste: EditCommand,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+        #[case] expected_register: &str,
+    ) {
+        let mut editor = editor_with(input);
+        // Line-wise if ending with a line break
+        let mode = if register.ends_with('\n') {
+            ClipboardMode::Lines
+        } else {
+            ClipboardMode::Normal
+        };
+        editor.cut_buffer.cut(register, mode);
+        editor.selection_anchor = Some(selection.0);
+        editor.line_buffer.set_insertion_point(selection.1);
+
+        editor.run_edit_command(&paste);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        assert_eq!(editor.get_selection(), None);
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, expected_register);
+        // The replaced text is cut line-wise from a line selection
+        assert_eq!(
+            matches!(mode, ClipboardMode::Lines),
+            paste == EditCommand::PasteCutBufferOverSelectedLines
+        );
+
+        // A single undo step
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), input);
+    }
+
+    #[rstest]
+    #[case(
+        "abc\ndef\nghi",
+        1,
+        EditCommand::PasteCutBufferAfter,
+        "abX c\ndeYYf\nghZ i",
+        2
+    )]
+    #[case(
+        "abc\ndef\nghi",
+        1,
+        EditCommand::PasteCutBufferBefore,
+        "aX bc\ndYYef\ngZ hi",
+        1
+    )]
+    #[case(
+        "abc\nd\nghi",
+        2,
+        EditCommand::PasteCutBufferAfter,
+        "abcX\nd  YY\nghiZ",
+        3
+    )]
+    #[case("abc", 1, EditCommand::PasteCutBufferAfter, "abX c\n  YY\n  Z", 2)]
+    #[case("ab\ncd", 4, EditCommand::PasteCutBufferBefore, "ab\ncX d\n YY\n Z", 4)]
+    fn test_paste_block(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] paste: EditCommand,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.cut_buffer.copy("X\nYY\nZ", ClipboardMode::Block);
+
+        editor.run_edit_command(&paste);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("ab", 0, "X", ClipboardMode::Normal, true, "aXXXb", 3)]
+    #[case("ab", 1, "X", ClipboardMode::Normal, false, "aXXXb", 3)]
+    #[case(
+        "abc\ndef",
+        1,
+        "xy\n",
+        ClipboardMode::Lines,
+        true,
+        "abc\nxy\nxy\nxy\ndef",
+        4
+    )]
+    #[case(
+        "abc\ndef",
+        1,
+        "  xy",
+        ClipboardMode::Lines,
+        true,
+        "abc\n  xy\n  xy\n  xy\ndef",
+        6
+    )]
+    #[case(
+        "ab\ncd",
+        1,
+        "X\nYY",
+        ClipboardMode::Block,
+        true,
+        "abX X X\ncdYYYYYY",
+        2
+    )]
+    fn test_paste_times(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] content: &str,
+        #[case] mode: ClipboardMode,
+        #[case] after: bool,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.cut_buffer.copy(content, mode);
+
+        editor.run_edit_command(&if after {
+            EditCommand::PasteCutBufferAfterTimes(3)
+        } else {
+            EditCommand::PasteCutBufferBeforeTimes(3)
+        });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case(
+        EditCommand::PasteCutBufferAfterTimes(1),
+        EditCommand::PasteCutBufferAfter
+    )]
+    #[case(
+        EditCommand::PasteCutBufferBeforeTimes(0),
+        EditCommand::PasteCutBufferBefore
+    )]
+    fn test_paste_once_times(#[case] paste_times: EditCommand, #[case] paste: EditCommand) {
+        let mut expected = editor_with("abc\ndef");
+        expected.line_buffer.set_insertion_point(5);
+        expected.cut_buffer.copy("xy\n", ClipboardMode::Lines);
+        let mut editor = editor_with("abc\ndef");
+        editor.line_buffer.set_insertion_point(5);
+        editor.cut_buffer.copy("xy\n", ClipboardMode::Lines);
+
+        expected.run_edit_command(&paste);
+        editor.run_edit_command(&paste_times);
+
+        assert_eq!(editor.get_buffer(), expected.get_buffer());
+        assert_eq!(editor.insertion_point(), expected.insertion_point());
+    }
+
+    #[test]
+    fn test_copy_selected_block() {
+        let mut editor = editor_with("abcd\nef\nghij");
+        editor.selection_anchor = Some(1);
+        editor.line_buffer.set_insertion_point(11);
+
+        editor.run_edit_command(&EditCommand::CopySelectedBlock);
+        assert_eq!(editor.get_buffer(), "abcd\nef\nghij");
+        assert_eq!(editor.cut_buffer.get().0, "bc\nf\nhi");
+
+        editor.selection_anchor = None;
+        editor.line_buffer.set_insertion_point(4);
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "abcdbc\nef  f\nghijhi");
+    }
+
+    #[rstest]
+    #[case("abcd", 0, 3, "xxxd", 3)]
+    #[case("abcd", 1, 3, "axxx", 4)]
+    #[case("abcd", 2, 3, "abcd", 2)]
+    #[case("ab\ncd", 0, 3, "ab\ncd", 0)]
+    #[case("aé日\r\ncd", 0, 3, "xxx\r\ncd", 3)]
+    fn test_replace_chars_in_line(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] count: usize,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.run_edit_command(&EditCommand::ReplaceCharsInLine { c: 'x', count });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("one\ntwo\nthree", 5, 1, "two\n")]
+    #[case("one\ntwo\nthree", 5, 2, "two\nthree")]
+    #[case("one\ntwo\nthree", 0, 5, "one\ntwo\nthree")]
+    fn test_copy_lines(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] count: usize,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+        editor.run_edit_command(&EditCommand::CopyLines(count));
+
+        assert_eq!(editor.get_buffer(), input);
+        assert_eq!(editor.insertion_point(), position);
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, expected);
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_kill_ring() {
+        let mut editor = editor_with("one two three");
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        assert_eq!(editor.get_buffer(), "");
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "three");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "two ");
+        assert_eq!(editor.insertion_point(), 4);
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "one ");
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "three");
+
+        // Only directly after a paste
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::YankPop);
+        assert_eq!(editor.get_buffer(), "three");
+    }
+
+    #[test]
+    fn test_insert_register() {
+        let mut editor = editor_with("foo bar");
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&EditCommand::CopyWordRight);
+        editor.line_buffer.set_insertion_point(7);
+
+        editor.run_edit_command(&EditCommand::InsertRegister('a'));
+        assert_eq!(editor.get_buffer(), "foo barfoo");
+        editor.run_edit_command(&EditCommand::InsertRegister('"'));
+        assert_eq!(editor.get_buffer(), "foo barfoofoo");
+        editor.run_edit_command(&EditCommand::InsertRegister('b'));
+        assert_eq!(editor.get_buffer(), "foo barfoofoo");
+        assert_eq!(editor.insertion_point(), 13);
+    }
+
+    #[test]
+    fn test_paste_line_yanked_to_plus_register() {
+        let mut editor = editor_with("one\ntwo");
+        editor.line_buffer.set_insertion_point(0);
+        editor.run_edit_command(&EditCommand::SelectRegister('+'));
+        editor.run_edit_command(&EditCommand::CopyCurrentLine);
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferAfter);
+        assert_eq!(editor.get_buffer(), "one\none\ntwo");
+    }
+
+    #[rstest]
+    #[case("abc\ndef\nghi", 1, 0)]
+    #[case("abc\ndef\nghi", 2, 4)]
+    #[case("abc\ndef\nghi", 3, 8)]
+    #[case("abc\ndef\nghi", 10, 8)]
+    #[case("abc", 0, 0)]
+    fn test_move_to_line(#[case] input: &str, #[case] line: usize, #[case] expected: usize) {
+        let mut editor = editor_with(input);
+
+        editor.run_edit_command(&EditCommand::MoveToLine {
+            line,
+            select: false,
+        });
+
+        assert_eq!(editor.insertion_point(), expected);
+    }
+
+    #[rstest]
+    #[case("abc\ndef\nghi", 5, EditCommand::MoveToEnd { select: true }, "abc\n")]
+    #[case("abc\ndef\nghi", 5, EditCommand::MoveToStart { select: true }, "ghi")]
+    #[case("abc\ndef\nghi", 0, EditCommand::MoveToStart { select: true }, "def\nghi")]
+    #[case("abc\ndef\nghi", 9, EditCommand::MoveToLine { line: 2, select: true }, "abc\n")]
+    fn test_cut_lines_to_target(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] target: EditCommand,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&target);
+        editor.run_edit_command(&EditCommand::CutSelectedLines);
 
         assert_eq!(editor.get_buffer(), expected);
     }
 
     #[rstest]
-    #[case("abc def ghi", 11, "abc def ")]
-    #[case("abc def-ghi", 11, "abc ")]
-    #[case("abc def.ghi", 11, "abc ")]
-    #[case("abc def gh ", 11, "abc def ")]
-    fn test_cut_big_word_left(
+    #[case("a (b [c] d) e", 0, Some(10))]
+    #[case("a (b [c] d) e", 5, Some(7))]
+    #[case("a (b [c] d) e", 8, Some(2))]
+    #[case("f(a, b)\n}", 7, None)]
+    #[case("no brackets", 0, None)]
+    #[case("(unbalanced", 0, None)]
+    #[case("{\n  x\n}", 0, Some(6))]
+    fn test_matching_bracket_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: Option<usize>,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        assert_eq!(editor.line_buffer.matching_bracket_index(), expected);
+    }
+
+    #[rstest]
+    #[case("call(a, (b)) rest", 4, "call rest")]
+    #[case("call(a, (b)) rest", 0, " rest")]
+    #[case("call(a, (b)) rest", 7, "call(a,) rest")]
+    #[case("call(a, (b)) rest", 10, "call(a, ) rest")]
+    #[case("no brackets", 0, "no brackets")]
+    fn test_cut_to_matching_bracket(
         #[case] input: &str,
         #[case] position: usize,
         #[case] expected: &str,
@@ -659,60 +2174,560 @@ mod test {
         let mut editor = editor_with(input);
         editor.line_buffer.set_insertion_point(position);
 
-        editor.cut_big_word_left();
+        editor.run_edit_command(&EditCommand::CutToMatchingBracket);
 
         assert_eq!(editor.get_buffer(), expected);
     }
 
     #[rstest]
-    #[case("hello world", 0, 'l', 1, false, "lo world")]
-    #[case("hello world", 0, 'l', 1, true, "llo world")]
-    #[ignore = "Deleting two consecutives is not implemented correctly and needs the multiplier explicitly."]
-    #[case("hello world", 0, 'l', 2, false, "o world")]
-    #[case("hello world", 0, 'h', 1, false, "hello world")]
-    #[case("hello world", 0, 'l', 3, true, "ld")]
-    #[case("hello world", 4, 'o', 1, true, "hellorld")]
-    #[case("hello world", 4, 'w', 1, false, "hellorld")]
-    #[case("hello world", 4, 'o', 1, false, "hellrld")]
-    fn test_cut_right_until_char(
+    #[case("abc", 1, "", 0, "abc\n")]
+    #[case("a\n  b\n  c", 3, "a\n  c", 4, "  b\n")]
+    #[case("  a\nb", 4, "  a", 2, "b\n")]
+    #[case("a\r\nb", 3, "a", 0, "b\n")]
+    #[case("a\n", 2, "a", 0, "\n")]
+    fn test_cut_current_line(
         #[case] input: &str,
         #[case] position: usize,
-        #[case] search_char: char,
-        #[case] repeat: usize,
-        #[case] before_char: bool,
         #[case] expected: &str,
+        #[case] expected_position: usize,
+        #[case] expected_cut: &str,
     ) {
         let mut editor = editor_with(input);
         editor.line_buffer.set_insertion_point(position);
-        for _ in 0..repeat {
-            editor.cut_right_until_char(search_char, before_char, true);
+
+        editor.run_edit_command(&EditCommand::CutCurrentLine);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, expected_cut);
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[rstest]
+    #[case("a\n  b\nc", 4, "a\n  b\nc\n")]
+    #[case("a\nb\n", 3, "a\nb\n")]
+    fn test_cut_and_copy_all(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected_lines: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CopyAll);
+        assert_eq!(editor.get_buffer(), input);
+        assert_eq!(editor.insertion_point(), 0);
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, expected_lines);
+        assert!(matches!(mode, ClipboardMode::Lines));
+
+        editor.cut_buffer.copy("x", ClipboardMode::Normal);
+        editor.run_edit_command(&EditCommand::CutAll);
+        assert_eq!(editor.get_buffer(), "");
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, expected_lines);
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[test]
+    fn test_cut_all_of_empty_buffer() {
+        let mut editor = editor_with("");
+        editor.cut_buffer.copy("kept", ClipboardMode::Normal);
+
+        editor.run_edit_command(&EditCommand::CutAll);
+        editor.run_edit_command(&EditCommand::CopyAll);
+
+        assert_eq!(editor.get_buffer(), "");
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, "kept");
+        assert!(matches!(mode, ClipboardMode::Normal));
+    }
+
+    #[rstest]
+    #[case("  foo\nbar", 3, true, "  \nbar", 2)]
+    #[case("  foo\nbar", 3, false, "\nbar", 0)]
+    #[case("a\r\n\tb", 4, true, "a\r\n\t", 4)]
+    #[case("", 0, true, "", 0)]
+    fn test_change_line(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] keep_indent: bool,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::ChangeLine { keep_indent });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("a\nb\nc", 0, EditCommand::PasteCutBufferAfter, "b\na\nc")]
+    #[case("a\nb\nc", 2, EditCommand::PasteCutBufferAfter, "a\nc\nb")]
+    #[case("a\nb\nc", 4, EditCommand::PasteCutBufferBefore, "a\nc\nb")]
+    #[case("a\nb", 2, EditCommand::PasteCutBufferAfter, "a\nb")]
+    fn test_cut_current_line_and_paste(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] paste: EditCommand,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CutCurrentLine);
+        editor.run_edit_command(&paste);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("abc", 2, EditCommand::CutFromLineStart, "c", 0, "ab")]
+    #[case("ab\ncd\nef", 4, EditCommand::CutFromLineStart, "ab\nd\nef", 3, "c")]
+    #[case("ab\r\ncd", 6, EditCommand::CutFromLineStart, "ab\r\n", 4, "cd")]
+    #[case("ab\ncd", 3, EditCommand::CutFromLineStart, "ab\ncd", 3, "")]
+    #[case("ab\ncd\nef", 4, EditCommand::CutFromStart, "d\nef", 0, "ab\nc")]
+    #[case("ab\ncd", 0, EditCommand::CutFromStart, "ab\ncd", 0, "")]
+    fn test_cut_back_to_start(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] command: EditCommand,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+        #[case] expected_cut: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+        assert_eq!(editor.cut_buffer.get().0, expected_cut);
+    }
+
+    #[rstest]
+    #[case(EditCommand::PasteCutBufferBefore)]
+    #[case(EditCommand::PasteCutBufferAfter)]
+    #[case(EditCommand::PasteCutBufferAfterTimes(3))]
+    #[case(EditCommand::PasteCutBufferOverSelection)]
+    fn test_paste_from_empty_register_keeps_selected_text(#[case] paste: EditCommand) {
+        let mut editor = editor_with("abc");
+        editor.cut_buffer.copy("x", ClipboardMode::Normal);
+        editor.run_edit_command(&EditCommand::MoveLeft { select: true });
+
+        editor.run_edit_command(&EditCommand::SelectRegister('a'));
+        editor.run_edit_command(&paste);
+        assert_eq!(editor.get_buffer(), "abc");
+
+        // The register selected for the dropped paste doesn't carry over
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "abxc");
+    }
+
+    #[test]
+    fn test_take_clipboard_error() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory can't be replaced by the clipboard file
+        let file = dir.path().join("clipboard");
+        std::fs::create_dir(&file).unwrap();
+        std::fs::write(file.join("occupied"), "").unwrap();
+
+        let mut editor = editor_with("ab");
+        editor.set_clipboard(Box::new(
+            crate::core_editor::FileBackedClipboard::with_file(file),
+        ));
+        editor.run_edit_command(&EditCommand::CopyCurrentLine);
+
+        assert!(matches!(
+            editor.take_clipboard_error(),
+            Some(ClipboardError::IOError(_))
+        ));
+        assert!(editor.take_clipboard_error().is_none());
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferBefore);
+        assert_eq!(editor.get_buffer(), "ab\nab");
+    }
+
+    #[rstest]
+    #[case(PasteSanitizer::Keep, ClipboardMode::Normal, "ab", "ax\r\ny\u{1b}b")]
+    #[case(PasteSanitizer::Strip, ClipboardMode::Normal, "ab", "axyb")]
+    #[case(PasteSanitizer::Replace(' '), ClipboardMode::Normal, "ab", "ax y b")]
+    #[case(PasteSanitizer::Strip, ClipboardMode::Lines, "ab", "ab\nx\r\ny\u{1b}")]
+    fn test_paste_sanitizer(
+        #[case] paste_sanitizer: PasteSanitizer,
+        #[case] mode: ClipboardMode,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.set_paste_sanitizer(paste_sanitizer);
+        editor.line_buffer.set_insertion_point(0);
+        editor.cut_buffer.copy("x\r\ny\u{1b}", mode);
+
+        editor.run_edit_command(&EditCommand::PasteCutBufferAfter);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(PasteSanitizer::Keep, "ax\ny\u{1b}[31mb")]
+    #[case(PasteSanitizer::Strip, "axy[31mb")]
+    #[case(PasteSanitizer::Replace(' '), "ax y [31mb")]
+    fn test_insert_pasted_sanitizes(
+        #[case] paste_sanitizer: PasteSanitizer,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with("ab");
+        editor.set_paste_sanitizer(paste_sanitizer);
+        editor.line_buffer.set_insertion_point(1);
+
+        let insert = editor.insert_pasted("x\r\ny\u{1b}[31m");
+        editor.run_edit_command(&insert);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("", "", "foo-bar baz", "-bar baz")]
+    #[case("-", "", "foo-bar baz", "baz")]
+    #[case("", "", "foo_bar baz", "baz")]
+    #[case("", "_", "foo_bar baz", "_bar baz")]
+    #[case("-", "_", "a-b_c", "_c")]
+    fn test_cut_word_with_word_characters(
+        #[case] word: &str,
+        #[case] separators: &str,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.set_word_characters(WordCharacters {
+            word: word.into(),
+            separators: separators.into(),
+        });
+        editor.run_edit_command(&EditCommand::CutWordRightToNext);
+
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case(crate::ViCharSearch::ToRight('x'), 0, "c", "axbx")]
+    #[case(crate::ViCharSearch::TillRight('x'), 0, "xc", "axb")]
+    #[case(crate::ViCharSearch::ToLeft('x'), 4, "ac", "xbx")]
+    #[case(crate::ViCharSearch::TillLeft('x'), 4, "axc", "bx")]
+    #[case(crate::ViCharSearch::ToRight('x').reverse(), 4, "ac", "xbx")]
+    #[case(crate::ViCharSearch::TillLeft('x').reverse(), 0, "xc", "axb")]
+    fn test_cut_to_second_char(
+        #[case] char_search: crate::ViCharSearch,
+        #[case] position: usize,
+        #[case] expected: &str,
+        #[case] expected_cut: &str,
+    ) {
+        let mut editor = editor_with("axbxc");
+        editor.line_buffer.set_insertion_point(position);
+
+        for command in char_search.to_cut_n(2) {
+            editor.run_edit_command(&command);
         }
+
         assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.cut_buffer.get().0, expected_cut);
     }
 
     #[rstest]
-    #[case("abc", 1, 'X', "aXc")]
-    #[case("abc", 1, '🔄', "a🔄c")]
-    #[case("a🔄c", 1, 'X', "aXc")]
-    #[case("a🔄c", 1, '🔀', "a🔀c")]
-    fn test_replace_char(
+    #[case(r#"say "hi" now"#, 0, r#"say "" now"#, 5)]
+    #[case(r#"say "hi" now"#, 6, r#"say "" now"#, 5)]
+    #[case(r#"say "" now"#, 0, r#"say "" now"#, 5)]
+    #[case(r#"say "hi now"#, 0, r#"say "hi now"#, 0)]
+    #[case(r#"say "hi" now"#, 9, r#"say "hi" now"#, 9)]
+    fn test_cut_inside_quotes(
         #[case] input: &str,
         #[case] position: usize,
-        #[case] replacement: char,
         #[case] expected: &str,
+        #[case] expected_position: usize,
     ) {
         let mut editor = editor_with(input);
         editor.line_buffer.set_insertion_point(position);
 
-        editor.replace_char(replacement);
+        editor.run_edit_command(&EditCommand::CutTextObject {
+            text_object: TextObject {
+                around: false,
+                kind: crate::TextObjectKind::DoubleQuote,
+            },
+        });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[rstest]
+    #[case("f {\n    a\n    b\n}", 9, false, "f {\n\n}", 4)]
+    #[case("f {\n    a\n    b\n}", 9, true, "f ", 2)]
+    #[case("f {\n  { a }\n}", 2, false, "f {\n\n}", 4)]
+    fn test_cut_multiline_block(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] around: bool,
+        #[case] expected: &str,
+        #[case] expected_position: usize,
+    ) {
+        let mut editor = editor_with(input);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&EditCommand::CutTextObject {
+            text_object: TextObject {
+                around,
+                kind: TextObjectKind::Braces,
+            },
+        });
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), expected_position);
+    }
+
+    #[test]
+    fn test_cut_paragraph() {
+        let mut editor = editor_with("a\nb\n\nc\n");
+        editor.line_buffer.set_insertion_point(2);
+
+        editor.run_edit_command(&EditCommand::CutTextObject {
+            text_object: TextObject {
+                around: true,
+                kind: TextObjectKind::Paragraph,
+            },
+        });
+
+        assert_eq!(editor.get_buffer(), "c\n");
+        let (content, mode) = editor.cut_buffer.get();
+        assert_eq!(content, "a\nb\n\n");
+        assert!(matches!(mode, ClipboardMode::Lines));
+    }
+
+    #[rstest]
+    #[case(EditCommand::UppercaseSelection, "Hello WORLD ok")]
+    #[case(EditCommand::LowercaseSelection, "Hello world ok")]
+    #[case(EditCommand::SwitchcaseSelection, "Hello WOrld ok")]
+    fn test_change_case_selection(#[case] command: EditCommand, #[case] expected: &str) {
+        let mut editor = editor_with("Hello woRLD ok");
+        editor.line_buffer.set_insertion_point(6);
+        editor.run_edit_command(&EditCommand::SelectTextObject {
+            text_object: TextObject {
+                around: false,
+                kind: crate::TextObjectKind::Word,
+            },
+        });
+        assert_eq!(editor.get_selection(), Some((6, 11)));
+
+        editor.run_edit_command(&command);
+
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.insertion_point(), 6);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[rstest]
+    #[case("a\n  b\nc", 0, 4, EditCommand::IndentLines("  ".into()), "  a\n    b\nc")]
+    #[case("a\n\nb", 0, 4, EditCommand::IndentLines("\t".into()), "\ta\n\n\tb")]
+    #[case("a\n  b", 4, 4, EditCommand::IndentLines("  ".into()), "a\n    b")]
+    #[case("    a\n  b\nc", 0, 8, EditCommand::DedentLines("    ".into()), "a\nb\nc")]
+    #[case("\t\ta\n b", 0, 5, EditCommand::DedentLines("    ".into()), "\ta\nb")]
+    #[case("a\n b", 0, 3, EditCommand::DedentLines("  ".into()), "a\nb")]
+    fn test_indent_selected_lines(
+        #[case] input: &str,
+        #[case] anchor: usize,
+        #[case] position: usize,
+        #[case] command: EditCommand,
+        #[case] expected: &str,
+    ) {
+        let mut editor = editor_with(input);
+        editor.selection_anchor = Some(anchor);
+        editor.line_buffer.set_insertion_point(position);
+
+        editor.run_edit_command(&command);
 
         assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn test_overwrite_and_restore() {
+        let mut editor = editor_with("abc\nd");
+        editor.line_buffer.set_insertion_point(1);
+
+        for c in "xyz".chars() {
+            editor.run_edit_command(&EditCommand::OverwriteChar(c));
+        }
+        assert_eq!(editor.get_buffer(), "axyz\nd");
+        assert_eq!(editor.insertion_point(), 4);
+
+        editor.run_edit_command(&EditCommand::RestoreOverwrittenChar);
+        editor.run_edit_command(&EditCommand::RestoreOverwrittenChar);
+        assert_eq!(editor.get_buffer(), "axc\nd");
+        assert_eq!(editor.insertion_point(), 2);
+
+        editor.run_edit_command(&EditCommand::RestoreOverwrittenChar);
+        editor.run_edit_command(&EditCommand::RestoreOverwrittenChar);
+        assert_eq!(editor.get_buffer(), "abc\nd");
+        assert_eq!(editor.insertion_point(), 0);
+    }
+
+    #[test]
+    fn test_marks_shift_with_edits() {
+        let mut editor = editor_with("hello world");
+        editor.line_buffer.set_insertion_point(6);
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+
+        editor.line_buffer.set_insertion_point(0);
+        for cmd in str_to_edit_commands("oh ") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            mark: 'a',
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 9);
+        assert_eq!(&editor.get_buffer()[9..], "world");
+
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            mark: 'b',
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 9);
+    }
+
+    #[test]
+    fn test_cut_to_previous_word_end() {
+        let mut editor = editor_with("abc def");
+        editor.line_buffer.set_insertion_point(5);
+        for command in [
+            EditCommand::MoveRight { select: false },
+            EditCommand::MoveLeft { select: true },
+            EditCommand::MoveWordLeftEnd { select: true },
+            EditCommand::CutSelection,
+        ] {
+            editor.run_edit_command(&command);
+        }
+
+        assert_eq!(editor.get_buffer(), "abf");
+        assert_eq!(editor.cut_buffer.get().0, "c de");
+    }
+
+    #[test]
+    fn test_mark_past_buffer_end_clamps() {
+        let mut editor = editor_with("hello world");
+        editor.run_edit_command(&EditCommand::SetMark('^'));
+        editor.set_line_buffer(LineBuffer::from("hi"), UndoBehavior::CreateUndoPoint);
+        editor.line_buffer.set_insertion_point(0);
+
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            mark: '^',
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 2);
+    }
+
+    #[test]
+    fn test_jump_back_to_previous_position() {
+        let mut editor = editor_with("abc\ndef\nghi");
+        editor.line_buffer.set_insertion_point(5);
+        let jump_back = EditCommand::MoveToMark {
+            mark: '`',
+            select: false,
+        };
+
+        // Without a previous jump there is nowhere to go back to
+        editor.run_edit_command(&jump_back);
+        assert_eq!(editor.insertion_point(), 5);
+
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::MoveLeft { select: false });
+        editor.run_edit_command(&jump_back);
+        assert_eq!(editor.insertion_point(), 5);
+        // Toggles between the two positions
+        editor.run_edit_command(&jump_back);
+        assert_eq!(editor.insertion_point(), 10);
+        editor.run_edit_command(&EditCommand::MoveToMarkLine {
+            mark: '\'',
+            select: false,
+        });
+        assert_eq!(editor.insertion_point(), 4);
+    }
+
+    #[test]
+    fn test_jump_mark_clamps_to_shorter_buffer() {
+        let mut editor = editor_with("abc def");
+        editor.run_edit_command(&EditCommand::MoveToEnd { select: false });
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::CutToEnd);
+
+        editor.run_edit_command(&EditCommand::MoveToMark {
+            mark: '`',
+            select: false,
+        });
+
+        assert_eq!(editor.get_buffer(), "");
+        assert_eq!(editor.insertion_point(), 0);
+    }
+
+    #[test]
+    fn test_cut_to_mark_line() {
+        let mut editor = editor_with("one\ntwo\nthree");
+        editor.line_buffer.set_insertion_point(5);
+        editor.run_edit_command(&EditCommand::SetMark('a'));
+        editor.line_buffer.set_insertion_point(1);
+
+        editor.run_edit_command(&EditCommand::MoveToMarkLine {
+            mark: 'a',
+            select: true,
+        });
+        editor.run_edit_command(&EditCommand::CutSelectedLines);
+
+        assert_eq!(editor.get_buffer(), "three");
     }
 
     fn str_to_edit_commands(s: &str) -> Vec<EditCommand> {
         s.chars().map(EditCommand::InsertChar).collect()
     }
 
+    #[test]
+    fn test_redo_after_undo() {
+        let mut editor = editor_with("This is");
+        for cmd in str_to_edit_commands(" a test") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "This is");
+        // Moving around keeps the undone edits
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "This is a");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "This is a test");
+        // Nothing left to redo
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "This is a test");
+    }
+
+    #[test]
+    fn test_edit_after_undo_drops_redo() {
+        let mut editor = editor_with("This is");
+        for cmd in str_to_edit_commands(" a test") {
+            editor.run_edit_command(&cmd);
+        }
+        editor.run_edit_command(&EditCommand::Undo);
+        editor.run_edit_command(&EditCommand::InsertChar('!'));
+        assert_eq!(editor.get_buffer(), "This is a!");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "This is a!");
+    }
+
     #[test]
     fn test_undo_insert_works_on_work_boundaries() {
         let mut editor = editor_with("This is  a");
@@ -766,6 +2781,45 @@ mod test {
         assert_eq!(editor.get_buffer(), "This is a");
     }
 
+    #[test]
+    fn test_undo_inserted_string_in_one_step() {
+        // How a bracketed paste ends up in the buffer
+        let mut editor = editor_with("ls");
+        editor.run_edit_command(&EditCommand::InsertString(" a\n b \n\nc".to_string()));
+        editor.run_edit_command(&EditCommand::InsertChar('d'));
+        assert_eq!(editor.get_buffer(), "ls a\n b \n\ncd");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls a\n b \n\nc");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "ls");
+    }
+
+    #[test]
+    fn test_undo_group() {
+        let mut editor = editor_with("foo bar");
+        editor.run_edit_command(&EditCommand::BeginUndoGroup);
+        editor.run_edit_command(&EditCommand::MoveToStart { select: false });
+        editor.run_edit_command(&EditCommand::CutWordRight);
+        for cmd in str_to_edit_commands("baz qux") {
+            editor.run_edit_command(&cmd);
+        }
+        // Groups don't nest
+        editor.run_edit_command(&EditCommand::BeginUndoGroup);
+        editor.run_edit_command(&EditCommand::Delete);
+        editor.run_edit_command(&EditCommand::EndUndoGroup);
+        for cmd in str_to_edit_commands("!") {
+            editor.run_edit_command(&cmd);
+        }
+        assert_eq!(editor.get_buffer(), "baz qux!bar");
+
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "baz quxbar");
+        editor.run_edit_command(&EditCommand::Undo);
+        assert_eq!(editor.get_buffer(), "foo bar");
+        editor.run_edit_command(&EditCommand::Redo);
+        assert_eq!(editor.get_buffer(), "baz quxbar");
+    }
+
     #[test]
     fn test_undo_backspace_with_newline() {
         let mut editor = editor_with("This is a \n test");
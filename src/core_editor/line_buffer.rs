@@ -1,4 +1,5 @@
 use {
+    crate::enums::{TextObject, TextObjectKind, WordCharacters},
     itertools::Itertools,
     std::{convert::From, ops::Range},
     unicode_segmentation::UnicodeSegmentation,
@@ -9,6 +10,7 @@ use {
 pub struct LineBuffer {
     lines: String,
     insertion_point: usize,
+    word_characters: WordCharacters,
 }
 
 impl From<&str> for LineBuffer {
@@ -90,6 +92,21 @@ impl LineBuffer {
         self.lines[..self.insertion_point].matches('\n').count()
     }
 
+    /// Move the cursor to the start of the zero-based `line`, or of the last line if
+    /// there are fewer lines
+    pub fn move_to_line(&mut self, line: usize) {
+        self.insertion_point = match line.checked_sub(1) {
+            None => 0,
+            Some(newline) => self
+                .lines
+                .match_indices('\n')
+                .nth(newline)
+                .map(|(i, _)| i)
+                .or_else(|| self.lines.rfind('\n'))
+                .map_or(0, |i| i + 1),
+        };
+    }
+
     /// Counts the number of lines in the buffer
     pub fn num_lines(&self) -> usize {
         self.lines.split('\n').count()
@@ -168,10 +185,67 @@ impl LineBuffer {
             .unwrap_or(0)
     }
 
+    /// Set the characters changing the word boundaries of the word motions
+    pub fn set_word_characters(&mut self, word_characters: WordCharacters) {
+        self.word_characters = word_characters;
+    }
+
+    /// Split `text` at the word boundaries, adjusted by the configured word characters
+    ///
+    /// Separators become segments of their own. Word characters join the segments of letters,
+    /// digits and word characters next to them.
+    fn word_bound_indices<'a>(&self, text: &'a str) -> std::vec::IntoIter<(usize, &'a str)> {
+        let word_characters = &self.word_characters;
+        if word_characters.word.is_empty() && word_characters.separators.is_empty() {
+            return text
+                .split_word_bound_indices()
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+        let is_word = |segment: &str| {
+            segment.chars().all(|c| {
+                !word_characters.separators.contains(c)
+                    && (c.is_alphanumeric() || c == '_' || word_characters.word.contains(c))
+            })
+        };
+        let has_word_character =
+            |segment: &str| segment.contains(|c| word_characters.word.contains(c));
+
+        let mut segments: Vec<(usize, &str)> = Vec::new();
+        let mut push = |start: usize, end: usize| {
+            let segment = &text[start..end];
+            match segments.last_mut() {
+                Some((last_start, last))
+                    if is_word(last)
+                        && is_word(segment)
+                        && (has_word_character(last) || has_word_character(segment)) =>
+                {
+                    *last = &text[*last_start..end];
+                }
+                _ => segments.push((start, segment)),
+            }
+        };
+        for (i, segment) in text.split_word_bound_indices() {
+            let mut start = i;
+            for (j, c) in segment.char_indices() {
+                if word_characters.separators.contains(c) {
+                    if start < i + j {
+                        push(start, i + j);
+                    }
+                    push(i + j, i + j + c.len_utf8());
+                    start = i + j + c.len_utf8();
+                }
+            }
+            if start < i + segment.len() {
+                push(start, i + segment.len());
+            }
+        }
+        segments.into_iter()
+    }
+
     /// Cursor position *behind* the next word to the right
     pub fn word_right_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_bound_indices(&self.lines[self.insertion_point..])
             .find(|(_, word)| !is_whitespace_str(word))
             .map(|(i, word)| self.insertion_point + i + word.len())
             .unwrap_or_else(|| self.lines.len())
@@ -193,8 +267,7 @@ impl LineBuffer {
 
     /// Cursor position *at end of* the next word to the right
     pub fn word_right_end_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_bound_indices(&self.lines[self.insertion_point..])
             .find_map(|(i, word)| {
                 word.grapheme_indices(true)
                     .next_back()
@@ -235,10 +308,35 @@ impl LineBuffer {
             })
     }
 
+    /// Cursor position *at end of* the previous word to the left
+    pub fn word_left_end_index(&self) -> usize {
+        // The word under the cursor doesn't end before it
+        self.word_bound_indices(&self.lines)
+            .take_while(|(i, word)| i + word.len() <= self.insertion_point)
+            .filter(|(_, word)| !is_whitespace_str(word))
+            .last()
+            .and_then(|(i, word)| word.grapheme_indices(true).next_back().map(|x| i + x.0))
+            .unwrap_or(0)
+    }
+
+    /// Cursor position *at end of* the previous WORD to the left
+    pub fn big_word_left_end_index(&self) -> usize {
+        let in_word = self.lines[self.insertion_point..]
+            .graphemes(true)
+            .next()
+            .map_or(false, |grapheme| !is_whitespace_str(grapheme));
+        self.lines[..self.insertion_point]
+            .grapheme_indices(true)
+            .rev()
+            .skip_while(|(_, grapheme)| in_word && !is_whitespace_str(grapheme))
+            .find(|(_, grapheme)| !is_whitespace_str(grapheme))
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+
     /// Cursor position *in front of* the next word to the right
     pub fn word_right_start_index(&self) -> usize {
-        self.lines[self.insertion_point..]
-            .split_word_bound_indices()
+        self.word_bound_indices(&self.lines[self.insertion_point..])
             .find(|(i, word)| *i != 0 && !is_whitespace_str(word))
             .map(|(i, _)| self.insertion_point + i)
             .unwrap_or_else(|| self.lines.len())
@@ -260,8 +358,7 @@ impl LineBuffer {
 
     /// Cursor position *in front of* the next word to the left
     pub fn word_left_index(&self) -> usize {
-        self.lines[..self.insertion_point]
-            .split_word_bound_indices()
+        self.word_bound_indices(&self.lines[..self.insertion_point])
             .filter(|(_, word)| !is_whitespace_str(word))
             .last()
             .map(|(i, _)| i)
@@ -371,6 +468,108 @@ impl LineBuffer {
         self.insert_char('\n');
     }
 
+    /// Open a new line below the current one, or `above` it, and move the cursor onto it.
+    ///
+    /// The new line gets the indentation of the current line.
+    pub fn open_line(&mut self, above: bool) {
+        let line_start = self.current_line_range().start;
+        let rest = &self.lines[line_start..];
+        let indent_len = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+        let indent = self.lines[line_start..line_start + indent_len].to_string();
+
+        if above {
+            self.insertion_point = line_start;
+            self.insert_newline();
+            self.insertion_point = line_start;
+        } else {
+            self.insertion_point = self.find_current_line_end();
+            self.insert_newline();
+        }
+        self.insert_str(&indent);
+    }
+
+    /// Insert `count` copies of the current line below it, at least one, and move onto the
+    /// first copy at the same column
+    ///
+    /// The copies are separated by the line break of the current line, or `\n` for the last
+    /// line.
+    pub fn duplicate_line(&mut self, count: usize) {
+        let line_range = self.current_line_range();
+        let line_end = self.find_current_line_end();
+        let line_break = match &self.lines[line_end..line_range.end] {
+            "" => "\n",
+            line_break => line_break,
+        };
+        let copy = format!("{}{}", line_break, &self.lines[line_range.start..line_end]);
+        let column = self.insertion_point - line_range.start;
+
+        let copy_start = line_end + line_break.len();
+
+        self.lines.insert_str(line_end, &copy.repeat(count.max(1)));
+        self.insertion_point = copy_start + column;
+    }
+
+    /// Swap the current line with the line above it and keep the cursor on the same column of
+    /// the moved line
+    ///
+    /// Does nothing on the first line.
+    pub fn swap_line_up(&mut self) {
+        let line_start = self.current_line_range().start;
+        if line_start == 0 {
+            return;
+        }
+        let column = self.insertion_point - line_start;
+        let above_start = self.lines[..line_start - 1]
+            .rfind('\n')
+            .map_or(0, |offset| offset + 1);
+
+        self.swap_with_next_line(above_start, line_start);
+        self.insertion_point = above_start + column;
+    }
+
+    /// Swap the current line with the line below it and keep the cursor on the same column of
+    /// the moved line
+    ///
+    /// Does nothing on the last line.
+    pub fn swap_line_down(&mut self) {
+        let line_range = self.current_line_range();
+        if !self.lines[line_range.clone()].ends_with('\n') {
+            return;
+        }
+        let column = self.insertion_point - line_range.start;
+
+        let moved_start = self.swap_with_next_line(line_range.start, line_range.end);
+        self.insertion_point = moved_start + column;
+    }
+
+    /// Swap the line starting at `upper_start` with the following line starting at
+    /// `lower_start`, leaving the line breaks in place
+    ///
+    /// Returns the new start of the former upper line.
+    fn swap_with_next_line(&mut self, upper_start: usize, lower_start: usize) -> usize {
+        let upper = &self.lines[upper_start..lower_start];
+        let upper_text = upper
+            .strip_suffix("\r\n")
+            .or_else(|| upper.strip_suffix('\n'))
+            .unwrap_or(upper);
+        let line_break = &upper[upper_text.len()..];
+
+        let lower_end = match self.lines[lower_start..].find('\n') {
+            Some(offset) if self.lines[..lower_start + offset].ends_with('\r') => {
+                lower_start + offset - 1
+            }
+            Some(offset) => lower_start + offset,
+            None => self.lines.len(),
+        };
+        let lower_text = &self.lines[lower_start..lower_end];
+
+        let moved_start = upper_start + lower_text.len() + line_break.len();
+        let swapped = format!("{}{}{}", lower_text, line_break, upper_text);
+        self.lines.replace_range(upper_start..lower_end, &swapped);
+
+        moved_start
+    }
+
     /// Empty buffer and reset cursor
     pub fn clear(&mut self) {
         self.lines = String::new();
@@ -389,6 +588,36 @@ impl LineBuffer {
         self.clear_range(self.insertion_point..self.find_current_line_end());
     }
 
+    /// Join the next line onto the current one, leaving the cursor at the join point.
+    ///
+    /// With `smart` the line break and the indentation of the next line are replaced by a
+    /// single space like vi's `J` does. Does nothing on the last line.
+    pub fn join_lines(&mut self, smart: bool) {
+        if let Some(offset) = self.lines[self.insertion_point..].find('\n') {
+            let newline = self.insertion_point + offset;
+            let start = if self.lines[..newline].ends_with('\r') {
+                newline - 1
+            } else {
+                newline
+            };
+            let mut end = newline + 1;
+            let mut separator = "";
+            if smart {
+                let next_line = &self.lines[end..];
+                end += next_line.len() - next_line.trim_start_matches([' ', '\t']).len();
+                let rest = &self.lines[end..];
+                let needs_space = !rest.is_empty()
+                    && !rest.starts_with(['\n', '\r', ')'])
+                    && !self.lines[..start].ends_with([' ', '\t']);
+                if needs_space {
+                    separator = " ";
+                }
+            }
+            self.lines.replace_range(start..end, separator);
+            self.insertion_point = start;
+        }
+    }
+
     /// Clear from the start of the buffer to the cursor.
     /// Keeps the cursor at the beginning of the line/buffer.
     pub fn clear_to_insertion_point(&mut self) {
@@ -459,8 +688,8 @@ impl LineBuffer {
     /// Gets the range of the word the current edit position is pointing to
     pub fn current_word_range(&self) -> Range<usize> {
         let right_index = self.word_right_index();
-        let left_index = self.lines[..right_index]
-            .split_word_bound_indices()
+        let left_index = self
+            .word_bound_indices(&self.lines[..right_index])
             .filter(|(_, word)| !is_whitespace_str(word))
             .last()
             .map(|(i, _)| i)
@@ -469,6 +698,330 @@ impl LineBuffer {
         left_index..right_index
     }
 
+    /// Range covered by the vi text object `text_object` around the insertion point
+    ///
+    /// Returns `None` if the cursor is not inside such an object
+    pub fn text_object_range(&self, text_object: TextObject) -> Option<Range<usize>> {
+        let TextObject { around, kind } = text_object;
+        match kind {
+            TextObjectKind::Word => self.word_object_range(around),
+            TextObjectKind::BigWord => self.big_word_object_range(around),
+            TextObjectKind::Parentheses => self.pair_object_range('(', ')', around),
+            TextObjectKind::Brackets => self.pair_object_range('[', ']', around),
+            TextObjectKind::Braces => self.pair_object_range('{', '}', around),
+            TextObjectKind::DoubleQuote => self.quote_object_range('"', around),
+            TextObjectKind::SingleQuote => self.quote_object_range('\'', around),
+            TextObjectKind::Backtick => self.quote_object_range('`', around),
+            TextObjectKind::Paragraph => self.paragraph_object_range(around),
+        }
+    }
+
+    /// Range of the lines of the paragraph, or of the blank lines, at the insertion point
+    ///
+    /// Around also takes the following run of lines of the other kind, or the preceding one
+    /// at the end of the buffer, like vim's `ap`
+    fn paragraph_object_range(&self, around: bool) -> Option<Range<usize>> {
+        let mut start = 0;
+        let lines = self
+            .lines
+            .split_inclusive('\n')
+            .map(|line| {
+                let range = start..start + line.len();
+                start = range.end;
+                (range, line.trim().is_empty())
+            })
+            .collect::<Vec<_>>();
+        let current = lines
+            .iter()
+            .rposition(|(range, _)| range.start <= self.insertion_point)?;
+        let blank = lines[current].1;
+
+        let run_start = |mut first: usize, blank: bool| {
+            while first > 0 && lines[first - 1].1 == blank {
+                first -= 1;
+            }
+            first
+        };
+        let run_end = |mut last: usize, blank: bool| {
+            while last + 1 < lines.len() && lines[last + 1].1 == blank {
+                last += 1;
+            }
+            last
+        };
+        let mut first = run_start(current, blank);
+        let mut last = run_end(current, blank);
+        if around {
+            if last + 1 < lines.len() {
+                last = run_end(last + 1, !blank);
+            } else if first > 0 {
+                first = run_start(first - 1, !blank);
+            }
+        }
+
+        Some(lines[first].0.start..lines[last].0.end)
+    }
+
+    /// Extend an inner word `range` by the whitespace behind it, or in front of it if there is none
+    ///
+    /// When `range` itself is whitespace the following word gets included instead
+    fn extend_word_object(&self, range: Range<usize>, segments: &[Range<usize>]) -> Range<usize> {
+        let is_ws = |r: &Range<usize>| is_whitespace_str(&self.lines[r.clone()]);
+        let position = segments.iter().position(|r| *r == range);
+        let next = position.and_then(|p| segments.get(p + 1));
+        let previous = position.and_then(|p| p.checked_sub(1).map(|p| &segments[p]));
+
+        match (is_ws(&range), next, previous) {
+            (true, Some(next), _) => range.start..next.end,
+            (false, Some(next), _) if is_ws(next) => range.start..next.end,
+            (false, _, Some(previous)) if is_ws(previous) => previous.start..range.end,
+            _ => range,
+        }
+    }
+
+    fn word_object_range(&self, around: bool) -> Option<Range<usize>> {
+        let segments = self
+            .word_bound_indices(&self.lines)
+            .map(|(i, word)| i..i + word.len())
+            .collect::<Vec<_>>();
+        let range = segments
+            .iter()
+            .find(|r| r.contains(&self.insertion_point))
+            .or_else(|| segments.last())?
+            .clone();
+
+        Some(if around {
+            self.extend_word_object(range, &segments)
+        } else {
+            range
+        })
+    }
+
+    fn big_word_object_range(&self, around: bool) -> Option<Range<usize>> {
+        // Merge the unicode word segments into alternating runs of whitespace and WORDs
+        let mut segments: Vec<Range<usize>> = Vec::new();
+        for (i, word) in self.lines.split_word_bound_indices() {
+            let range = i..i + word.len();
+            match segments.last_mut() {
+                Some(last)
+                    if is_whitespace_str(&self.lines[last.clone()]) == is_whitespace_str(word) =>
+                {
+                    last.end = range.end;
+                }
+                _ => segments.push(range),
+            }
+        }
+        let range = segments
+            .iter()
+            .find(|r| r.contains(&self.insertion_point))
+            .or_else(|| segments.last())?
+            .clone();
+
+        Some(if around {
+            self.extend_word_object(range, &segments)
+        } else {
+            range
+        })
+    }
+
+    /// Find the innermost `open`/`close` pair enclosing the insertion point, respecting nesting
+    fn pair_object_range(&self, open: char, close: char, around: bool) -> Option<Range<usize>> {
+        let cursor_end = self.grapheme_right_index();
+        let mut depth = 0;
+        let open_index = self.lines[..cursor_end]
+            .char_indices()
+            .rev()
+            .find(|(i, c)| {
+                if *c == close && *i != self.insertion_point {
+                    depth += 1;
+                } else if *c == open {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            })
+            .map(|(i, _)| i)?;
+
+        let content_start = open_index + open.len_utf8();
+        let mut depth = 0;
+        let close_index = self.lines[content_start..]
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == open {
+                    depth += 1;
+                } else if *c == close {
+                    if depth == 0 {
+                        return true;
+                    }
+                    depth -= 1;
+                }
+                false
+            })
+            .map(|(i, _)| content_start + i)?;
+
+        Some(if around {
+            open_index..close_index + close.len_utf8()
+        } else {
+            // Like vim, a block spanning lines keeps the line break after the opening
+            // delimiter and the indentation of the closing one
+            let close_line_start = self.lines[..close_index].rfind('\n');
+            match close_line_start {
+                Some(newline)
+                    if newline >= content_start
+                        && self.lines[content_start..].starts_with('\n')
+                        && self.lines[newline..close_index].trim().is_empty() =>
+                {
+                    content_start + 1..newline.max(content_start + 1)
+                }
+                _ => content_start..close_index,
+            }
+        })
+    }
+
+    /// Index of the bracket matching the first of `()`, `[]` or `{}` at or behind the
+    /// insertion point on the current line (vi `%`)
+    ///
+    /// Returns `None` if there is no bracket ahead on the line or it has no partner
+    pub fn matching_bracket_index(&self) -> Option<usize> {
+        const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+        let line_end = self.current_line_range().end;
+        let (bracket_index, bracket) = self.lines[self.insertion_point..line_end]
+            .char_indices()
+            .find(|(_, c)| PAIRS.iter().any(|(open, close)| c == open || c == close))
+            .map(|(i, c)| (self.insertion_point + i, c))?;
+
+        let mut depth = 0;
+        let mut is_partner = |c: char, same: char, partner: char| {
+            if c == same {
+                depth += 1;
+            } else if c == partner {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            false
+        };
+
+        if let Some(&(open, close)) = PAIRS.iter().find(|(open, _)| *open == bracket) {
+            let content_start = bracket_index + open.len_utf8();
+            self.lines[content_start..]
+                .char_indices()
+                .find(|(_, c)| is_partner(*c, open, close))
+                .map(|(i, _)| content_start + i)
+        } else {
+            let &(open, close) = PAIRS.iter().find(|(_, close)| *close == bracket)?;
+            self.lines[..bracket_index]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| is_partner(*c, close, open))
+                .map(|(i, _)| i)
+        }
+    }
+
+    /// Start of the next sentence after the insertion point, or the end of the buffer (vi `)`)
+    pub fn sentence_right_index(&self) -> usize {
+        self.sentence_starts()
+            .into_iter()
+            .find(|&start| start > self.insertion_point)
+            .unwrap_or(self.lines.len())
+    }
+
+    /// Start of the sentence before the insertion point, or the start of the buffer (vi `(`)
+    pub fn sentence_left_index(&self) -> usize {
+        self.sentence_starts()
+            .into_iter()
+            .take_while(|&start| start < self.insertion_point)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Indices where sentences start
+    ///
+    /// A sentence ends with `.`, `!` or `?` followed by whitespace. Empty lines count as
+    /// sentences of their own.
+    fn sentence_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::new();
+        // The next non-whitespace character starts a sentence
+        let mut boundary = true;
+        let mut previous = '\n';
+        for (i, c) in self.lines.char_indices() {
+            if c == '\n' && previous == '\n' {
+                starts.push(i);
+                boundary = true;
+            } else if c.is_whitespace() {
+                boundary |= matches!(previous, '.' | '!' | '?');
+            } else if boundary {
+                starts.push(i);
+                boundary = false;
+            }
+            previous = c;
+        }
+        starts
+    }
+
+    /// Start of the next empty line after the current paragraph, or the end of the buffer
+    /// (vi `}`)
+    pub fn paragraph_right_index(&self) -> usize {
+        self.empty_line_indices()
+            .find(|&i| {
+                i > self.insertion_point
+                    && !matches!(self.lines[..i - 1].chars().last(), None | Some('\n'))
+            })
+            .unwrap_or(self.lines.len())
+    }
+
+    /// Start of the empty line before the current paragraph, or the start of the buffer
+    /// (vi `{`)
+    pub fn paragraph_left_index(&self) -> usize {
+        self.empty_line_indices()
+            .filter(|&i| {
+                i < self.insertion_point
+                    && !matches!(self.lines[i + 1..].chars().next(), None | Some('\n'))
+            })
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Start indices of the empty lines in the buffer
+    fn empty_line_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines
+            .match_indices('\n')
+            .map(|(i, _)| i)
+            .filter(move |&i| i == 0 || self.lines[..i].ends_with('\n'))
+    }
+
+    /// Find the pair of `quote` characters on the current line enclosing the insertion point
+    ///
+    /// Quotes are paired up from the start of the line, escaped quotes are skipped
+    fn quote_object_range(&self, quote: char, around: bool) -> Option<Range<usize>> {
+        let line_range = self.current_line_range();
+        let line = &self.lines[line_range.clone()];
+        let mut escaped = false;
+        let quotes = line
+            .char_indices()
+            .filter(|(_, c)| {
+                let is_quote = *c == quote && !escaped;
+                escaped = *c == '\\' && !escaped;
+                is_quote
+            })
+            .map(|(i, _)| line_range.start + i)
+            .collect::<Vec<_>>();
+
+        // Like vim, the pair enclosing the cursor or else the next one on the line
+        let (start, end) = quotes
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|(_, end)| self.insertion_point <= *end)?;
+
+        Some(if around {
+            start..end + quote.len_utf8()
+        } else {
+            start + quote.len_utf8()..end
+        })
+    }
+
     /// Range over the current line
     ///
     /// Starts on the first non-newline character and is an exclusive range
@@ -485,6 +1038,24 @@ impl LineBuffer {
         left_index..right_index
     }
 
+    /// Range over the current line and the following lines indented deeper than it, up to
+    /// the start of the next line at the same or a lesser indentation or the end of the buffer
+    ///
+    /// Blank lines belong to the block. A tab counts as one level of `indent`.
+    pub fn indent_block_range(&self, indent: &str) -> Range<usize> {
+        let line = self.current_line_range();
+        let block_indent = indent_width(&self.lines[line.clone()], indent);
+        let mut end = line.end;
+        for next_line in self.lines[line.end..].split_inclusive('\n') {
+            if !next_line.trim().is_empty() && indent_width(next_line, indent) <= block_indent {
+                break;
+            }
+            end += next_line.len();
+        }
+
+        line.start..end
+    }
+
     /// Uppercases the current word
     pub fn uppercase_word(&mut self) {
         let change_range = self.current_word_range();
@@ -542,6 +1113,54 @@ impl LineBuffer {
         }
     }
 
+    /// Uppercases the current line
+    pub fn uppercase_line(&mut self) {
+        self.change_current_line(str::to_uppercase);
+    }
+
+    /// Lowercases the current line
+    pub fn lowercase_line(&mut self) {
+        self.change_current_line(str::to_lowercase);
+    }
+
+    /// Capitalizes every word of the current line, lowercasing the rest
+    pub fn capitalize_line(&mut self) {
+        self.change_current_line(|line| {
+            line.split_word_bounds()
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| {
+                            first
+                                .to_uppercase()
+                                .chain(chars.flat_map(char::to_lowercase))
+                                .collect::<String>()
+                        })
+                        .unwrap_or_default()
+                })
+                .collect()
+        });
+    }
+
+    /// Replaces the text of the current line by the result of `change`, keeping the insertion
+    /// point on the same grapheme column or at the end of a shorter line
+    fn change_current_line(&mut self, change: impl Fn(&str) -> String) {
+        let line_start = self.current_line_range().start;
+        let line_end = self.find_current_line_end();
+        let column = self.lines[line_start..self.insertion_point]
+            .graphemes(true)
+            .count();
+        let changed = change(&self.lines[line_start..line_end]);
+
+        self.replace_range(line_start..line_end, &changed);
+        self.insertion_point = line_start
+            + changed
+                .grapheme_indices(true)
+                .nth(column)
+                .map_or(changed.len(), |(i, _)| i);
+    }
+
     /// Deletes on grapheme to the left
     pub fn delete_left_grapheme(&mut self) {
         let left_index = self.grapheme_left_index();
@@ -615,6 +1234,75 @@ impl LineBuffer {
         }
     }
 
+    /// Swaps the grapheme left of the insertion point with the one right of it and moves
+    /// behind both, or swaps the last two graphemes of the line at its end
+    ///
+    /// Does nothing at the start of a line or on a line of a single grapheme
+    pub fn transpose_chars(&mut self) {
+        let line_start = self.current_line_range().start;
+        let line_end = self.find_current_line_end();
+        if self.insertion_point == line_start {
+            return;
+        }
+        let middle = if self.insertion_point == line_end {
+            self.grapheme_left_index()
+        } else {
+            self.insertion_point
+        };
+        let left_start = self.lines[..middle]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(middle, |(i, _)| i);
+        let right_end = self.lines[middle..line_end]
+            .graphemes(true)
+            .next()
+            .map_or(middle, |g| middle + g.len());
+        if left_start < line_start || left_start == middle || right_end == middle {
+            return;
+        }
+
+        let swapped = format!(
+            "{}{}",
+            &self.lines[middle..right_end],
+            &self.lines[left_start..middle]
+        );
+        self.replace_range(left_start..right_end, &swapped);
+        self.insertion_point = right_end;
+    }
+
+    /// Swaps the word left of or around the insertion point with the word right of it and
+    /// moves behind both, words being delimited like for the word motions
+    ///
+    /// Before the first word of the line swaps the first two words, after the last word the
+    /// last two. Does nothing on a line of less than two words.
+    pub fn transpose_words(&mut self) {
+        let line_start = self.current_line_range().start;
+        let line_end = self.find_current_line_end();
+        let words = self
+            .word_bound_indices(&self.lines[line_start..line_end])
+            .filter(|(_, word)| !is_whitespace_str(word))
+            .map(|(i, word)| line_start + i..line_start + i + word.len())
+            .collect::<Vec<_>>();
+        if words.len() < 2 {
+            return;
+        }
+        let started = words
+            .iter()
+            .filter(|word| word.start < self.insertion_point)
+            .count();
+        let second = started.clamp(1, words.len() - 1);
+        let (first, second) = (words[second - 1].clone(), words[second].clone());
+
+        let swapped = format!(
+            "{}{}{}",
+            &self.lines[second.clone()],
+            &self.lines[first.end..second.start],
+            &self.lines[first.clone()]
+        );
+        self.replace_range(first.start..second.end, &swapped);
+        self.insertion_point = second.end;
+    }
+
     /// Moves one line up
     pub fn move_line_up(&mut self) {
         if !self.is_cursor_at_first_line() {
@@ -774,6 +1462,15 @@ fn is_whitespace_str(s: &str) -> bool {
     s.chars().all(char::is_whitespace)
 }
 
+/// Width of the leading whitespace of `line`, a tab counting as one level of `indent`
+fn indent_width(line: &str, indent: &str) -> usize {
+    let level = indent.chars().filter(|c| *c == ' ').count().max(1);
+    line.chars()
+        .take_while(|c| matches!(c, ' ' | '\t'))
+        .map(|c| if c == '\t' { level } else { 1 })
+        .sum()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -951,6 +1648,70 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("straße\nnext", 2, "STRASSE\nnext", 2)]
+    #[case("a\nstraße", 8, "a\nSTRASSE", 7)]
+    #[case("a\nstraße", 9, "a\nSTRASSE", 8)]
+    #[case("", 0, "", 0)]
+    fn uppercase_line_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.uppercase_line();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("İstanbul", 2, "i\u{307}stanbul", 3)]
+    #[case("A\r\nBC\nD", 4, "A\r\nbc\nD", 4)]
+    #[case("", 0, "", 0)]
+    fn lowercase_line_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.lowercase_line();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("hello WORLD", 6, "Hello World", 6)]
+    #[case("ßa über-all", 2, "SSa Über-All", 1)]
+    #[case("", 0, "", 0)]
+    fn capitalize_line_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+        line_buffer.capitalize_line();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(expected, line_buffer);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("This is a test", 13, "This is a TEST", 14)]
     #[case("This is a test", 10, "This is a TEST", 14)]
@@ -1023,6 +1784,64 @@ mod test {
         line_buffer.assert_valid();
     }
 
+    #[rstest]
+    #[case("one two three", 1, "two one three", 7)]
+    #[case("one two three", 3, "two one three", 7)]
+    #[case("one two three", 4, "two one three", 7)]
+    #[case("one two three", 5, "one three two", 13)]
+    #[case("one two three", 13, "one three two", 13)]
+    #[case("  one two", 1, "  two one", 9)]
+    #[case("one", 1, "one", 1)]
+    #[case("one  two\nthree", 8, "two  one\nthree", 8)]
+    #[case("one\ntwo three", 4, "one\nthree two", 13)]
+    #[case("ein schöner Tag", 5, "ein Tag schöner", 16)]
+    fn transpose_words_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.transpose_words();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("abc", 0, "abc", 0)]
+    #[case("abc", 1, "bac", 2)]
+    #[case("abc", 2, "acb", 3)]
+    #[case("abc", 3, "acb", 3)]
+    #[case("ab\ncd", 3, "ab\ncd", 3)]
+    #[case("ab\ncd", 2, "ba\ncd", 2)]
+    #[case("ab\r\ncd", 2, "ba\r\ncd", 2)]
+    #[case("a\nb", 1, "a\nb", 1)]
+    #[case("aé😊", 1, "éa😊", 3)]
+    #[case("aé😊", 7, "a😊é", 7)]
+    fn transpose_chars_works(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] output: &str,
+        #[case] out_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.transpose_chars();
+
+        let mut expected = buffer_with(output);
+        expected.set_insertion_point(out_location);
+
+        assert_eq!(line_buffer, expected);
+        line_buffer.assert_valid();
+    }
+
     #[rstest]
     #[case("This is a test", 13, "This is a tets", 14)]
     #[case("This is a test", 14, "This is a tets", 14)] // NOTE: Swapping works in opposite direction at last index
@@ -1455,6 +2274,81 @@ mod test {
         assert_eq!(line_buffer.current_line_range(), expected);
     }
 
+    #[rstest]
+    #[case("if x\n  a\n  b\nfi", 0, "  ", 0..13)]
+    #[case("if x\n  a\n  b\nfi", 5, "  ", 5..9)]
+    #[case("a\n  b\n\n  c\nd", 0, "  ", 0..11)]
+    #[case("a\n  b", 0, "  ", 0..5)]
+    #[case("a\n  b\nc", 6, "  ", 6..7)]
+    #[case("\tx\n    y\n\tz", 0, "    ", 0..3)]
+    #[case("\tx\n    y\n\tz", 0, "  ", 0..9)]
+    fn test_indent_block_range(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] indent: &str,
+        #[case] expected: Range<usize>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(line_buffer.indent_block_range(indent), expected);
+    }
+
+    #[rstest]
+    #[case("hello world", 2, TextObjectKind::Word, false, Some(0..5))]
+    #[case("hello world", 2, TextObjectKind::Word, true, Some(0..6))]
+    #[case("hello world", 8, TextObjectKind::Word, true, Some(5..11))]
+    #[case("hello world", 5, TextObjectKind::Word, false, Some(5..6))]
+    #[case("hello world", 5, TextObjectKind::Word, true, Some(5..11))]
+    #[case("foo-bar baz", 1, TextObjectKind::Word, false, Some(0..3))]
+    #[case("foo-bar baz", 1, TextObjectKind::BigWord, false, Some(0..7))]
+    #[case("foo-bar baz", 1, TextObjectKind::BigWord, true, Some(0..8))]
+    #[case("f(a, (b), c)", 3, TextObjectKind::Parentheses, false, Some(2..11))]
+    #[case("f(a, (b), c)", 3, TextObjectKind::Parentheses, true, Some(1..12))]
+    #[case("f(a, (b), c)", 6, TextObjectKind::Parentheses, false, Some(6..7))]
+    #[case("f(a, (b), c)", 7, TextObjectKind::Parentheses, true, Some(5..8))]
+    #[case("f(a, (b), c)", 9, TextObjectKind::Parentheses, false, Some(2..11))]
+    #[case("f(a, (b), c)", 0, TextObjectKind::Parentheses, false, None)]
+    #[case("{ [x] }", 3, TextObjectKind::Brackets, false, Some(3..4))]
+    #[case("{ [x] }", 3, TextObjectKind::Braces, true, Some(0..7))]
+    #[case(r#"a "bc" d"#, 3, TextObjectKind::DoubleQuote, false, Some(3..5))]
+    #[case(r#"a "bc" d"#, 2, TextObjectKind::DoubleQuote, true, Some(2..6))]
+    #[case(r#"a "b\"c" d"#, 3, TextObjectKind::DoubleQuote, false, Some(3..7))]
+    #[case(r#"a "bc" d"#, 0, TextObjectKind::DoubleQuote, false, Some(3..5))]
+    #[case("'x' y 'z'", 4, TextObjectKind::SingleQuote, false, Some(7..8))]
+    #[case("'x' y 'z'", 4, TextObjectKind::SingleQuote, true, Some(6..9))]
+    #[case(r#"a "bc" d"#, 7, TextObjectKind::DoubleQuote, false, None)]
+    #[case(r#"a "bc"#, 0, TextObjectKind::DoubleQuote, false, None)]
+    #[case("\"a\"\nb", 4, TextObjectKind::DoubleQuote, false, None)]
+    #[case("`x`", 1, TextObjectKind::Backtick, false, Some(1..2))]
+    #[case("f {\n    a\n    b\n}", 8, TextObjectKind::Braces, false, Some(4..15))]
+    #[case("f {\n    a\n    b\n}", 8, TextObjectKind::Braces, true, Some(2..17))]
+    #[case("f { {\n  a\n  }\n}", 8, TextObjectKind::Braces, false, Some(6..9))]
+    #[case("f(\n  a,\n  b)", 5, TextObjectKind::Parentheses, false, Some(2..11))]
+    #[case("{\n}", 0, TextObjectKind::Braces, false, Some(2..2))]
+    #[case("a\nb\n\nc\n", 2, TextObjectKind::Paragraph, false, Some(0..4))]
+    #[case("a\nb\n\nc\n", 2, TextObjectKind::Paragraph, true, Some(0..5))]
+    #[case("a\nb\n\nc\n", 4, TextObjectKind::Paragraph, false, Some(4..5))]
+    #[case("a\nb\n\nc\n", 4, TextObjectKind::Paragraph, true, Some(4..7))]
+    #[case("a\n\nc", 3, TextObjectKind::Paragraph, true, Some(2..4))]
+    #[case("a\n\nc", 4, TextObjectKind::Paragraph, false, Some(3..4))]
+    #[case("", 0, TextObjectKind::Paragraph, false, None)]
+    fn test_text_object_range(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] kind: TextObjectKind,
+        #[case] around: bool,
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        assert_eq!(
+            line_buffer.text_object_range(TextObject { around, kind }),
+            expected
+        );
+    }
+
     #[rstest]
     #[case("This is a test", 7, "This is", 7)]
     #[case("This is a test\nunrelated", 7, "This is\nunrelated", 7)]
@@ -1585,6 +2479,51 @@ mod test {
         assert_eq!(index, expected);
     }
 
+    #[rstest]
+    #[case("abc def ghi", 9, 6)]
+    #[case("abc def ghi", 8, 6)]
+    #[case("abc def ghi", 7, 6)]
+    #[case("abc def ghi", 5, 2)]
+    #[case("abc def", 2, 0)]
+    #[case("abc def", 0, 0)]
+    #[case("abc--def", 6, 4)]
+    #[case("abc--def", 4, 3)]
+    #[case("abc--def", 3, 2)]
+    #[case("abc  \n  def", 10, 2)]
+    fn test_word_left_end_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        let index = line_buffer.word_left_end_index();
+
+        assert_eq!(index, expected);
+    }
+
+    #[rstest]
+    #[case("abc def ghi", 9, 6)]
+    #[case("abc def ghi", 7, 6)]
+    #[case("abc--def ghi", 10, 7)]
+    #[case("abc--def ghi", 6, 0)]
+    #[case("a.b c-d", 6, 2)]
+    #[case("abc  \n  def", 10, 2)]
+    #[case("abc", 1, 0)]
+    fn test_big_word_left_end_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        let index = line_buffer.big_word_left_end_index();
+
+        assert_eq!(index, expected);
+    }
+
     #[rstest]
     #[case("abc def", 0, 3)]
     #[case("abc def ghi", 3, 7)]
@@ -1597,4 +2536,149 @@ mod test {
 
         assert_eq!(index, expected);
     }
+
+    #[rstest]
+    #[case("One. Two! Three?", 0, 5, 0)]
+    #[case("One. Two! Three?", 5, 10, 0)]
+    #[case("One. Two! Three?", 12, 16, 10)]
+    #[case("One.  Two", 2, 6, 0)]
+    #[case("a.b c", 4, 5, 0)]
+    #[case("One.\n\nTwo", 0, 5, 0)]
+    #[case("One.\n\nTwo", 5, 6, 0)]
+    #[case("One.\n\nTwo", 7, 9, 6)]
+    fn test_sentence_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected_right: usize,
+        #[case] expected_left: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.sentence_right_index(), expected_right);
+        assert_eq!(line_buffer.sentence_left_index(), expected_left);
+    }
+
+    #[rstest]
+    #[case("a\nb\n\nc\n\n\nd", 0, 4, 0)]
+    #[case("a\nb\n\nc\n\n\nd", 4, 7, 0)]
+    #[case("a\nb\n\nc\n\n\nd", 5, 7, 4)]
+    #[case("a\nb\n\nc\n\n\nd", 7, 10, 4)]
+    #[case("a\nb\n\nc\n\n\nd", 9, 10, 8)]
+    #[case("abc", 1, 3, 0)]
+    fn test_paragraph_index(
+        #[case] input: &str,
+        #[case] position: usize,
+        #[case] expected_right: usize,
+        #[case] expected_left: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(position);
+
+        assert_eq!(line_buffer.paragraph_right_index(), expected_right);
+        assert_eq!(line_buffer.paragraph_left_index(), expected_left);
+    }
+
+    #[rstest]
+    #[case("abc\n  def", 1, true, "abc def", 3)]
+    #[case("abc\n  def", 1, false, "abc  def", 3)]
+    #[case("abc \ndef", 0, true, "abc def", 4)]
+    #[case("abc\n\ndef", 0, true, "abc\ndef", 3)]
+    #[case("f(a\n)", 0, true, "f(a)", 3)]
+    #[case("abc\r\ndef", 0, true, "abc def", 3)]
+    #[case("a\nb\nc", 2, true, "a\nb c", 3)]
+    #[case("abc", 1, true, "abc", 1)]
+    fn test_join_lines(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] smart: bool,
+        #[case] expected: &str,
+        #[case] expected_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.join_lines(smart);
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_location);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("abc", 1, 1, "abc\nabc", 5)]
+    #[case("a\nbc\nd", 3, 1, "a\nbc\nbc\nd", 6)]
+    #[case("a\r\nb", 0, 2, "a\r\na\r\na\r\nb", 3)]
+    #[case("a\n", 2, 1, "a\n\n", 3)]
+    #[case("", 0, 0, "\n", 1)]
+    fn test_duplicate_line(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] count: usize,
+        #[case] expected: &str,
+        #[case] expected_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.duplicate_line(count);
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_location);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("abc\ndef\nghi", 1, true, "abc\ndef\nghi", 1)]
+    #[case("abc\ndef\nghi", 6, true, "def\nabc\nghi", 2)]
+    #[case("abc\ndef\nghi", 10, true, "abc\nghi\ndef", 6)]
+    #[case("abc\ndef\nghi", 1, false, "def\nabc\nghi", 5)]
+    #[case("abc\ndef\nghi", 6, false, "abc\nghi\ndef", 10)]
+    #[case("abc\ndef\nghi", 10, false, "abc\ndef\nghi", 10)]
+    #[case("ab\r\ncdef", 7, true, "cdef\r\nab", 3)]
+    #[case("ab\r\ncdef", 1, false, "cdef\r\nab", 7)]
+    #[case("abc\n", 2, false, "\nabc", 3)]
+    fn test_swap_line(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] up: bool,
+        #[case] expected: &str,
+        #[case] expected_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        if up {
+            line_buffer.swap_line_up();
+        } else {
+            line_buffer.swap_line_down();
+        }
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_location);
+        line_buffer.assert_valid();
+    }
+
+    #[rstest]
+    #[case("abc", 1, false, "abc\n", 4)]
+    #[case("abc", 1, true, "\nabc", 0)]
+    #[case("a\n  bc\nd", 5, false, "a\n  bc\n  \nd", 9)]
+    #[case("a\n  bc\nd", 5, true, "a\n  \n  bc\nd", 4)]
+    #[case("\tx", 0, false, "\tx\n\t", 4)]
+    fn test_open_line(
+        #[case] input: &str,
+        #[case] in_location: usize,
+        #[case] above: bool,
+        #[case] expected: &str,
+        #[case] expected_location: usize,
+    ) {
+        let mut line_buffer = buffer_with(input);
+        line_buffer.set_insertion_point(in_location);
+
+        line_buffer.open_line(above);
+
+        assert_eq!(line_buffer.get_buffer(), expected);
+        assert_eq!(line_buffer.insertion_point(), expected_location);
+        line_buffer.assert_valid();
+    }
 }
@@ -3,6 +3,10 @@ mod edit_stack;
 mod editor;
 mod line_buffer;
 
-pub(crate) use clip_buffer::{get_default_clipboard, Clipboard, ClipboardMode};
+pub(crate) use clip_buffer::{
+    get_default_clipboard, get_primary_selection_clipboard, Clipboard, FileBackedClipboard,
+    Registers,
+};
+pub use clip_buffer::{CallbackClipboard, ClipboardError, ClipboardMode, PasteSanitizer};
 pub use editor::Editor;
 pub use line_buffer::LineBuffer;
@@ -1,5 +1,5 @@
 use crate::{
-    enums::{ReedlineEvent, ReedlineRawEvent},
+    enums::{ReedlineEvent, ReedlineRawEvent, WordCharacters},
     PromptEditMode,
 };
 
@@ -13,4 +13,16 @@ pub trait EditMode: Send {
 
     /// What to display in the prompt indicator
     fn edit_mode(&self) -> PromptEditMode;
+
+    /// Called after a text object to cut, like the one of vi's `ci"`, wasn't found in the
+    /// buffer. Returns the event undoing what the edit mode did expecting the cut, if anything
+    fn handle_missed_text_object(&mut self) -> ReedlineEvent {
+        ReedlineEvent::None
+    }
+
+    /// The characters moving the word boundaries of the word motions and edits, set on the
+    /// editor when the edit mode is installed
+    fn word_characters(&self) -> WordCharacters {
+        WordCharacters::default()
+    }
 }
@@ -55,6 +55,7 @@ pub fn default_emacs_keybindings() -> Keybindings {
     kb.add_binding(KM::CONTROL, KC::Char('k'), edit_bind(EC::CutToEnd));
     kb.add_binding(KM::CONTROL, KC::Char('u'), edit_bind(EC::CutFromStart));
     kb.add_binding(KM::ALT, KC::Char('d'), edit_bind(EC::CutWordRight));
+    kb.add_binding(KM::ALT, KC::Char('y'), edit_bind(EC::YankPop));
     // Edits
     kb.add_binding(KM::CONTROL, KC::Char('t'), edit_bind(EC::SwapGraphemes));
 
@@ -168,9 +169,7 @@ impl EditMode for Emacs {
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
             Event::FocusGained => ReedlineEvent::None,
             Event::FocusLost => ReedlineEvent::None,
-            Event::Paste(body) => ReedlineEvent::Edit(vec![EditCommand::InsertString(
-                body.replace("\r\n", "\n").replace('\r', "\n"),
-            )]),
+            Event::Paste(body) => ReedlineEvent::Paste(body),
         }
     }
 
@@ -224,6 +223,15 @@ mod test {
         assert_eq!(result, ReedlineEvent::HistoryHintComplete);
     }
 
+    #[test]
+    fn paste_is_a_single_event() {
+        let mut emacs = Emacs::default();
+        let paste = ReedlineRawEvent::convert_from(Event::Paste("ls\r\ncd".to_string())).unwrap();
+        let result = emacs.parse_event(paste);
+
+        assert_eq!(result, ReedlineEvent::Paste("ls\r\ncd".to_string()));
+    }
+
     #[test]
     fn inserting_character_works() {
         let mut emacs = Emacs::default();
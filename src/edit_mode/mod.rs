@@ -8,4 +8,4 @@ pub use base::EditMode;
 pub use cursors::CursorConfig;
 pub use emacs::{default_emacs_keybindings, Emacs};
 pub use keybindings::Keybindings;
-pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi};
+pub use vi::{default_vi_insert_keybindings, default_vi_normal_keybindings, Vi, ViCharSearch};
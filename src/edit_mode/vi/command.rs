@@ -1,16 +1,51 @@
-use super::{motion::Motion, motion::ViCharSearch, parser::ReedlineOption};
+use super::{motion::Motion, motion::ViCharSearch, parser::ReedlineOption, ViMode};
 use crate::{EditCommand, ReedlineEvent, Vi};
 use std::iter::Peekable;
 
+/// Stands for `Ctrl-R` among the keys of a command, the control character the key types
+pub const REDO_KEY: char = '\u{12}';
+
 pub fn parse_command<'iter, I>(input: &mut Peekable<I>) -> Option<Command>
 where
-    I: Iterator<Item = &'iter char>,
+    I: Iterator<Item = &'iter char> + Clone,
 {
     match input.peek() {
+        Some('g') => {
+            // Only consume the `g` if it starts a command, leaving `gg` to the motions
+            let mut lookahead = input.clone();
+            let _ = lookahead.next();
+            let command = match lookahead.next() {
+                Some('J') => Command::JoinLines { smart: false },
+                Some('u') => Command::Lowercase,
+                Some('U') => Command::Uppercase,
+                Some('~') => Command::ToggleCase,
+                Some('i') => Command::ResumeInsert,
+                _ => return None,
+            };
+            let _ = input.next();
+            let _ = input.next();
+            Some(command)
+        }
+        Some('J') => {
+            let _ = input.next();
+            Some(Command::JoinLines { smart: true })
+        }
+        Some('>') => {
+            let _ = input.next();
+            Some(Command::Indent)
+        }
+        Some('<') => {
+            let _ = input.next();
+            Some(Command::Dedent)
+        }
         Some('d') => {
             let _ = input.next();
             Some(Command::Delete)
         }
+        Some('y') => {
+            let _ = input.next();
+            Some(Command::Yank)
+        }
         Some('p') => {
             let _ = input.next();
             Some(Command::PasteAfter)
@@ -31,6 +66,10 @@ where
             let _ = input.next();
             Some(Command::Undo)
         }
+        Some(&&REDO_KEY) => {
+            let _ = input.next();
+            Some(Command::Redo)
+        }
         Some('c') => {
             let _ = input.next();
             Some(Command::Change)
@@ -46,14 +85,51 @@ where
                 None => Some(Command::Incomplete),
             }
         }
+        Some('m') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::SetMark(*c)),
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('q') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::RecordMacro(*c)),
+                None => Some(Command::Incomplete),
+            }
+        }
+        Some('@') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => Some(Command::ReplayMacro(*c)),
+                None => Some(Command::Incomplete),
+            }
+        }
         Some('s') => {
             let _ = input.next();
             Some(Command::SubstituteCharWithInsert)
         }
+        Some('v') => {
+            let _ = input.next();
+            Some(Command::EnterViVisual)
+        }
+        Some('V') => {
+            let _ = input.next();
+            Some(Command::EnterViVisualLine)
+        }
         Some('?') => {
             let _ = input.next();
             Some(Command::HistorySearch)
         }
+        Some('n') => {
+            let _ = input.next();
+            Some(Command::RepeatSearch { reverse: false })
+        }
+        Some('N') => {
+            let _ = input.next();
+            Some(Command::RepeatSearch { reverse: true })
+        }
         Some('C') => {
             let _ = input.next();
             Some(Command::ChangeToLineEnd)
@@ -62,6 +138,10 @@ where
             let _ = input.next();
             Some(Command::DeleteToEnd)
         }
+        Some('Y') => {
+            let _ = input.next();
+            Some(Command::YankLines)
+        }
         Some('I') => {
             let _ = input.next();
             Some(Command::PrependToStart)
@@ -70,10 +150,22 @@ where
             let _ = input.next();
             Some(Command::AppendToEnd)
         }
+        Some('o') => {
+            let _ = input.next();
+            Some(Command::OpenLineBelow)
+        }
+        Some('O') => {
+            let _ = input.next();
+            Some(Command::OpenLineAbove)
+        }
         Some('S') => {
             let _ = input.next();
             Some(Command::RewriteCurrentLine)
         }
+        Some('R') => {
+            let _ = input.next();
+            Some(Command::EnterViReplace)
+        }
         Some('~') => {
             let _ = input.next();
             Some(Command::Switchcase)
@@ -91,21 +183,40 @@ pub enum Command {
     Incomplete,
     Delete,
     DeleteChar,
+    Yank,
     ReplaceChar(char),
+    SetMark(char),
+    RecordMacro(char),
+    ReplayMacro(char),
     SubstituteCharWithInsert,
     PasteAfter,
     PasteBefore,
     EnterViAppend,
     EnterViInsert,
+    EnterViReplace,
+    JoinLines { smart: bool },
+    EnterViVisual,
+    EnterViVisualLine,
     Undo,
+    Redo,
     ChangeToLineEnd,
     DeleteToEnd,
+    YankLines,
+    ResumeInsert,
     AppendToEnd,
     PrependToStart,
+    OpenLineBelow,
+    OpenLineAbove,
     RewriteCurrentLine,
     Change,
     HistorySearch,
+    RepeatSearch { reverse: bool },
     Switchcase,
+    Lowercase,
+    Uppercase,
+    ToggleCase,
+    Indent,
+    Dedent,
     RepeatLastAction,
 }
 
@@ -114,41 +225,212 @@ impl Command {
         match self {
             Command::Delete => Some('d'),
             Command::Change => Some('c'),
+            Command::Yank => Some('y'),
+            Command::Lowercase => Some('u'),
+            Command::Uppercase => Some('U'),
+            Command::ToggleCase => Some('~'),
+            Command::Indent => Some('>'),
+            Command::Dedent => Some('<'),
             _ => None,
         }
     }
 
     pub fn requires_motion(&self) -> bool {
-        matches!(self, Command::Delete | Command::Change)
+        matches!(
+            self,
+            Command::Delete
+                | Command::Change
+                | Command::Yank
+                | Command::Lowercase
+                | Command::Uppercase
+                | Command::ToggleCase
+                | Command::Indent
+                | Command::Dedent
+        )
+    }
+
+    /// Whether `.` repeats the command, which it only does for the commands changing the
+    /// buffer, not for yanks, mode switches or searches
+    pub fn is_repeatable(&self) -> bool {
+        !matches!(
+            self,
+            Command::Incomplete
+                | Command::Yank
+                | Command::YankLines
+                | Command::SetMark(_)
+                | Command::RecordMacro(_)
+                | Command::ReplayMacro(_)
+                | Command::EnterViVisual
+                | Command::EnterViVisualLine
+                | Command::Undo
+                | Command::Redo
+                | Command::HistorySearch
+                | Command::RepeatSearch { .. }
+                | Command::RepeatLastAction
+        )
+    }
+
+    /// The in-place transformation of the current line done by the doubled case operators
+    /// `guu` and `gUU`
+    pub fn transform_line(&self) -> Option<EditCommand> {
+        match self {
+            Command::Lowercase => Some(EditCommand::LowercaseLine),
+            Command::Uppercase => Some(EditCommand::UppercaseLine),
+            _ => None,
+        }
+    }
+
+    /// The in-place transformation of the selection done by the case and indent operators
+    pub fn transform_selection(&self, vi_state: &Vi) -> Option<EditCommand> {
+        match self {
+            Command::Lowercase => Some(EditCommand::LowercaseSelection),
+            Command::Uppercase => Some(EditCommand::UppercaseSelection),
+            Command::ToggleCase => Some(EditCommand::SwitchcaseSelection),
+            Command::Indent => Some(EditCommand::IndentLines(vi_state.indent.clone())),
+            Command::Dedent => Some(EditCommand::DedentLines(vi_state.indent.clone())),
+            _ => None,
+        }
     }
 
     pub fn to_reedline(&self, vi_state: &mut Vi) -> Vec<ReedlineOption> {
         match self {
-            Self::EnterViInsert => vec![ReedlineOption::Event(ReedlineEvent::Repaint)],
+            Self::EnterViInsert | Self::EnterViReplace => {
+                vec![ReedlineOption::Event(ReedlineEvent::Repaint)]
+            }
             Self::EnterViAppend => vec![ReedlineOption::Edit(EditCommand::MoveRight {
                 select: false,
             })],
+            // In visual mode `p` swaps the selection with the register
+            Self::PasteAfter if vi_state.mode == ViMode::Visual => {
+                vec![ReedlineOption::Edit(
+                    EditCommand::PasteCutBufferOverSelection,
+                )]
+            }
+            Self::PasteAfter if vi_state.mode == ViMode::VisualLine => vec![ReedlineOption::Edit(
+                EditCommand::PasteCutBufferOverSelectedLines,
+            )],
             Self::PasteAfter => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferAfter)],
             Self::PasteBefore => vec![ReedlineOption::Edit(EditCommand::PasteCutBufferBefore)],
             Self::Undo => vec![ReedlineOption::Edit(EditCommand::Undo)],
-            Self::ChangeToLineEnd => vec![ReedlineOption::Edit(EditCommand::ClearToLineEnd)],
+            Self::Redo => vec![ReedlineOption::Edit(EditCommand::Redo)],
+            // The commands entering insert mode repaint for the prompt to show the new mode
+            Self::ChangeToLineEnd => vec![
+                ReedlineOption::Edit(EditCommand::ClearToLineEnd),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
             Self::DeleteToEnd => vec![ReedlineOption::Edit(EditCommand::CutToLineEnd)],
-            Self::AppendToEnd => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd {
-                select: false,
-            })],
-            Self::PrependToStart => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart {
+            Self::YankLines => vec![ReedlineOption::Edit(EditCommand::CopyLines(1))],
+            Self::ResumeInsert => vec![ReedlineOption::Edit(EditCommand::MoveToMark {
+                mark: '^',
                 select: false,
             })],
-            Self::RewriteCurrentLine => vec![ReedlineOption::Edit(EditCommand::CutCurrentLine)],
+            Self::AppendToEnd => vec![
+                ReedlineOption::Edit(EditCommand::MoveToLineEnd { select: false }),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::PrependToStart => vec![
+                ReedlineOption::Edit(EditCommand::MoveToLineStart { select: false }),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::OpenLineBelow => {
+                vec![ReedlineOption::Edit(EditCommand::OpenLine { above: false })]
+            }
+            Self::OpenLineAbove => {
+                vec![ReedlineOption::Edit(EditCommand::OpenLine { above: true })]
+            }
+            Self::RewriteCurrentLine => vec![
+                ReedlineOption::Edit(EditCommand::ChangeLine {
+                    keep_indent: vi_state.autoindent,
+                }),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
             Self::DeleteChar => vec![ReedlineOption::Edit(EditCommand::CutChar)],
             Self::ReplaceChar(c) => {
                 vec![ReedlineOption::Edit(EditCommand::ReplaceChar(*c))]
             }
-            Self::SubstituteCharWithInsert => vec![ReedlineOption::Edit(EditCommand::CutChar)],
-            Self::HistorySearch => vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)],
+            Self::SetMark(c) => vec![ReedlineOption::Edit(EditCommand::SetMark(*c))],
+            Self::RecordMacro(register) => {
+                vi_state.start_recording(*register);
+                vec![]
+            }
+            Self::ReplayMacro(register) => {
+                vec![ReedlineOption::Event(vi_state.replay_macro(*register, 1))]
+            }
+            Self::SubstituteCharWithInsert => vec![
+                ReedlineOption::Edit(EditCommand::CutChar),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            // Like vim, the selection covers the character under the cursor
+            Self::EnterViVisual => vec![
+                ReedlineOption::Edit(EditCommand::StartInclusiveSelection),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::EnterViVisualLine => vec![
+                ReedlineOption::Edit(EditCommand::MoveToLineStart { select: false }),
+                ReedlineOption::Edit(EditCommand::MoveToLineEnd { select: true }),
+            ],
+            Self::HistorySearch => {
+                vi_state.search_input = Some(String::new());
+                vec![ReedlineOption::Event(ReedlineEvent::SearchHistory)]
+            }
+            Self::RepeatSearch { reverse } => vi_state
+                .last_search
+                .as_ref()
+                .map(|(term, backward)| {
+                    ReedlineOption::Event(ReedlineEvent::RepeatHistorySearch {
+                        term: term.clone(),
+                        backward: backward != reverse,
+                    })
+                })
+                .into_iter()
+                .collect(),
             Self::Switchcase => vec![ReedlineOption::Edit(EditCommand::SwitchcaseChar)],
+            Self::JoinLines { smart } => {
+                vec![ReedlineOption::Edit(EditCommand::JoinLines {
+                    smart: *smart,
+                })]
+            }
+            // In visual mode the operators act on the selection instead of a motion
+            Self::Delete if vi_state.mode == ViMode::Visual => {
+                vec![ReedlineOption::Edit(EditCommand::CutSelection)]
+            }
+            Self::Delete if vi_state.mode == ViMode::VisualLine => {
+                vec![ReedlineOption::Edit(EditCommand::CutSelectedLines)]
+            }
+            Self::Change if vi_state.mode == ViMode::Visual => vec![
+                ReedlineOption::Edit(EditCommand::CutSelection),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::Change if vi_state.mode == ViMode::VisualLine => vec![
+                ReedlineOption::Edit(EditCommand::CutSelectedLines),
+                ReedlineOption::Event(ReedlineEvent::Repaint),
+            ],
+            Self::Yank if vi_state.mode == ViMode::Visual => {
+                vec![ReedlineOption::Edit(EditCommand::CopySelection)]
+            }
+            Self::Yank if vi_state.mode == ViMode::VisualLine => {
+                vec![ReedlineOption::Edit(EditCommand::CopySelectedLines)]
+            }
+            Self::Lowercase | Self::Uppercase | Self::ToggleCase | Self::Indent | Self::Dedent
+                if vi_state.mode.is_visual() =>
+            {
+                self.transform_selection(vi_state)
+                    .map(ReedlineOption::Edit)
+                    .into_iter()
+                    .collect()
+            }
             // Mark a command as incomplete whenever a motion is required to finish the command
-            Self::Delete | Self::Change | Self::Incomplete => vec![ReedlineOption::Incomplete],
+            Self::Delete
+            | Self::Change
+            | Self::Yank
+            | Self::Lowercase
+            | Self::Uppercase
+            | Self::ToggleCase
+            | Self::Indent
+            | Self::Dedent
+            | Self::Incomplete => {
+                vec![ReedlineOption::Incomplete]
+            }
             Command::RepeatLastAction => match &vi_state.previous {
                 Some(event) => vec![ReedlineOption::Event(event.clone())],
                 None => vec![],
@@ -208,14 +490,65 @@ impl Command {
                     .last_char_search
                     .as_ref()
                     .map(|char_search| vec![ReedlineOption::Edit(char_search.reverse().to_cut())]),
+                Motion::TextObject { .. } => motion.text_object().map(|text_object| {
+                    vec![ReedlineOption::Edit(EditCommand::CutTextObject {
+                        text_object,
+                    })]
+                }),
+                Motion::FirstLine | Motion::LastLine(_) => {
+                    motion.select_line_target().map(|target| {
+                        vec![
+                            ReedlineOption::Edit(target),
+                            ReedlineOption::Edit(EditCommand::CutSelectedLines),
+                        ]
+                    })
+                }
+                Motion::MatchingBracket => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutToMatchingBracket,
+                )]),
+                Motion::IndentBlock => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CutIndentBlock(vi_state.indent.clone()),
+                )]),
+                Motion::WholeBuffer => Some(vec![ReedlineOption::Edit(EditCommand::CutAll)]),
+                Motion::PrevWordEnd | Motion::PrevBigWordEnd => {
+                    motion.select_prev_word_end().map(|selection| {
+                        selection
+                            .into_iter()
+                            .chain([EditCommand::CutSelection])
+                            .map(ReedlineOption::Edit)
+                            .collect()
+                    })
+                }
+                Motion::NextSentence
+                | Motion::PrevSentence
+                | Motion::NextParagraph
+                | Motion::PrevParagraph => motion.select_text_block_target().map(|target| {
+                    vec![
+                        ReedlineOption::Edit(target),
+                        ReedlineOption::Edit(EditCommand::CutSelection),
+                    ]
+                }),
+                Motion::ToMark(mark) => Some(vec![
+                    ReedlineOption::Edit(EditCommand::MoveToMark {
+                        mark: *mark,
+                        select: true,
+                    }),
+                    ReedlineOption::Edit(EditCommand::CutSelection),
+                ]),
+                Motion::ToMarkLine(mark) => Some(vec![
+                    ReedlineOption::Edit(EditCommand::MoveToMarkLine {
+                        mark: *mark,
+                        select: true,
+                    }),
+                    ReedlineOption::Edit(EditCommand::CutSelectedLines),
+                ]),
             },
             Self::Change => {
                 let op = match motion {
                     Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::ClearToLineEnd)]),
-                    Motion::Line => Some(vec![
-                        ReedlineOption::Edit(EditCommand::MoveToStart { select: false }),
-                        ReedlineOption::Edit(EditCommand::ClearToLineEnd),
-                    ]),
+                    Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::ChangeLine {
+                        keep_indent: vi_state.autoindent,
+                    })]),
                     Motion::NextWord => Some(vec![ReedlineOption::Edit(EditCommand::CutWordRight)]),
                     Motion::NextBigWord => {
                         Some(vec![ReedlineOption::Edit(EditCommand::CutBigWordRight)])
@@ -264,6 +597,56 @@ impl Command {
                             vec![ReedlineOption::Edit(char_search.reverse().to_cut())]
                         })
                     }
+                    Motion::TextObject { .. } => motion.text_object().map(|text_object| {
+                        vec![ReedlineOption::Edit(EditCommand::CutTextObject {
+                            text_object,
+                        })]
+                    }),
+                    Motion::FirstLine | Motion::LastLine(_) => {
+                        motion.select_line_target().map(|target| {
+                            vec![
+                                ReedlineOption::Edit(target),
+                                ReedlineOption::Edit(EditCommand::CutSelectedLines),
+                            ]
+                        })
+                    }
+                    Motion::MatchingBracket => Some(vec![ReedlineOption::Edit(
+                        EditCommand::CutToMatchingBracket,
+                    )]),
+                    Motion::IndentBlock => None,
+                    Motion::WholeBuffer => Some(vec![ReedlineOption::Edit(EditCommand::CutAll)]),
+                    Motion::PrevWordEnd | Motion::PrevBigWordEnd => {
+                        motion.select_prev_word_end().map(|selection| {
+                            selection
+                                .into_iter()
+                                .chain([EditCommand::CutSelection])
+                                .map(ReedlineOption::Edit)
+                                .collect()
+                        })
+                    }
+                    Motion::NextSentence
+                    | Motion::PrevSentence
+                    | Motion::NextParagraph
+                    | Motion::PrevParagraph => motion.select_text_block_target().map(|target| {
+                        vec![
+                            ReedlineOption::Edit(target),
+                            ReedlineOption::Edit(EditCommand::CutSelection),
+                        ]
+                    }),
+                    Motion::ToMark(mark) => Some(vec![
+                        ReedlineOption::Edit(EditCommand::MoveToMark {
+                            mark: *mark,
+                            select: true,
+                        }),
+                        ReedlineOption::Edit(EditCommand::CutSelection),
+                    ]),
+                    Motion::ToMarkLine(mark) => Some(vec![
+                        ReedlineOption::Edit(EditCommand::MoveToMarkLine {
+                            mark: *mark,
+                            select: true,
+                        }),
+                        ReedlineOption::Edit(EditCommand::CutSelectedLines),
+                    ]),
                 };
                 // Semihack: Append `Repaint` to ensure the mode change gets displayed
                 op.map(|mut vec| {
@@ -271,6 +654,107 @@ impl Command {
                     vec
                 })
             }
+            Self::Yank => match motion {
+                Motion::End => Some(vec![ReedlineOption::Edit(EditCommand::CopyToLineEnd)]),
+                Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyCurrentLine)]),
+                Motion::NextWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRightToNext)])
+                }
+                Motion::NextBigWord => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyBigWordRightToNext,
+                )]),
+                Motion::NextWordEnd => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordRight)]),
+                Motion::NextBigWordEnd => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordRight)])
+                }
+                Motion::PreviousWord => Some(vec![ReedlineOption::Edit(EditCommand::CopyWordLeft)]),
+                Motion::PreviousBigWord => {
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyBigWordLeft)])
+                }
+                Motion::RightUntil(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::ToRight(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightUntil(*c))])
+                }
+                Motion::RightBefore(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::TillRight(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyRightBefore(*c))])
+                }
+                Motion::LeftUntil(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::ToLeft(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftUntil(*c))])
+                }
+                Motion::LeftBefore(c) => {
+                    vi_state.last_char_search = Some(ViCharSearch::TillLeft(*c));
+                    Some(vec![ReedlineOption::Edit(EditCommand::CopyLeftBefore(*c))])
+                }
+                Motion::Start => Some(vec![ReedlineOption::Edit(EditCommand::CopyFromLineStart)]),
+                Motion::Left => Some(vec![ReedlineOption::Edit(EditCommand::CopyLeft)]),
+                Motion::Right => Some(vec![ReedlineOption::Edit(EditCommand::CopyRight)]),
+                Motion::Up | Motion::Down => {
+                    motion.to_selection(vi_state, 1).map(|mut selection| {
+                        selection.push(ReedlineOption::Edit(EditCommand::YankSelectedLines));
+                        selection
+                    })
+                }
+                Motion::ReplayCharSearch => vi_state
+                    .last_char_search
+                    .as_ref()
+                    .map(|char_search| vec![ReedlineOption::Edit(char_search.to_copy())]),
+                Motion::ReverseCharSearch => vi_state
+                    .last_char_search
+                    .as_ref()
+                    .map(|char_search| vec![ReedlineOption::Edit(char_search.reverse().to_copy())]),
+                Motion::TextObject { .. } => motion.text_object().map(|text_object| {
+                    vec![ReedlineOption::Edit(EditCommand::CopyTextObject {
+                        text_object,
+                    })]
+                }),
+                Motion::FirstLine | Motion::LastLine(_) => {
+                    motion.select_line_target().map(|target| {
+                        vec![
+                            ReedlineOption::Edit(target),
+                            ReedlineOption::Edit(EditCommand::YankSelectedLines),
+                        ]
+                    })
+                }
+                Motion::MatchingBracket => Some(vec![ReedlineOption::Edit(
+                    EditCommand::CopyToMatchingBracket,
+                )]),
+                Motion::IndentBlock => None,
+                Motion::WholeBuffer => Some(vec![ReedlineOption::Edit(EditCommand::CopyAll)]),
+                Motion::PrevWordEnd | Motion::PrevBigWordEnd => {
+                    motion.select_prev_word_end().map(|selection| {
+                        selection
+                            .into_iter()
+                            .chain([EditCommand::YankSelection])
+                            .map(ReedlineOption::Edit)
+                            .collect()
+                    })
+                }
+                Motion::NextSentence
+                | Motion::PrevSentence
+                | Motion::NextParagraph
+                | Motion::PrevParagraph => motion.select_text_block_target().map(|target| {
+                    vec![
+                        ReedlineOption::Edit(target),
+                        ReedlineOption::Edit(EditCommand::YankSelection),
+                    ]
+                }),
+                Motion::ToMark(mark) => Some(vec![
+                    ReedlineOption::Edit(EditCommand::MoveToMark {
+                        mark: *mark,
+                        select: true,
+                    }),
+                    ReedlineOption::Edit(EditCommand::YankSelection),
+                ]),
+                Motion::ToMarkLine(mark) => Some(vec![
+                    ReedlineOption::Edit(EditCommand::MoveToMarkLine {
+                        mark: *mark,
+                        select: true,
+                    }),
+                    ReedlineOption::Edit(EditCommand::YankSelectedLines),
+                ]),
+            },
             _ => None,
         }
     }
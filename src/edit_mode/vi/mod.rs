@@ -3,15 +3,17 @@ mod motion;
 mod parser;
 mod vi_keybindings;
 
+use std::collections::HashMap;
+
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+pub use motion::ViCharSearch;
 pub use vi_keybindings::{default_vi_insert_keybindings, default_vi_normal_keybindings};
 
-use self::motion::ViCharSearch;
-
 use super::EditMode;
 use crate::{
+    core_editor::Registers,
     edit_mode::{keybindings::Keybindings, vi::parser::parse},
-    enums::{EditCommand, ReedlineEvent, ReedlineRawEvent},
+    enums::{EditCommand, EditType, ReedlineEvent, ReedlineRawEvent, WordCharacters},
     PromptEditMode, PromptViMode,
 };
 
@@ -19,6 +21,15 @@ use crate::{
 enum ViMode {
     Normal,
     Insert,
+    Replace,
+    Visual,
+    VisualLine,
+}
+
+impl ViMode {
+    fn is_visual(&self) -> bool {
+        matches!(self, ViMode::Visual | ViMode::VisualLine)
+    }
 }
 
 /// This parses incoming input `Event`s like a Vi-Style editor
@@ -28,8 +39,33 @@ pub struct Vi {
     normal_keybindings: Keybindings,
     mode: ViMode,
     previous: Option<ReedlineEvent>,
+    // the previous action with a count of one, replayed by a counted `.`
+    previous_once: Option<ReedlineEvent>,
+    // edits typed since a repeatable command entered insert mode, added to it for `.` on Esc
+    inserted: Option<Vec<ReedlineEvent>>,
     // last f, F, t, T motion for ; and ,
     last_char_search: Option<ViCharSearch>,
+    // register and keys of the macro currently recorded with q
+    recording: Option<(char, Vec<ReedlineRawEvent>)>,
+    macros: HashMap<char, Vec<ReedlineRawEvent>>,
+    // last macro replayed with @ for @@
+    last_macro: Option<char>,
+    // macros being replayed right now, to stop a macro from calling itself
+    replaying: Vec<char>,
+    // Ctrl-R was pressed in insert mode and the next key names the register to insert
+    insert_register_pending: bool,
+    // one level of indentation for > and <
+    indent: String,
+    // S and cc keep the indentation of the line
+    autoindent: bool,
+    // characters moving the word boundaries of the word motions
+    word_characters: WordCharacters,
+    // term typed so far into the history search started with ?
+    search_input: Option<String>,
+    // last submitted history search term, and whether it went backward, for n and N
+    last_search: Option<(String, bool)>,
+    // an undo group holds the edits of the insert session, closed by Esc
+    undo_group: bool,
 }
 
 impl Default for Vi {
@@ -40,7 +76,20 @@ impl Default for Vi {
             cache: Vec::new(),
             mode: ViMode::Insert,
             previous: None,
+            previous_once: None,
+            inserted: None,
             last_char_search: None,
+            recording: None,
+            macros: HashMap::new(),
+            last_macro: None,
+            replaying: Vec::new(),
+            insert_register_pending: false,
+            indent: "    ".to_string(),
+            autoindent: true,
+            word_characters: WordCharacters::default(),
+            search_input: None,
+            last_search: None,
+            undo_group: false,
         }
     }
 }
@@ -54,49 +103,355 @@ impl Vi {
             ..Default::default()
         }
     }
+
+    /// Set the text inserted by one level of indentation with `>`, four spaces by default
+    #[must_use]
+    pub fn with_indent(mut self, indent: &str) -> Self {
+        self.indent = indent.to_string();
+        self
+    }
+
+    /// Set if `S` and `cc` keep the indentation of the changed line, like vim's `autoindent`,
+    /// on by default
+    #[must_use]
+    pub fn with_autoindent(mut self, autoindent: bool) -> Self {
+        self.autoindent = autoindent;
+        self
+    }
+
+    /// Set the characters joining or separating words for the word motions, text objects and
+    /// word edits, like vim's `iskeyword`. The editor takes them over when the edit mode is
+    /// installed with [`Reedline::with_edit_mode`](crate::Reedline::with_edit_mode)
+    #[must_use]
+    pub fn with_word_characters(mut self, word_characters: WordCharacters) -> Self {
+        self.word_characters = word_characters;
+        self
+    }
+
+    /// The last `f`, `F`, `t` or `T` search, repeated by `;` and `,`
+    pub fn last_char_search(&self) -> Option<&ViCharSearch> {
+        self.last_char_search.as_ref()
+    }
+
+    /// Set the character search repeated by `;` and `,`, e.g. to restore a saved session
+    pub fn set_last_char_search(&mut self, char_search: Option<ViCharSearch>) {
+        self.last_char_search = char_search;
+    }
+
+    /// Start recording keys into the macro `register`, uppercase appends to the lowercase one
+    fn start_recording(&mut self, register: char) {
+        let keys = if register.is_ascii_uppercase() {
+            self.macros
+                .get(&register.to_ascii_lowercase())
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        self.recording = Some((register.to_ascii_lowercase(), keys));
+    }
+
+    /// Feed the keys of the macro `register` back through the parser `times` times
+    ///
+    /// `@` refers to the last replayed macro. A macro that is already being replayed is
+    /// ignored, so a macro calling itself runs only once.
+    fn replay_macro(&mut self, register: char, times: usize) -> ReedlineEvent {
+        let register = match register {
+            '@' => match self.last_macro {
+                Some(register) => register,
+                None => return ReedlineEvent::None,
+            },
+            register => register.to_ascii_lowercase(),
+        };
+        if self.replaying.contains(&register) {
+            return ReedlineEvent::None;
+        }
+        let keys = match self.macros.get(&register) {
+            Some(keys) => keys.clone(),
+            None => return ReedlineEvent::None,
+        };
+        self.last_macro = Some(register);
+
+        self.cache.clear();
+        self.replaying.push(register);
+        let events = std::iter::repeat(keys)
+            .take(times)
+            .flatten()
+            .map(|key| self.parse_event(key))
+            .filter(|event| {
+                !matches!(
+                    event,
+                    ReedlineEvent::None | ReedlineEvent::OperatorPending(_)
+                )
+            })
+            .collect::<Vec<_>>();
+        self.replaying.pop();
+
+        if events.is_empty() {
+            ReedlineEvent::None
+        } else {
+            ReedlineEvent::Multiple(events)
+        }
+    }
+
+    /// Replay the last repeatable action, or its single instance `count` times if given
+    ///
+    /// Like vim, the new count also applies to the following repeats.
+    fn repeat_previous(&mut self, count: Option<usize>) -> ReedlineEvent {
+        match count {
+            None => self.previous.clone().unwrap_or(ReedlineEvent::None),
+            Some(count) => {
+                let once = match self.previous_once.as_ref().or(self.previous.as_ref()) {
+                    Some(event) => event.clone(),
+                    None => return ReedlineEvent::None,
+                };
+                let events = ReedlineEvent::Multiple(vec![once; count]);
+                self.previous = Some(events.clone());
+                events
+            }
+        }
+    }
+
+    /// Make the edits typed in insert mode part of the command that entered it, so `.`
+    /// replays both
+    fn finish_insert(&mut self) {
+        if let Some(inserted) = self.inserted.take().filter(|edits| !edits.is_empty()) {
+            for previous in [&mut self.previous, &mut self.previous_once] {
+                if let Some(event) = previous.take() {
+                    let mut events = vec![event];
+                    events.extend(inserted.iter().cloned());
+                    *previous = Some(ReedlineEvent::Multiple(events));
+                }
+            }
+        }
+    }
+
+    /// Make the edits of a complete command one undo step
+    ///
+    /// Like vim, the text typed in the insert session a command starts is undone along with
+    /// it, so its group stays open until Esc. The keys replayed by a macro are grouped as a
+    /// whole instead.
+    fn group_undo(&mut self, event: ReedlineEvent) -> ReedlineEvent {
+        if event == ReedlineEvent::None {
+            event
+        } else if matches!(self.mode, ViMode::Insert | ViMode::Replace) {
+            self.open_undo_group(event)
+        } else if text_changes(&event) > 1 {
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::BeginUndoGroup]),
+                event,
+                ReedlineEvent::Edit(vec![EditCommand::EndUndoGroup]),
+            ])
+        } else {
+            event
+        }
+    }
+
+    /// Run `event` in the undo group of the insert session, opening it first if needed
+    fn open_undo_group(&mut self, event: ReedlineEvent) -> ReedlineEvent {
+        if std::mem::replace(&mut self.undo_group, true) {
+            event
+        } else {
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::BeginUndoGroup]),
+                event,
+            ])
+        }
+    }
+
+    /// Follow the search term, the text typed in insert mode and its undo group through the
+    /// `event` about to be run
+    fn track_event(&mut self, mut event: ReedlineEvent) -> ReedlineEvent {
+        if self.search_input.is_some() {
+            self.track_search(&event);
+        }
+        if let (ViMode::Insert, Some(inserted), ReedlineEvent::Edit(_) | ReedlineEvent::Paste(_)) =
+            (self.mode, self.inserted.as_mut(), &event)
+        {
+            inserted.push(event.clone());
+        }
+        if matches!(self.mode, ViMode::Insert | ViMode::Replace)
+            && matches!(event, ReedlineEvent::Edit(_) | ReedlineEvent::Paste(_))
+            && self.replaying.is_empty()
+            && self.search_input.is_none()
+        {
+            // Also covers the insert sessions not started by a command, like the first one
+            event = self.open_undo_group(event);
+        } else if event == ReedlineEvent::CtrlC {
+            // The line is dropped along with its undo history
+            self.undo_group = false;
+        }
+        event
+    }
+
+    /// Follow the term typed into the history search started with `?`, keeping it for `n`
+    /// and `N` once the search is submitted
+    fn track_search(&mut self, event: &ReedlineEvent) {
+        match event {
+            ReedlineEvent::Edit(commands) => {
+                for command in commands {
+                    match (command, self.search_input.as_mut()) {
+                        (EditCommand::InsertChar(c), Some(term)) => term.push(*c),
+                        (EditCommand::Backspace, Some(term)) => {
+                            let _ = term.pop();
+                        }
+                        // Any other edit leaves the history search
+                        _ => self.search_input = None,
+                    }
+                }
+            }
+            ReedlineEvent::Enter => {
+                // An empty search keeps the previous term, like vim
+                if let Some(term) = self.search_input.take().filter(|term| !term.is_empty()) {
+                    self.last_search = Some((term, true));
+                }
+            }
+            ReedlineEvent::Paste(text) => {
+                if let Some(term) = self.search_input.as_mut() {
+                    term.push_str(text);
+                }
+            }
+            ReedlineEvent::Esc | ReedlineEvent::CtrlC => self.search_input = None,
+            ReedlineEvent::Multiple(events) => {
+                for event in events {
+                    self.track_search(event);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Number of the edits in `event` changing the text
+fn text_changes(event: &ReedlineEvent) -> usize {
+    match event {
+        ReedlineEvent::Edit(commands) => commands
+            .iter()
+            .filter(|command| command.edit_type() == EditType::EditText)
+            .count(),
+        ReedlineEvent::Multiple(events) => events.iter().map(text_changes).sum(),
+        _ => 0,
+    }
 }
 
 impl EditMode for Vi {
     fn parse_event(&mut self, event: ReedlineRawEvent) -> ReedlineEvent {
-        match event.into() {
+        // Keys produced by a replayed macro are not recorded again
+        if self.replaying.is_empty() {
+            if let Some((register, mut keys)) = self.recording.take() {
+                let stops_recording = !matches!(self.mode, ViMode::Insert | ViMode::Replace)
+                    && self.cache.is_empty()
+                    && matches!(
+                        event.clone().into(),
+                        Event::Key(KeyEvent {
+                            code: KeyCode::Char('q'),
+                            modifiers: KeyModifiers::NONE,
+                            ..
+                        })
+                    );
+                if stops_recording {
+                    self.macros.insert(register, keys);
+                    return ReedlineEvent::None;
+                }
+                keys.push(event.clone());
+                self.recording = Some((register, keys));
+            }
+        }
+
+        let event = event.into();
+        if self.insert_register_pending {
+            self.insert_register_pending = false;
+            // Inserted like typed text, so `.` replays it and `u` undoes it with the session
+            let event = match event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(register),
+                    modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                    ..
+                }) if Registers::is_valid_name(register) => {
+                    ReedlineEvent::Edit(vec![EditCommand::InsertRegister(register)])
+                }
+                _ => ReedlineEvent::None,
+            };
+            return self.track_event(event);
+        }
+
+        let event = match event {
             Event::Key(KeyEvent {
                 code, modifiers, ..
             }) => match (self.mode, modifiers, code) {
-                (ViMode::Normal, modifier, KeyCode::Char(c)) => {
+                (
+                    ViMode::Normal | ViMode::Visual | ViMode::VisualLine,
+                    modifier,
+                    KeyCode::Char(c),
+                ) => {
                     let c = c.to_ascii_lowercase();
+                    let key = match modifier {
+                        KeyModifiers::NONE => Some(c),
+                        KeyModifiers::SHIFT => Some(c.to_ascii_uppercase()),
+                        KeyModifiers::CONTROL if c == 'r' => Some(command::REDO_KEY),
+                        _ => None,
+                    };
 
                     if let Some(event) = self
                         .normal_keybindings
                         .find_binding(modifiers, KeyCode::Char(c))
                     {
                         event
-                    } else if modifier == KeyModifiers::NONE || modifier == KeyModifiers::SHIFT {
-                        self.cache.push(if modifier == KeyModifiers::SHIFT {
-                            c.to_ascii_uppercase()
-                        } else {
-                            c
-                        });
+                    } else if let Some(key) = key {
+                        self.cache.push(key);
 
                         let res = parse(&mut self.cache.iter().peekable());
 
                         if !res.is_valid() {
+                            // Aborting a pending command clears its keys from the prompt
+                            let was_pending = self.cache.len() > 1;
                             self.cache.clear();
-                            ReedlineEvent::None
-                        } else if res.is_complete() {
-                            if res.enters_insert_mode() {
-                                self.mode = ViMode::Insert;
+                            if was_pending {
+                                ReedlineEvent::Repaint
+                            } else {
+                                ReedlineEvent::None
+                            }
+                        } else if res.is_complete()
+                            || (self.mode.is_visual() && res.operates_on_selection())
+                        {
+                            let mut event = res.to_reedline_event(self);
+                            if let Some(mode) = res.changes_mode(self.mode) {
+                                if self.mode.is_visual() && !mode.is_visual() {
+                                    event = ReedlineEvent::Multiple(vec![
+                                        event,
+                                        ReedlineEvent::Edit(vec![EditCommand::ClearSelection]),
+                                    ]);
+                                }
+                                if mode == ViMode::Insert
+                                    && event != ReedlineEvent::None
+                                    && self.search_input.is_none()
+                                {
+                                    self.inserted = Some(Vec::new());
+                                }
+                                self.mode = mode;
+                            }
+                            if self.replaying.is_empty() && self.search_input.is_none() {
+                                event = self.group_undo(event);
                             }
 
-                            let event = res.to_reedline_event(self);
                             self.cache.clear();
                             event
                         } else {
-                            ReedlineEvent::None
+                            ReedlineEvent::OperatorPending(self.cache.iter().collect())
                         }
                     } else {
                         ReedlineEvent::None
                     }
                 }
+                (ViMode::Insert | ViMode::Replace, KeyModifiers::CONTROL, KeyCode::Char('r'))
+                    if self
+                        .insert_keybindings
+                        .find_binding(modifiers, code)
+                        .is_none() =>
+                {
+                    self.insert_register_pending = true;
+                    ReedlineEvent::None
+                }
                 (ViMode::Insert, modifier, KeyCode::Char(c)) => {
                     // Note. The modifier can also be a combination of modifiers, for
                     // example:
@@ -134,20 +489,60 @@ impl EditMode for Vi {
                             }
                         })
                 }
+                (ViMode::Replace, KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                    ReedlineEvent::Edit(vec![EditCommand::OverwriteChar(c)])
+                }
+                (ViMode::Replace, KeyModifiers::NONE, KeyCode::Backspace) => {
+                    ReedlineEvent::Edit(vec![EditCommand::RestoreOverwrittenChar])
+                }
                 (_, KeyModifiers::NONE, KeyCode::Esc) => {
                     self.cache.clear();
+                    let previous_mode = self.mode;
                     self.mode = ViMode::Normal;
-                    ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+                    if previous_mode == ViMode::Insert {
+                        self.finish_insert();
+                    }
+                    // Like vim, `^` marks where insert mode was left, for `gi`
+                    let mut edits = vec![EditCommand::SetMark('^')];
+                    if std::mem::take(&mut self.undo_group) {
+                        edits.push(EditCommand::EndUndoGroup);
+                    }
+                    if previous_mode == ViMode::Replace {
+                        edits.push(EditCommand::MoveLeft { select: false });
+                        ReedlineEvent::Multiple(vec![
+                            ReedlineEvent::Edit(edits),
+                            ReedlineEvent::Esc,
+                            ReedlineEvent::Repaint,
+                        ])
+                    } else if previous_mode == ViMode::Insert {
+                        ReedlineEvent::Multiple(vec![
+                            ReedlineEvent::Edit(edits),
+                            ReedlineEvent::Esc,
+                            ReedlineEvent::Repaint,
+                        ])
+                    } else if previous_mode.is_visual() {
+                        ReedlineEvent::Multiple(vec![
+                            ReedlineEvent::Edit(vec![EditCommand::ClearSelection]),
+                            ReedlineEvent::Esc,
+                            ReedlineEvent::Repaint,
+                        ])
+                    } else {
+                        ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+                    }
                 }
                 (_, KeyModifiers::NONE, KeyCode::Enter) => {
                     self.mode = ViMode::Insert;
+                    self.inserted = None;
+                    // A submitted line takes its undo group along, a newline keeps it open
+                    // and the next edit opens it again
+                    self.undo_group = false;
                     ReedlineEvent::Enter
                 }
-                (ViMode::Normal, _, _) => self
+                (ViMode::Normal | ViMode::Visual | ViMode::VisualLine, _, _) => self
                     .normal_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
-                (ViMode::Insert, _, _) => self
+                (ViMode::Insert | ViMode::Replace, _, _) => self
                     .insert_keybindings
                     .find_binding(modifiers, code)
                     .unwrap_or(ReedlineEvent::None),
@@ -157,24 +552,57 @@ impl EditMode for Vi {
             Event::Resize(width, height) => ReedlineEvent::Resize(width, height),
             Event::FocusGained => ReedlineEvent::None,
             Event::FocusLost => ReedlineEvent::None,
-            Event::Paste(body) => ReedlineEvent::Edit(vec![EditCommand::InsertString(
-                body.replace("\r\n", "\n").replace('\r', "\n"),
-            )]),
-        }
+            Event::Paste(body) => {
+                // Pasted text is never taken for keys, so it drops a pending command
+                self.cache.clear();
+                ReedlineEvent::Paste(body)
+            }
+        };
+        self.track_event(event)
     }
 
     fn edit_mode(&self) -> PromptEditMode {
         match self.mode {
-            ViMode::Normal => PromptEditMode::Vi(PromptViMode::Normal),
-            ViMode::Insert => PromptEditMode::Vi(PromptViMode::Insert),
+            ViMode::Normal | ViMode::Visual | ViMode::VisualLine if !self.cache.is_empty() => {
+                PromptEditMode::Vi(PromptViMode::OperatorPending(self.cache.iter().collect()))
+            }
+            ViMode::Normal | ViMode::Visual | ViMode::VisualLine => {
+                PromptEditMode::Vi(PromptViMode::Normal)
+            }
+            ViMode::Insert | ViMode::Replace => PromptEditMode::Vi(PromptViMode::Insert),
         }
     }
+
+    /// A change of a text object that isn't there, like `ci"` without quotes, stays in
+    /// normal mode like vim
+    fn handle_missed_text_object(&mut self) -> ReedlineEvent {
+        if self.mode != ViMode::Insert {
+            return ReedlineEvent::None;
+        }
+        self.mode = ViMode::Normal;
+        self.inserted = None;
+        if std::mem::take(&mut self.undo_group) {
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::EndUndoGroup]),
+                ReedlineEvent::Repaint,
+            ])
+        } else {
+            ReedlineEvent::Repaint
+        }
+    }
+
+    fn word_characters(&self) -> WordCharacters {
+        self.word_characters.clone()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::core_editor::Editor;
+    use crate::UndoBehavior;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     #[test]
     fn esc_leads_to_normal_mode_test() {
@@ -188,11 +616,128 @@ mod test {
 
         assert_eq!(
             result,
-            ReedlineEvent::Multiple(vec![ReedlineEvent::Esc, ReedlineEvent::Repaint])
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::SetMark('^')]),
+                ReedlineEvent::Esc,
+                ReedlineEvent::Repaint
+            ])
         );
         assert!(matches!(vi.mode, ViMode::Normal));
     }
 
+    fn char_event(c: char) -> ReedlineRawEvent {
+        ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Char(c),
+            KeyModifiers::NONE,
+        )))
+        .unwrap()
+    }
+
+    fn undo_group(event: ReedlineEvent) -> ReedlineEvent {
+        ReedlineEvent::Multiple(vec![
+            ReedlineEvent::Edit(vec![EditCommand::BeginUndoGroup]),
+            event,
+            ReedlineEvent::Edit(vec![EditCommand::EndUndoGroup]),
+        ])
+    }
+
+    /// The event run in the undo group it opens for the insert session
+    fn in_opened_undo_group(event: ReedlineEvent) -> ReedlineEvent {
+        match event {
+            ReedlineEvent::Multiple(mut events)
+                if events.len() == 2
+                    && events[0] == ReedlineEvent::Edit(vec![EditCommand::BeginUndoGroup]) =>
+            {
+                events.pop().unwrap()
+            }
+            other => panic!("no undo group opened by {other:?}"),
+        }
+    }
+
+    #[test]
+    fn visual_mode_extends_selection_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vi.parse_event(char_event('v')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::StartInclusiveSelection]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Visual);
+
+        assert_eq!(
+            vi.parse_event(char_event('w')),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::MoveWordRightStart { select: true }
+            ])])
+        );
+
+        assert_eq!(
+            vi.parse_event(char_event('d')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutSelection])]),
+                ReedlineEvent::Edit(vec![EditCommand::ClearSelection]),
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+    }
+
+    #[test]
+    fn visual_line_mode_yank_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        let _ = vi.parse_event(
+            ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                KeyCode::Char('V'),
+                KeyModifiers::SHIFT,
+            )))
+            .unwrap(),
+        );
+        assert_eq!(vi.mode, ViMode::VisualLine);
+
+        assert_eq!(
+            vi.parse_event(char_event('y')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                    EditCommand::CopySelectedLines
+                ])]),
+                ReedlineEvent::Edit(vec![EditCommand::ClearSelection]),
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+    }
+
+    #[test]
+    fn esc_leaves_visual_mode_test() {
+        let mut vi = Vi {
+            mode: ViMode::Visual,
+            ..Default::default()
+        };
+        let esc = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+
+        assert_eq!(
+            vi.parse_event(esc),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::ClearSelection]),
+                ReedlineEvent::Esc,
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+    }
+
     #[test]
     fn keybinding_without_modifier_test() {
         let mut keybindings = default_vi_normal_keybindings();
@@ -262,6 +807,737 @@ mod test {
         .unwrap();
         let result = vi.parse_event(esc);
 
-        assert_eq!(result, ReedlineEvent::None);
+        assert_eq!(result, ReedlineEvent::OperatorPending("q".to_string()));
+    }
+
+    #[test]
+    fn change_line_without_autoindent_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        }
+        .with_autoindent(false);
+
+        let _ = vi.parse_event(char_event('c'));
+        let result = in_opened_undo_group(vi.parse_event(char_event('c')));
+
+        assert_eq!(
+            result,
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![EditCommand::ChangeLine { keep_indent: false }]),
+                ReedlineEvent::Repaint
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Insert);
+    }
+
+    #[rstest]
+    #[case('s', ViMode::Insert)]
+    #[case('S', ViMode::Insert)]
+    #[case('C', ViMode::Insert)]
+    #[case('A', ViMode::Insert)]
+    #[case('I', ViMode::Insert)]
+    #[case('D', ViMode::Normal)]
+    fn line_commands_mode_test(#[case] key: char, #[case] expected: ViMode) {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let key = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Char(key),
+            if key.is_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            },
+        )))
+        .unwrap();
+
+        let event = vi.parse_event(key);
+
+        assert_eq!(vi.mode, expected);
+        if expected == ViMode::Insert {
+            // The prompt is repainted to show the new mode
+            let event = in_opened_undo_group(event);
+            assert!(
+                matches!(&event, ReedlineEvent::Multiple(events) if events.contains(&ReedlineEvent::Repaint))
+            );
+            assert!(matches!(
+                vi.edit_mode(),
+                PromptEditMode::Vi(PromptViMode::Insert)
+            ));
+        }
+    }
+
+    #[test]
+    fn operator_pending_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let esc = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+        let is_normal =
+            |vi: &Vi| matches!(vi.edit_mode(), PromptEditMode::Vi(PromptViMode::Normal));
+
+        assert_eq!(
+            vi.parse_event(char_event('2')),
+            ReedlineEvent::OperatorPending("2".to_string())
+        );
+        assert_eq!(
+            vi.parse_event(char_event('d')),
+            ReedlineEvent::OperatorPending("2d".to_string())
+        );
+        assert!(matches!(
+            vi.edit_mode(),
+            PromptEditMode::Vi(PromptViMode::OperatorPending(keys)) if keys == "2d"
+        ));
+        // Completed
+        vi.parse_event(char_event('w'));
+        assert!(is_normal(&vi));
+
+        // Aborted by an invalid key
+        vi.parse_event(char_event('d'));
+        assert_eq!(vi.parse_event(char_event('z')), ReedlineEvent::Repaint);
+        assert!(is_normal(&vi));
+
+        // Aborted by escape
+        vi.parse_event(char_event('c'));
+        vi.parse_event(esc);
+        assert!(is_normal(&vi));
+    }
+
+    #[test]
+    fn paste_is_taken_literally_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let paste =
+            |text: &str| ReedlineRawEvent::convert_from(Event::Paste(text.to_string())).unwrap();
+
+        assert_eq!(
+            vi.parse_event(paste("dd\nx")),
+            ReedlineEvent::Paste("dd\nx".to_string())
+        );
+        assert!(matches!(vi.mode, ViMode::Normal));
+
+        // Drops the pending operator, so the next key starts over
+        vi.parse_event(char_event('d'));
+        assert_eq!(
+            vi.parse_event(paste("w")),
+            ReedlineEvent::Paste("w".to_string())
+        );
+        assert!(matches!(
+            vi.edit_mode(),
+            PromptEditMode::Vi(PromptViMode::Normal)
+        ));
+    }
+
+    #[test]
+    fn counted_repeat_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let delete_word = ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+            EditCommand::CutWordRightToNext,
+        ])]);
+
+        // Without a previous action a counted repeat does nothing
+        assert_eq!(
+            vi.parse_event(char_event('3')),
+            ReedlineEvent::OperatorPending("3".to_string())
+        );
+        assert_eq!(vi.parse_event(char_event('.')), ReedlineEvent::None);
+
+        vi.parse_event(char_event('2'));
+        vi.parse_event(char_event('d'));
+        vi.parse_event(char_event('w'));
+        // The count replaces the one of the original action
+        vi.parse_event(char_event('3'));
+        // in one undo step
+        let repeated = undo_group(ReedlineEvent::Multiple(vec![delete_word.clone(); 3]));
+        assert_eq!(vi.parse_event(char_event('.')), repeated);
+        // and sticks for the following repeats
+        assert_eq!(vi.parse_event(char_event('.')), repeated);
+        vi.parse_event(char_event('1'));
+        assert_eq!(
+            vi.parse_event(char_event('.')),
+            ReedlineEvent::Multiple(vec![delete_word])
+        );
+    }
+
+    #[test]
+    fn repeat_skips_motions_and_yanks_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let esc = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+        let delete_char =
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutChar])]);
+
+        vi.parse_event(char_event('x'));
+        for c in ['w', 'b', 'y', 'w', 'm', 'a', 'u', 'v'] {
+            vi.parse_event(char_event(c));
+        }
+        vi.parse_event(esc);
+
+        assert_eq!(vi.parse_event(char_event('.')), delete_char);
+    }
+
+    #[test]
+    fn repeat_replays_inserted_text_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let esc = ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        )))
+        .unwrap();
+        let insert = |c| ReedlineEvent::Edit(vec![EditCommand::InsertChar(c)]);
+
+        vi.parse_event(char_event('3'));
+        vi.parse_event(char_event('c'));
+        let change = in_opened_undo_group(vi.parse_event(char_event('w')));
+        assert_eq!(vi.mode, ViMode::Insert);
+        for c in "bar".chars() {
+            assert_eq!(vi.parse_event(char_event(c)), insert(c));
+        }
+        vi.parse_event(esc.clone());
+
+        let change_to_bar = undo_group(ReedlineEvent::Multiple(vec![
+            change,
+            insert('b'),
+            insert('a'),
+            insert('r'),
+        ]));
+        assert_eq!(vi.parse_event(char_event('.')), change_to_bar);
+        assert_eq!(vi.mode, ViMode::Normal);
+
+        // Text typed after another command replaces the recorded one
+        vi.parse_event(char_event('a'));
+        vi.parse_event(char_event('x'));
+        vi.parse_event(esc);
+        assert_eq!(
+            vi.parse_event(char_event('.')),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveRight {
+                    select: false
+                }])]),
+                insert('x'),
+            ])
+        );
+    }
+
+    /// Run the edits of `event` on the `editor`, like the engine does
+    fn run(editor: &mut Editor, event: ReedlineEvent) {
+        match event {
+            ReedlineEvent::Edit(commands) => {
+                for command in &commands {
+                    editor.run_edit_command(command);
+                }
+            }
+            ReedlineEvent::Multiple(events) => {
+                for event in events {
+                    run(editor, event);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn type_keys(vi: &mut Vi, editor: &mut Editor, keys: &str) {
+        for c in keys.chars() {
+            let key = match c {
+                '\u{1b}' => KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+                command::REDO_KEY => KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+                c if c.is_ascii_uppercase() => KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT),
+                c => KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            };
+            let event = ReedlineRawEvent::convert_from(Event::Key(key)).unwrap();
+            run(editor, vi.parse_event(event));
+            if editor.take_missed_text_object() {
+                run(editor, vi.handle_missed_text_object());
+            }
+        }
+    }
+
+    #[test]
+    fn undo_reverts_whole_changes_test() {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, "one two\u{1b}0cwsix\u{1b}");
+        assert_eq!(editor.get_buffer(), "six two");
+
+        // The change and the text typed after it
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "one two");
+        // The repeat of both
+        type_keys(&mut vi, &mut editor, "0.");
+        assert_eq!(editor.get_buffer(), "six two");
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "one two");
+        // The whole first insert session
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "");
+
+        // Ctrl-R redoes the same steps
+        type_keys(&mut vi, &mut editor, "\u{12}");
+        assert_eq!(editor.get_buffer(), "one two");
+        type_keys(&mut vi, &mut editor, "\u{12}");
+        assert_eq!(editor.get_buffer(), "six two");
+    }
+
+    #[rstest]
+    #[case("one two\u{1b}0yiwwv$p", "one one")]
+    // The replaced line is pasted back above
+    #[case("one two\u{1b}0yiwVpP", "one two\none")]
+    // Lines pasted over a part of a line go on lines of their own
+    #[case("one two\u{1b}yy0wv$p", "one \none two\n")]
+    fn visual_paste_replaces_selection_test(#[case] keys: &str, #[case] expected: &str) {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, keys);
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(vi.mode, ViMode::Normal);
+        assert_eq!(editor.get_selection(), None);
+    }
+
+    #[test]
+    fn visual_paste_swaps_with_register_test() {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+        type_keys(&mut vi, &mut editor, "one two\u{1b}0yiw");
+
+        // The register is taken before the selection replaces it
+        type_keys(&mut vi, &mut editor, "wv$p");
+        assert_eq!(editor.get_buffer(), "one one");
+        type_keys(&mut vi, &mut editor, "0vlllp");
+        assert_eq!(editor.get_buffer(), "twoone");
+
+        // A named register is pasted, the replaced text still goes to the unnamed one
+        type_keys(&mut vi, &mut editor, "0\"ayiw0vll\"ap");
+        assert_eq!(editor.get_buffer(), "twooneone");
+        type_keys(&mut vi, &mut editor, "$p");
+        assert_eq!(editor.get_buffer(), "twooneonetwo");
+
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "twooneone");
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "twoone");
+    }
+
+    #[rstest]
+    #[case("abc\u{1b}0vd", "bc")]
+    #[case("abcdef\u{1b}0vfcd", "def")]
+    #[case("abc def\u{1b}0vey$p", "abc defabc")]
+    #[case("abc def\u{1b}$vbd", "abc ")]
+    #[case("abc def\u{1b}0vegU", "ABC def")]
+    #[case("abc def\u{1b}0vecx\u{1b}", "x def")]
+    fn visual_selection_covers_cursor_test(#[case] keys: &str, #[case] expected: &str) {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, keys);
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[rstest]
+    #[case("x = abc\u{1b}0ci\"", "x = abc", ViMode::Normal)]
+    #[case("x = abc\u{1b}0ci\"y", "x = abc", ViMode::Normal)]
+    #[case("f (a)\u{1b}0ci(", "f (a)", ViMode::Normal)]
+    #[case("x = \"abc\"\u{1b}0ci\"y", "x = \"y\"", ViMode::Insert)]
+    fn change_missing_text_object_stays_in_normal_mode_test(
+        #[case] keys: &str,
+        #[case] expected: &str,
+        #[case] expected_mode: ViMode,
+    ) {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, keys);
+        assert_eq!(editor.get_buffer(), expected);
+        assert_eq!(vi.mode, expected_mode);
+    }
+
+    #[rstest]
+    #[case("one\ntwo\nthree", "Gmaggey'a", 2, "one\ntwo\nthree\none\ntwo\nthree")]
+    #[case("one two\n\nthree", "wy}", 4, "one two\ntwo\n\nthree")]
+    #[case("one\ntwo\nthree", "Geygg", 12, "one\ntwo\none\ntwo\nthree\nthree")]
+    #[case("aa bb", "weyge", 4, "aa ba bbb")]
+    #[case("aa bb cc", "2yw", 0, "aa bb aa bb cc")]
+    #[case("aa bb cc", "w3yl", 3, "aa bb bb cc")]
+    #[case("one\ntwo\nthree", "e2y$", 2, "one\ntwoe\ntwo\nthree")]
+    #[case("one\ntwo\nthree", "yj", 0, "one\ntwo\none\ntwo\nthree")]
+    #[case("one\ntwo\nthree", "Gyk", 8, "one\ntwo\ntwo\nthree\nthree")]
+    #[case("one\ntwo\nthree", "2yj", 0, "one\ntwo\nthree\none\ntwo\nthree")]
+    #[case("aa, bb, cc", "2yf,", 0, "aa, bb,aa, bb, cc")]
+    #[case("one\n\ntwo\n\nthree", "2y}", 0, "one\n\ntwo\none\n\ntwo\n\nthree")]
+    fn yank_keeps_cursor_test(
+        #[case] buffer: &str,
+        #[case] keys: &str,
+        #[case] expected_cursor: usize,
+        #[case] expected_after_paste: &str,
+    ) {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut editor = Editor::default();
+        editor.set_buffer(buffer.to_string(), UndoBehavior::CreateUndoPoint);
+        editor.move_to_start(false);
+
+        type_keys(&mut vi, &mut editor, keys);
+        assert_eq!(editor.insertion_point(), expected_cursor);
+        type_keys(&mut vi, &mut editor, "P");
+        assert_eq!(editor.get_buffer(), expected_after_paste);
+    }
+
+    #[rstest]
+    #[case("aa bb cc\u{1b}02ywP", "aa bb aa bb cc")]
+    #[case("aa bb cc\u{1b}03ylP", "aa aa bb cc")]
+    #[case("aa bb cc\u{1b}02w2ybP", "aa bb aa bb cc")]
+    fn counted_yank_copies_whole_span_test(#[case] keys: &str, #[case] expected: &str) {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, keys);
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[test]
+    fn counted_yank_lines_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut editor = Editor::default();
+        editor.set_buffer(
+            "one\ntwo\nthree\nfour".to_string(),
+            UndoBehavior::CreateUndoPoint,
+        );
+        editor.move_to_start(false);
+
+        type_keys(&mut vi, &mut editor, "3yyP");
+        assert_eq!(
+            editor.get_buffer(),
+            "one\ntwo\nthree\none\ntwo\nthree\nfour"
+        );
+    }
+
+    #[test]
+    fn macro_record_and_replay_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let delete_char =
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutChar])]);
+
+        assert_eq!(
+            vi.parse_event(char_event('q')),
+            ReedlineEvent::OperatorPending("q".to_string())
+        );
+        assert_eq!(vi.parse_event(char_event('a')), ReedlineEvent::None);
+        // Keys take effect while recording
+        assert_eq!(vi.parse_event(char_event('x')), delete_char);
+        assert_eq!(vi.parse_event(char_event('q')), ReedlineEvent::None);
+
+        let _ = vi.parse_event(char_event('2'));
+        let _ = vi.parse_event(char_event('@'));
+        // The replayed changes are undone together
+        assert_eq!(
+            vi.parse_event(char_event('a')),
+            undo_group(ReedlineEvent::Multiple(vec![
+                delete_char.clone(),
+                delete_char.clone()
+            ]))
+        );
+
+        let _ = vi.parse_event(char_event('@'));
+        assert_eq!(
+            vi.parse_event(char_event('@')),
+            ReedlineEvent::Multiple(vec![delete_char])
+        );
+    }
+
+    #[test]
+    fn recursive_macro_terminates_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        for c in ['q', 'a', 'x', '@', 'a', 'q'] {
+            let _ = vi.parse_event(char_event(c));
+        }
+
+        let _ = vi.parse_event(char_event('@'));
+        assert_eq!(
+            vi.parse_event(char_event('a')),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(
+                vec![EditCommand::CutChar]
+            )])])
+        );
+    }
+
+    #[rstest]
+    #[case("", "-bar baz")]
+    #[case("-", "baz")]
+    fn word_characters_test(#[case] word: &str, #[case] expected: &str) {
+        let vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let mut vi = vi.with_word_characters(WordCharacters {
+            word: word.into(),
+            separators: String::new(),
+        });
+        let mut editor = Editor::default();
+        editor.set_buffer("foo-bar baz".to_string(), UndoBehavior::CreateUndoPoint);
+        editor.set_word_characters(vi.word_characters());
+        editor.move_to_start(false);
+
+        type_keys(&mut vi, &mut editor, "dw");
+        assert_eq!(editor.get_buffer(), expected);
+        // Undo brings back a line buffer still using the word characters
+        type_keys(&mut vi, &mut editor, "u0dw");
+        assert_eq!(editor.get_buffer(), expected);
+    }
+
+    #[test]
+    fn repeat_char_search_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        // Without a previous search there is nothing to repeat
+        assert_eq!(vi.parse_event(char_event(';')), ReedlineEvent::None);
+        assert_eq!(vi.parse_event(char_event(',')), ReedlineEvent::None);
+
+        let _ = vi.parse_event(char_event('t'));
+        let _ = vi.parse_event(char_event('x'));
+        let move_right = ReedlineEvent::Edit(vec![EditCommand::MoveRightBefore {
+            c: 'x',
+            select: false,
+        }]);
+        assert_eq!(
+            vi.parse_event(char_event(';')),
+            ReedlineEvent::Multiple(vec![move_right.clone()])
+        );
+        assert_eq!(
+            vi.parse_event(char_event(',')),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+                EditCommand::MoveLeftBefore {
+                    c: 'x',
+                    select: false,
+                }
+            ])])
+        );
+
+        // A counted repeat passes the earlier occurrences instead of getting stuck before them
+        let pass = |search: ViCharSearch| ReedlineEvent::Edit(vec![search.to_move(false)]);
+        let _ = vi.parse_event(char_event('3'));
+        assert_eq!(
+            vi.parse_event(char_event(';')),
+            ReedlineEvent::Multiple(vec![
+                pass(ViCharSearch::ToRight('x')),
+                pass(ViCharSearch::ToRight('x')),
+                move_right
+            ])
+        );
+        let _ = vi.parse_event(char_event('2'));
+        assert_eq!(
+            vi.parse_event(char_event(',')),
+            ReedlineEvent::Multiple(vec![
+                pass(ViCharSearch::ToLeft('x')),
+                pass(ViCharSearch::TillLeft('x'))
+            ])
+        );
+    }
+
+    #[test]
+    fn repeat_history_search_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+        let key_event = |code| {
+            ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+                .unwrap()
+        };
+
+        // Without a previous search there is nothing to repeat
+        assert_eq!(vi.parse_event(char_event('n')), ReedlineEvent::None);
+
+        let _ = vi.parse_event(char_event('?'));
+        for c in ['a', 'b', 'c'] {
+            let _ = vi.parse_event(char_event(c));
+        }
+        let _ = vi.parse_event(key_event(KeyCode::Backspace));
+        let _ = vi.parse_event(key_event(KeyCode::Enter));
+        let _ = vi.parse_event(key_event(KeyCode::Esc));
+
+        assert_eq!(
+            vi.parse_event(char_event('n')),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::RepeatHistorySearch {
+                term: "ab".to_string(),
+                backward: true,
+            }])
+        );
+        assert_eq!(
+            vi.parse_event(
+                ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                    KeyCode::Char('N'),
+                    KeyModifiers::SHIFT,
+                )))
+                .unwrap()
+            ),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::RepeatHistorySearch {
+                term: "ab".to_string(),
+                backward: false,
+            }])
+        );
+
+        // An abandoned search keeps the previous term
+        let _ = vi.parse_event(char_event('?'));
+        let _ = vi.parse_event(char_event('x'));
+        let _ = vi.parse_event(key_event(KeyCode::Esc));
+        assert_eq!(
+            vi.parse_event(char_event('n')),
+            ReedlineEvent::Multiple(vec![ReedlineEvent::RepeatHistorySearch {
+                term: "ab".to_string(),
+                backward: true,
+            }])
+        );
+    }
+
+    #[test]
+    fn replace_mode_test() {
+        let mut vi = Vi {
+            mode: ViMode::Normal,
+            ..Default::default()
+        };
+
+        let _ = vi.parse_event(
+            ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                KeyCode::Char('R'),
+                KeyModifiers::SHIFT,
+            )))
+            .unwrap(),
+        );
+        assert_eq!(vi.mode, ViMode::Replace);
+
+        assert_eq!(
+            vi.parse_event(char_event('x')),
+            ReedlineEvent::Edit(vec![EditCommand::OverwriteChar('x')])
+        );
+        assert_eq!(
+            vi.parse_event(
+                ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                    KeyCode::Backspace,
+                    KeyModifiers::NONE,
+                )))
+                .unwrap()
+            ),
+            ReedlineEvent::Edit(vec![EditCommand::RestoreOverwrittenChar])
+        );
+        assert_eq!(
+            vi.parse_event(
+                ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                    KeyCode::Esc,
+                    KeyModifiers::NONE,
+                )))
+                .unwrap()
+            ),
+            ReedlineEvent::Multiple(vec![
+                ReedlineEvent::Edit(vec![
+                    EditCommand::SetMark('^'),
+                    EditCommand::EndUndoGroup,
+                    EditCommand::MoveLeft { select: false }
+                ]),
+                ReedlineEvent::Esc,
+                ReedlineEvent::Repaint,
+            ])
+        );
+        assert_eq!(vi.mode, ViMode::Normal);
+    }
+
+    #[test]
+    fn insert_register_test() {
+        let mut vi = Vi::default();
+
+        assert_eq!(
+            vi.parse_event(
+                ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                    KeyCode::Char('r'),
+                    KeyModifiers::CONTROL,
+                )))
+                .unwrap()
+            ),
+            ReedlineEvent::None
+        );
+        // Like typed text, the register opens the undo group of the insert session
+        assert_eq!(
+            in_opened_undo_group(vi.parse_event(char_event('a'))),
+            ReedlineEvent::Edit(vec![EditCommand::InsertRegister('a')])
+        );
+        assert_eq!(
+            vi.parse_event(char_event('a')),
+            ReedlineEvent::Edit(vec![EditCommand::InsertChar('a')])
+        );
+    }
+
+    #[test]
+    fn inserted_register_is_repeated_and_undone_with_insert_test() {
+        let mut vi = Vi::default();
+        let mut editor = Editor::default();
+
+        type_keys(&mut vi, &mut editor, "x\u{1b}0\"aylAfoo\u{12}abar\u{1b}");
+        assert_eq!(editor.get_buffer(), "xfooxbar");
+
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "x");
+        type_keys(&mut vi, &mut editor, ".");
+        assert_eq!(editor.get_buffer(), "xfooxbar");
+        type_keys(&mut vi, &mut editor, ".");
+        assert_eq!(editor.get_buffer(), "xfooxbarfooxbar");
+        type_keys(&mut vi, &mut editor, "u");
+        assert_eq!(editor.get_buffer(), "xfooxbar");
+    }
+
+    #[rstest]
+    #[case(ViMode::Insert)]
+    #[case(ViMode::Normal)]
+    fn alt_r_searches_history_test(#[case] mode: ViMode) {
+        let mut vi = Vi {
+            mode,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vi.parse_event(
+                ReedlineRawEvent::convert_from(Event::Key(KeyEvent::new(
+                    KeyCode::Char('r'),
+                    KeyModifiers::ALT,
+                )))
+                .unwrap()
+            ),
+            ReedlineEvent::SearchHistory
+        );
     }
 }
@@ -1,6 +1,6 @@
 use std::iter::Peekable;
 
-use crate::{EditCommand, ReedlineEvent, Vi};
+use crate::{EditCommand, ReedlineEvent, TextObject, TextObjectKind, Vi};
 
 use super::parser::{ParseResult, ReedlineOption};
 
@@ -100,6 +100,69 @@ where
                 None => ParseResult::Incomplete,
             }
         }
+        Some('g') => {
+            let _ = input.next();
+            match input.next() {
+                Some('g') => ParseResult::Valid(Motion::FirstLine),
+                Some('e') => ParseResult::Valid(Motion::PrevWordEnd),
+                Some('E') => ParseResult::Valid(Motion::PrevBigWordEnd),
+                Some('i') => ParseResult::Valid(Motion::IndentBlock),
+                Some(_) => ParseResult::Invalid,
+                None => ParseResult::Incomplete,
+            }
+        }
+        Some('G') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::LastLine(None))
+        }
+        Some('%') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::MatchingBracket)
+        }
+        Some(')') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::NextSentence)
+        }
+        Some('(') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::PrevSentence)
+        }
+        Some('}') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::NextParagraph)
+        }
+        Some('{') => {
+            let _ = input.next();
+            ParseResult::Valid(Motion::PrevParagraph)
+        }
+        Some('`') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => ParseResult::Valid(Motion::ToMark(*c)),
+                None => ParseResult::Incomplete,
+            }
+        }
+        Some('\'') => {
+            let _ = input.next();
+            match input.next() {
+                Some(c) => ParseResult::Valid(Motion::ToMarkLine(*c)),
+                None => ParseResult::Incomplete,
+            }
+        }
+        Some(&&scope @ ('i' | 'a')) => {
+            let _ = input.next();
+            match input.next() {
+                Some('g') => ParseResult::Valid(Motion::WholeBuffer),
+                Some(c) => match parse_text_object_kind(*c) {
+                    Some(kind) => ParseResult::Valid(Motion::TextObject {
+                        around: scope == 'a',
+                        kind,
+                    }),
+                    None => ParseResult::Invalid,
+                },
+                None => ParseResult::Incomplete,
+            }
+        }
         Some(';') => {
             let _ = input.next();
             ParseResult::Valid(Motion::ReplayCharSearch)
@@ -117,6 +180,21 @@ where
     }
 }
 
+fn parse_text_object_kind(c: char) -> Option<TextObjectKind> {
+    match c {
+        'w' => Some(TextObjectKind::Word),
+        'W' => Some(TextObjectKind::BigWord),
+        '(' | ')' | 'b' => Some(TextObjectKind::Parentheses),
+        '[' | ']' => Some(TextObjectKind::Brackets),
+        '{' | '}' | 'B' => Some(TextObjectKind::Braces),
+        '"' => Some(TextObjectKind::DoubleQuote),
+        '\'' => Some(TextObjectKind::SingleQuote),
+        '`' => Some(TextObjectKind::Backtick),
+        'p' => Some(TextObjectKind::Paragraph),
+        _ => None,
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Motion {
     Left,
@@ -129,6 +207,8 @@ pub enum Motion {
     NextBigWordEnd,
     PreviousWord,
     PreviousBigWord,
+    PrevWordEnd,
+    PrevBigWordEnd,
     Line,
     Start,
     End,
@@ -138,11 +218,233 @@ pub enum Motion {
     LeftBefore(char),
     ReplayCharSearch,
     ReverseCharSearch,
+    TextObject { around: bool, kind: TextObjectKind },
+    ToMark(char),
+    ToMarkLine(char),
+    MatchingBracket,
+    NextSentence,
+    PrevSentence,
+    NextParagraph,
+    PrevParagraph,
+    FirstLine,
+    LastLine(Option<usize>),
+    IndentBlock,
+    WholeBuffer,
+}
+
+impl Motion {
+    /// The text object this motion describes, if it is one
+    pub fn text_object(&self) -> Option<TextObject> {
+        match self {
+            Motion::TextObject { around, kind } => Some(TextObject {
+                around: *around,
+                kind: *kind,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Selecting move to the target line of the linewise `gg` and `G` motions
+    pub fn select_line_target(&self) -> Option<EditCommand> {
+        match self {
+            Motion::FirstLine => Some(EditCommand::MoveToStart { select: true }),
+            Motion::LastLine(None) => Some(EditCommand::MoveToEnd { select: true }),
+            Motion::LastLine(Some(line)) => Some(EditCommand::MoveToLine {
+                line: *line,
+                select: true,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Selection from the end of the previous word or WORD through the cursor character, as
+    /// `ge` and `gE` are inclusive on both ends
+    pub fn select_prev_word_end(&self) -> Option<Vec<EditCommand>> {
+        let target = match self {
+            Motion::PrevWordEnd => EditCommand::MoveWordLeftEnd { select: true },
+            Motion::PrevBigWordEnd => EditCommand::MoveBigWordLeftEnd { select: true },
+            _ => return None,
+        };
+        // Anchor the selection on the cursor character, covering it
+        Some(vec![EditCommand::StartInclusiveSelection, target])
+    }
+
+    /// The character search done by the motion, with `;` and `,` referring to the last one
+    pub fn char_search(&self, vi_state: &Vi) -> Option<ViCharSearch> {
+        match self {
+            Motion::RightUntil(c) => Some(ViCharSearch::ToRight(*c)),
+            Motion::RightBefore(c) => Some(ViCharSearch::TillRight(*c)),
+            Motion::LeftUntil(c) => Some(ViCharSearch::ToLeft(*c)),
+            Motion::LeftBefore(c) => Some(ViCharSearch::TillLeft(*c)),
+            Motion::ReplayCharSearch => vi_state.last_char_search.clone(),
+            Motion::ReverseCharSearch => vi_state
+                .last_char_search
+                .as_ref()
+                .map(ViCharSearch::reverse),
+            _ => None,
+        }
+    }
+
+    /// Whether a count makes the motion span more text, rather than repeat going to the same
+    /// place
+    pub fn extends_with_count(&self) -> bool {
+        matches!(
+            self,
+            Motion::Line
+                | Motion::Up
+                | Motion::Down
+                | Motion::Left
+                | Motion::Right
+                | Motion::NextWord
+                | Motion::NextBigWord
+                | Motion::NextWordEnd
+                | Motion::NextBigWordEnd
+                | Motion::PreviousWord
+                | Motion::PreviousBigWord
+                | Motion::PrevWordEnd
+                | Motion::PrevBigWordEnd
+                | Motion::End
+                | Motion::NextSentence
+                | Motion::PrevSentence
+                | Motion::NextParagraph
+                | Motion::PrevParagraph
+        )
+    }
+
+    /// Selecting move to the target of the sentence and paragraph motions
+    pub fn select_text_block_target(&self) -> Option<EditCommand> {
+        match self {
+            Motion::NextSentence => Some(EditCommand::MoveSentenceRight { select: true }),
+            Motion::PrevSentence => Some(EditCommand::MoveSentenceLeft { select: true }),
+            Motion::NextParagraph => Some(EditCommand::MoveParagraphRight { select: true }),
+            Motion::PrevParagraph => Some(EditCommand::MoveParagraphLeft { select: true }),
+            _ => None,
+        }
+    }
 }
 
 impl Motion {
     pub fn to_reedline(&self, vi_state: &mut Vi) -> Vec<ReedlineOption> {
+        // In visual mode every motion extends the selection
+        let select = vi_state.mode.is_visual();
+        self.to_moves(vi_state, select)
+    }
+
+    /// Select the span of the motion repeated `times`, for operators transforming the text in
+    /// place
+    ///
+    /// Returns `None` for motions that can't extend a selection
+    pub fn to_selection(&self, vi_state: &mut Vi, times: usize) -> Option<Vec<ReedlineOption>> {
+        match self {
+            Motion::Up => Some(vec![
+                ReedlineOption::Edit(EditCommand::MoveCursorLineUp {
+                    select: true
+                });
+                times
+            ]),
+            Motion::Down => Some(vec![
+                ReedlineOption::Edit(EditCommand::MoveCursorLineDown {
+                    select: true
+                });
+                times
+            ]),
+            Motion::Line => Some(
+                [
+                    EditCommand::MoveToLineStart { select: false },
+                    EditCommand::MoveToLineEnd { select: true },
+                ]
+                .into_iter()
+                .chain(
+                    std::iter::repeat([
+                        EditCommand::MoveRight { select: true },
+                        EditCommand::MoveToLineEnd { select: true },
+                    ])
+                    .take(times.saturating_sub(1))
+                    .flatten(),
+                )
+                .map(ReedlineOption::Edit)
+                .collect(),
+            ),
+            // Each further count reaches the end of the next line
+            Motion::End => Some(
+                std::iter::once(EditCommand::MoveToLineEnd { select: true })
+                    .chain(
+                        std::iter::repeat([
+                            EditCommand::MoveRight { select: true },
+                            EditCommand::MoveToLineEnd { select: true },
+                        ])
+                        .take(times.saturating_sub(1))
+                        .flatten(),
+                    )
+                    .map(ReedlineOption::Edit)
+                    .collect(),
+            ),
+            Motion::TextObject { .. } => self.text_object().map(|text_object| {
+                vec![ReedlineOption::Edit(EditCommand::SelectTextObject {
+                    text_object,
+                })]
+            }),
+            Motion::PrevWordEnd | Motion::PrevBigWordEnd => {
+                self.select_prev_word_end().map(|mut selection| {
+                    let target = selection.pop().expect("ends on the target");
+                    selection
+                        .into_iter()
+                        .chain(std::iter::repeat(target).take(times))
+                        .map(ReedlineOption::Edit)
+                        .collect()
+                })
+            }
+            _ => {
+                let moves = self.to_moves(vi_state, true);
+                if moves.is_empty() {
+                    return None;
+                }
+                let mut selection = std::iter::repeat(moves)
+                    .take(times)
+                    .flatten()
+                    .collect::<Vec<_>>();
+                // Inclusive motions end on the last character of the span
+                if self.is_inclusive(vi_state) {
+                    selection.push(ReedlineOption::Edit(EditCommand::MoveRight {
+                        select: true,
+                    }));
+                }
+                Some(selection)
+            }
+        }
+    }
+
+    fn is_inclusive(&self, vi_state: &Vi) -> bool {
+        let is_forward_search = |char_search: Option<ViCharSearch>| {
+            matches!(
+                char_search,
+                Some(ViCharSearch::ToRight(_) | ViCharSearch::TillRight(_))
+            )
+        };
         match self {
+            Motion::NextWordEnd
+            | Motion::NextBigWordEnd
+            | Motion::RightUntil(_)
+            | Motion::RightBefore(_) => true,
+            Motion::ReplayCharSearch => is_forward_search(vi_state.last_char_search.clone()),
+            Motion::ReverseCharSearch => is_forward_search(
+                vi_state
+                    .last_char_search
+                    .as_ref()
+                    .map(ViCharSearch::reverse),
+            ),
+            _ => false,
+        }
+    }
+
+    fn to_moves(&self, vi_state: &mut Vi, select: bool) -> Vec<ReedlineOption> {
+        match self {
+            Motion::Left if select => {
+                vec![ReedlineOption::Edit(EditCommand::MoveLeft { select })]
+            }
+            Motion::Right if select => {
+                vec![ReedlineOption::Edit(EditCommand::MoveRight { select })]
+            }
             Motion::Left => vec![ReedlineOption::Event(ReedlineEvent::UntilFound(vec![
                 ReedlineEvent::MenuLeft,
                 ReedlineEvent::Left,
@@ -161,70 +463,122 @@ impl Motion {
                 ReedlineEvent::Down,
             ]))],
             Motion::NextWord => vec![ReedlineOption::Edit(EditCommand::MoveWordRightStart {
-                select: false,
+                select,
             })],
             Motion::NextBigWord => vec![ReedlineOption::Edit(EditCommand::MoveBigWordRightStart {
-                select: false,
+                select,
             })],
             Motion::NextWordEnd => vec![ReedlineOption::Edit(EditCommand::MoveWordRightEnd {
-                select: false,
+                select,
             })],
             Motion::NextBigWordEnd => {
                 vec![ReedlineOption::Edit(EditCommand::MoveBigWordRightEnd {
-                    select: false,
+                    select,
                 })]
             }
-            Motion::PreviousWord => vec![ReedlineOption::Edit(EditCommand::MoveWordLeft {
-                select: false,
-            })],
+            Motion::PreviousWord => {
+                vec![ReedlineOption::Edit(EditCommand::MoveWordLeft { select })]
+            }
             Motion::PreviousBigWord => vec![ReedlineOption::Edit(EditCommand::MoveBigWordLeft {
-                select: false,
+                select,
             })],
-            Motion::Line => vec![], // Placeholder as unusable standalone motion
-            Motion::Start => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart {
-                select: false,
+            Motion::PrevWordEnd => {
+                vec![ReedlineOption::Edit(EditCommand::MoveWordLeftEnd {
+                    select,
+                })]
+            }
+            Motion::PrevBigWordEnd => vec![ReedlineOption::Edit(EditCommand::MoveBigWordLeftEnd {
+                select,
             })],
-            Motion::End => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd {
-                select: false,
+            Motion::FirstLine => vec![ReedlineOption::Edit(EditCommand::MoveToStart { select })],
+            Motion::LastLine(None) => vec![
+                ReedlineOption::Edit(EditCommand::MoveToEnd { select }),
+                ReedlineOption::Edit(EditCommand::MoveToLineStart { select }),
+            ],
+            Motion::LastLine(Some(line)) => vec![ReedlineOption::Edit(EditCommand::MoveToLine {
+                line: *line,
+                select,
             })],
+            Motion::MatchingBracket => {
+                vec![ReedlineOption::Edit(EditCommand::MoveToMatchingBracket {
+                    select,
+                })]
+            }
+            Motion::NextSentence => {
+                vec![ReedlineOption::Edit(EditCommand::MoveSentenceRight {
+                    select,
+                })]
+            }
+            Motion::PrevSentence => {
+                vec![ReedlineOption::Edit(EditCommand::MoveSentenceLeft {
+                    select,
+                })]
+            }
+            Motion::NextParagraph => {
+                vec![ReedlineOption::Edit(EditCommand::MoveParagraphRight {
+                    select,
+                })]
+            }
+            Motion::PrevParagraph => {
+                vec![ReedlineOption::Edit(EditCommand::MoveParagraphLeft {
+                    select,
+                })]
+            }
+            Motion::ToMark(mark) => vec![ReedlineOption::Edit(EditCommand::MoveToMark {
+                mark: *mark,
+                select,
+            })],
+            Motion::ToMarkLine(mark) => vec![ReedlineOption::Edit(EditCommand::MoveToMarkLine {
+                mark: *mark,
+                select,
+            })],
+            // Placeholders as unusable standalone motions
+            Motion::Line
+            | Motion::TextObject { .. }
+            | Motion::IndentBlock
+            | Motion::WholeBuffer => vec![],
+            Motion::Start => vec![ReedlineOption::Edit(EditCommand::MoveToLineStart {
+                select,
+            })],
+            Motion::End => vec![ReedlineOption::Edit(EditCommand::MoveToLineEnd { select })],
             Motion::RightUntil(ch) => {
                 vi_state.last_char_search = Some(ViCharSearch::ToRight(*ch));
                 vec![ReedlineOption::Edit(EditCommand::MoveRightUntil {
                     c: *ch,
-                    select: false,
+                    select,
                 })]
             }
             Motion::RightBefore(ch) => {
                 vi_state.last_char_search = Some(ViCharSearch::TillRight(*ch));
                 vec![ReedlineOption::Edit(EditCommand::MoveRightBefore {
                     c: *ch,
-                    select: false,
+                    select,
                 })]
             }
             Motion::LeftUntil(ch) => {
                 vi_state.last_char_search = Some(ViCharSearch::ToLeft(*ch));
                 vec![ReedlineOption::Edit(EditCommand::MoveLeftUntil {
                     c: *ch,
-                    select: false,
+                    select,
                 })]
             }
             Motion::LeftBefore(ch) => {
                 vi_state.last_char_search = Some(ViCharSearch::TillLeft(*ch));
                 vec![ReedlineOption::Edit(EditCommand::MoveLeftBefore {
                     c: *ch,
-                    select: false,
+                    select,
                 })]
             }
             Motion::ReplayCharSearch => {
                 if let Some(char_search) = vi_state.last_char_search.as_ref() {
-                    vec![ReedlineOption::Edit(char_search.to_move())]
+                    vec![ReedlineOption::Edit(char_search.to_move(select))]
                 } else {
                     vec![]
                 }
             }
             Motion::ReverseCharSearch => {
                 if let Some(char_search) = vi_state.last_char_search.as_ref() {
-                    vec![ReedlineOption::Edit(char_search.reverse().to_move())]
+                    vec![ReedlineOption::Edit(char_search.reverse().to_move(select))]
                 } else {
                     vec![]
                 }
@@ -257,27 +611,37 @@ impl ViCharSearch {
         }
     }
 
-    pub fn to_move(&self) -> EditCommand {
+    fn is_forward(&self) -> bool {
+        matches!(self, ViCharSearch::ToRight(_) | ViCharSearch::TillRight(_))
+    }
+
+    /// Move to the character of the search
+    pub fn to_move(&self, select: bool) -> EditCommand {
         match self {
-            ViCharSearch::ToRight(c) => EditCommand::MoveRightUntil {
-                c: *c,
-                select: false,
-            },
-            ViCharSearch::ToLeft(c) => EditCommand::MoveLeftUntil {
-                c: *c,
-                select: false,
-            },
-            ViCharSearch::TillRight(c) => EditCommand::MoveRightBefore {
-                c: *c,
-                select: false,
-            },
-            ViCharSearch::TillLeft(c) => EditCommand::MoveLeftBefore {
-                c: *c,
-                select: false,
-            },
+            ViCharSearch::ToRight(c) => EditCommand::MoveRightUntil { c: *c, select },
+            ViCharSearch::ToLeft(c) => EditCommand::MoveLeftUntil { c: *c, select },
+            ViCharSearch::TillRight(c) => EditCommand::MoveRightBefore { c: *c, select },
+            ViCharSearch::TillLeft(c) => EditCommand::MoveLeftBefore { c: *c, select },
         }
     }
 
+    /// Move to the `count`th occurrence of the character, passing the earlier ones
+    ///
+    /// A till search stops next to the last occurrence only, so it doesn't get stuck in
+    /// front of the first one.
+    pub fn to_move_n(&self, count: usize, select: bool) -> Vec<EditCommand> {
+        let to = match self {
+            ViCharSearch::TillRight(c) => ViCharSearch::ToRight(*c),
+            ViCharSearch::TillLeft(c) => ViCharSearch::ToLeft(*c),
+            to => to.clone(),
+        };
+        std::iter::repeat(to.to_move(select))
+            .take(count.saturating_sub(1))
+            .chain([self.to_move(select)])
+            .collect()
+    }
+
+    /// Cut up to the character of the search
     pub fn to_cut(&self) -> EditCommand {
         match self {
             ViCharSearch::ToRight(c) => EditCommand::CutRightUntil(*c),
@@ -286,4 +650,44 @@ impl ViCharSearch {
             ViCharSearch::TillLeft(c) => EditCommand::CutLeftBefore(*c),
         }
     }
+
+    /// Cut up to the `count`th occurrence of the character as a single cut
+    pub fn to_cut_n(&self, count: usize) -> Vec<EditCommand> {
+        if count <= 1 {
+            return vec![self.to_cut()];
+        }
+        let mut edits = self.select_n(count);
+        edits.push(EditCommand::CutSelection);
+        edits
+    }
+
+    /// Copy up to the character of the search
+    pub fn to_copy(&self) -> EditCommand {
+        match self {
+            ViCharSearch::ToRight(c) => EditCommand::CopyRightUntil(*c),
+            ViCharSearch::ToLeft(c) => EditCommand::CopyLeftUntil(*c),
+            ViCharSearch::TillRight(c) => EditCommand::CopyRightBefore(*c),
+            ViCharSearch::TillLeft(c) => EditCommand::CopyLeftBefore(*c),
+        }
+    }
+
+    /// Copy up to the `count`th occurrence of the character, keeping the cursor
+    pub fn to_copy_n(&self, count: usize) -> Vec<EditCommand> {
+        if count <= 1 {
+            return vec![self.to_copy()];
+        }
+        let mut edits = self.select_n(count);
+        edits.push(EditCommand::YankSelection);
+        edits
+    }
+
+    /// Select up to the `count`th occurrence, including it when searching forward as `f` and
+    /// `t` are inclusive
+    fn select_n(&self, count: usize) -> Vec<EditCommand> {
+        let mut edits = self.to_move_n(count, true);
+        if self.is_forward() {
+            edits.push(EditCommand::MoveRight { select: true });
+        }
+        edits
+    }
 }
@@ -1,8 +1,14 @@
 use super::command::{parse_command, Command};
 use super::motion::{parse_motion, Motion};
-use crate::{EditCommand, ReedlineEvent, Vi};
+use super::ViMode;
+use crate::{core_editor::Registers, EditCommand, ReedlineEvent, Vi};
 use std::iter::Peekable;
 
+/// Upper bound for the repeat count of a single vi sequence
+///
+/// Guards against pathological inputs like `99999999x` locking up the editor
+const MAX_COUNT: usize = 1000;
+
 #[derive(Debug, Clone)]
 pub enum ReedlineOption {
     Event(ReedlineEvent),
@@ -39,6 +45,7 @@ impl<T> ParseResult<T> {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsedViSequence {
+    register: Option<char>,
     multiplier: Option<usize>,
     command: Option<Command>,
     count: Option<usize>,
@@ -61,21 +68,42 @@ impl ParsedViSequence {
         }
     }
 
+    /// An operator without motion, which in visual mode acts on the selection
+    pub fn operates_on_selection(&self) -> bool {
+        matches!(
+            (&self.command, &self.motion),
+            (Some(cmd), ParseResult::Incomplete) if cmd.requires_motion()
+        )
+    }
+
     /// Combine `multiplier` and `count` as vim only considers the product
     ///
-    /// Default return value: 1
+    /// Default return value: 1, capped at [`MAX_COUNT`]
     ///
     /// ### Note:
     ///
     /// <https://github.com/vim/vim/blob/140f6d0eda7921f2f0b057ec38ed501240903fc3/runtime/doc/motion.txt#L64-L70>
     fn total_multiplier(&self) -> usize {
-        self.multiplier.unwrap_or(1) * self.count.unwrap_or(1)
+        self.multiplier
+            .unwrap_or(1)
+            .saturating_mul(self.count.unwrap_or(1))
+            .min(MAX_COUNT)
     }
 
     fn apply_multiplier(&self, raw_events: Option<Vec<ReedlineOption>>) -> ReedlineEvent {
-        if let Some(raw_events) = raw_events {
+        self.apply_times(raw_events, self.total_multiplier())
+    }
+
+    fn apply_times(&self, raw_events: Option<Vec<ReedlineOption>>, times: usize) -> ReedlineEvent {
+        if let Some(mut raw_events) = raw_events {
+            if let Some(register) = self.register {
+                raw_events.insert(
+                    0,
+                    ReedlineOption::Edit(EditCommand::SelectRegister(register)),
+                );
+            }
             let events = std::iter::repeat(raw_events)
-                .take(self.total_multiplier())
+                .take(times)
                 .flatten()
                 .filter_map(ReedlineOption::into_reedline_event)
                 .collect::<Vec<ReedlineEvent>>();
@@ -99,33 +127,236 @@ impl ParsedViSequence {
                 | (Some(Command::ChangeToLineEnd), ParseResult::Incomplete)
                 | (Some(Command::AppendToEnd), ParseResult::Incomplete)
                 | (Some(Command::PrependToStart), ParseResult::Incomplete)
+                | (Some(Command::OpenLineBelow), ParseResult::Incomplete)
+                | (Some(Command::OpenLineAbove), ParseResult::Incomplete)
                 | (Some(Command::RewriteCurrentLine), ParseResult::Incomplete)
                 | (
                     Some(Command::SubstituteCharWithInsert),
                     ParseResult::Incomplete
                 )
                 | (Some(Command::HistorySearch), ParseResult::Incomplete)
+                | (Some(Command::ResumeInsert), ParseResult::Incomplete)
                 | (Some(Command::Change), ParseResult::Valid(_))
         )
     }
 
+    /// The mode the editor should switch to after this sequence, given the current `mode`
+    pub fn changes_mode(&self, mode: ViMode) -> Option<ViMode> {
+        match (&self.command, &self.motion) {
+            (Some(Command::EnterViVisual), ParseResult::Incomplete) => {
+                Some(if mode == ViMode::Visual {
+                    ViMode::Normal
+                } else {
+                    ViMode::Visual
+                })
+            }
+            (Some(Command::EnterViVisualLine), ParseResult::Incomplete) => {
+                Some(if mode == ViMode::VisualLine {
+                    ViMode::Normal
+                } else {
+                    ViMode::VisualLine
+                })
+            }
+            (Some(Command::EnterViReplace), ParseResult::Incomplete) => Some(ViMode::Replace),
+            (Some(Command::Change), ParseResult::Incomplete) if mode.is_visual() => {
+                Some(ViMode::Insert)
+            }
+            (
+                Some(
+                    Command::Delete
+                    | Command::Yank
+                    | Command::Lowercase
+                    | Command::Uppercase
+                    | Command::ToggleCase
+                    | Command::Indent
+                    | Command::Dedent
+                    | Command::PasteAfter,
+                ),
+                ParseResult::Incomplete,
+            ) if mode.is_visual() => Some(ViMode::Normal),
+            _ if self.enters_insert_mode() => Some(ViMode::Insert),
+            _ => None,
+        }
+    }
+
     pub fn to_reedline_event(&self, vi_state: &mut Vi) -> ReedlineEvent {
         match (&self.multiplier, &self.command, &self.count, &self.motion) {
+            // The replayed keys repeat as a whole and set their own previous action
+            (_, Some(Command::ReplayMacro(register)), None, ParseResult::Incomplete) => {
+                vi_state.replay_macro(*register, self.total_multiplier())
+            }
+            // A counted replacement happens at once, as it must fit into the line
+            (Some(_), Some(Command::ReplaceChar(c)), None, ParseResult::Incomplete) => {
+                let events = self.apply_times(
+                    Some(vec![ReedlineOption::Edit(
+                        EditCommand::ReplaceCharsInLine {
+                            c: *c,
+                            count: self.total_multiplier(),
+                        },
+                    )]),
+                    1,
+                );
+                vi_state.previous = Some(events.clone());
+                vi_state.previous_once = Some(self.apply_times(
+                    Some(vec![ReedlineOption::Edit(EditCommand::ReplaceChar(*c))]),
+                    1,
+                ));
+                events
+            }
+            // The count names the number of lines instead of repeating the copy
+            (_, Some(Command::YankLines), None, ParseResult::Incomplete) => self.apply_times(
+                Some(vec![ReedlineOption::Edit(EditCommand::CopyLines(
+                    self.total_multiplier(),
+                ))]),
+                1,
+            ),
+            // A counted paste happens at once, to leave the cursor where vim does
+            (
+                Some(_),
+                Some(command @ (Command::PasteAfter | Command::PasteBefore)),
+                None,
+                ParseResult::Incomplete,
+            ) => {
+                let count = self.total_multiplier();
+                let paste = if *command == Command::PasteAfter {
+                    EditCommand::PasteCutBufferAfterTimes(count)
+                } else {
+                    EditCommand::PasteCutBufferBeforeTimes(count)
+                };
+                let events = self.apply_times(Some(vec![ReedlineOption::Edit(paste)]), 1);
+                vi_state.previous = Some(events.clone());
+                vi_state.previous_once =
+                    Some(self.apply_times(Some(command.to_reedline(vi_state)), 1));
+                events
+            }
+            (_, Some(Command::RepeatLastAction), None, ParseResult::Incomplete) => {
+                vi_state.repeat_previous(self.multiplier.map(|_| self.total_multiplier()))
+            }
             (_, Some(command), None, ParseResult::Incomplete) => {
-                let events = self.apply_multiplier(Some(command.to_reedline(vi_state)));
+                let raw_events = command.to_reedline(vi_state);
+                let events = self.apply_multiplier(Some(raw_events.clone()));
                 match &events {
                     ReedlineEvent::None => {}
-                    event => vi_state.previous = Some(event.clone()),
+                    _ if !command.is_repeatable() => {}
+                    event => {
+                        vi_state.previous = Some(event.clone());
+                        vi_state.previous_once = Some(self.apply_times(Some(raw_events), 1));
+                    }
                 }
                 events
             }
+            // A single line changes case without being selected
+            (_, Some(command), _, ParseResult::Valid(Motion::Line))
+                if self.total_multiplier() == 1 && command.transform_line().is_some() =>
+            {
+                let events = self.apply_times(
+                    command
+                        .transform_line()
+                        .map(|transform| vec![ReedlineOption::Edit(transform)]),
+                    1,
+                );
+                vi_state.previous = Some(events.clone());
+                vi_state.previous_once = None;
+                events
+            }
+            // In-place operators transform the span of the counted motion once
+            (_, Some(command), _, ParseResult::Valid(motion))
+                if command.transform_selection(vi_state).is_some() =>
+            {
+                let transform = command.transform_selection(vi_state);
+                let events = self.apply_times(
+                    motion
+                        .to_selection(vi_state, self.total_multiplier())
+                        .map(|mut selection| {
+                            selection.extend(transform.map(ReedlineOption::Edit));
+                            selection
+                        }),
+                    1,
+                );
+                if events != ReedlineEvent::None {
+                    // The count is part of the selection, so a counted `.` repeats it whole
+                    vi_state.previous = Some(events.clone());
+                    vi_state.previous_once = None;
+                }
+                events
+            }
+            // A counted character search goes to the nth occurrence at once
+            (
+                _,
+                command @ (None | Some(Command::Delete | Command::Change | Command::Yank)),
+                _,
+                ParseResult::Valid(motion),
+            ) if self.total_multiplier() > 1 && motion.char_search(vi_state).is_some() => {
+                let count = self.total_multiplier();
+                let char_search = motion
+                    .char_search(vi_state)
+                    .expect("guarded to be a character search");
+                if !matches!(motion, Motion::ReplayCharSearch | Motion::ReverseCharSearch) {
+                    vi_state.last_char_search = Some(char_search.clone());
+                }
+                let mut edits = match command {
+                    Some(Command::Delete | Command::Change) => char_search.to_cut_n(count),
+                    Some(_) => char_search.to_copy_n(count),
+                    None => char_search.to_move_n(count, vi_state.mode.is_visual()),
+                }
+                .into_iter()
+                .map(ReedlineOption::Edit)
+                .collect::<Vec<_>>();
+                if command == &Some(Command::Change) {
+                    // Semihack: Append `Repaint` to ensure the mode change gets displayed
+                    edits.push(ReedlineOption::Event(ReedlineEvent::Repaint));
+                }
+                let events = self.apply_times(Some(edits), 1);
+                if let Some(command @ (Command::Delete | Command::Change)) = command {
+                    vi_state.previous = Some(events.clone());
+                    vi_state.previous_once = Some(
+                        self.apply_times(command.to_reedline_with_motion(motion, vi_state), 1),
+                    );
+                }
+                events
+            }
+            // Yanking up or down copies the lines the motion spans, one more per count, at once
+            // and keeps the cursor
+            (
+                _,
+                Some(Command::Yank),
+                _,
+                ParseResult::Valid(motion @ (Motion::Up | Motion::Down)),
+            ) => {
+                let count = self.total_multiplier();
+                let edits = motion.to_selection(vi_state, count).map(|mut selection| {
+                    selection.push(ReedlineOption::Edit(EditCommand::YankSelectedLines));
+                    selection
+                });
+                self.apply_times(edits, 1)
+            }
+            // A counted yank copies the span of the counted motion at once and keeps the
+            // cursor, like the yank commands do for a single motion. Motions a count doesn't
+            // extend repeat the yank like other commands
+            (_, Some(Command::Yank), _, ParseResult::Valid(motion))
+                if self.total_multiplier() > 1 && motion.extends_with_count() =>
+            {
+                let count = self.total_multiplier();
+                let edits = match motion {
+                    Motion::Line => Some(vec![ReedlineOption::Edit(EditCommand::CopyLines(count))]),
+                    _ => motion.to_selection(vi_state, count).map(|mut selection| {
+                        selection.push(ReedlineOption::Edit(EditCommand::YankSelection));
+                        selection
+                    }),
+                };
+                self.apply_times(edits, 1)
+            }
             // This case handles all combinations of commands and motions that could exist
             (_, Some(command), _, ParseResult::Valid(motion)) => {
-                let events =
-                    self.apply_multiplier(command.to_reedline_with_motion(motion, vi_state));
+                let raw_events = command.to_reedline_with_motion(motion, vi_state);
+                let events = self.apply_multiplier(raw_events.clone());
                 match &events {
                     ReedlineEvent::None => {}
-                    event => vi_state.previous = Some(event.clone()),
+                    _ if !command.is_repeatable() => {}
+                    event => {
+                        vi_state.previous = Some(event.clone());
+                        vi_state.previous_once = Some(self.apply_times(raw_events, 1));
+                    }
                 }
                 events
             }
@@ -149,8 +380,7 @@ where
                 if c.is_ascii_digit() {
                     let c = c.to_digit(10).expect("already checked if is a digit");
                     let _ = input.next();
-                    count *= 10;
-                    count += c as usize;
+                    count = count.saturating_mul(10).saturating_add(c as usize);
                 } else {
                     return Some(count);
                 }
@@ -161,16 +391,62 @@ where
     }
 }
 
-pub fn parse<'iter, I>(input: &mut Peekable<I>) -> ParsedViSequence
+/// Parse a `"<name>` register prefix
+fn parse_register<'iter, I>(input: &mut Peekable<I>) -> ParseResult<Option<char>>
 where
     I: Iterator<Item = &'iter char>,
 {
-    let multiplier = parse_number(input);
+    match input.peek() {
+        Some('"') => {
+            let _ = input.next();
+            match input.next() {
+                Some(&register) if Registers::is_valid_name(register) => {
+                    ParseResult::Valid(Some(register))
+                }
+                Some(_) => ParseResult::Invalid,
+                None => ParseResult::Incomplete,
+            }
+        }
+        _ => ParseResult::Valid(None),
+    }
+}
+
+pub fn parse<'iter, I>(input: &mut Peekable<I>) -> ParsedViSequence
+where
+    I: Iterator<Item = &'iter char> + Clone,
+{
+    let register = match parse_register(input) {
+        ParseResult::Valid(register) => register,
+        invalid_or_incomplete => {
+            return ParsedViSequence {
+                register: None,
+                multiplier: None,
+                command: None,
+                count: None,
+                motion: match invalid_or_incomplete {
+                    ParseResult::Invalid => ParseResult::Invalid,
+                    _ => ParseResult::Incomplete,
+                },
+            }
+        }
+    };
+    let mut multiplier = parse_number(input);
     let command = parse_command(input);
-    let count = parse_number(input);
-    let motion = parse_motion(input, command.as_ref().and_then(Command::whole_line_char));
+    let mut count = parse_number(input);
+    let mut motion = parse_motion(input, command.as_ref().and_then(Command::whole_line_char));
+
+    // A count in front of `G` names the target line instead of repeating the motion
+    if let ParseResult::Valid(Motion::LastLine(None)) = motion {
+        if multiplier.is_some() || count.is_some() {
+            let line = multiplier.unwrap_or(1).saturating_mul(count.unwrap_or(1));
+            motion = ParseResult::Valid(Motion::LastLine(Some(line)));
+            multiplier = None;
+            count = None;
+        }
+    }
 
     ParsedViSequence {
+        register,
         multiplier,
         command,
         count,
@@ -181,6 +457,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{edit_mode::vi::command::REDO_KEY, TextObject, TextObjectKind};
     use pretty_assertions::assert_eq;
     use rstest::rstest;
 
@@ -196,6 +473,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Delete),
                 count: None,
@@ -214,6 +492,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -232,6 +511,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(2),
@@ -250,6 +530,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: Some(20),
@@ -268,6 +549,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -278,6 +560,75 @@ mod tests {
         assert_eq!(output.is_complete(), true);
     }
 
+    #[test]
+    fn test_delete_to_line_start_is_not_a_count() {
+        let input = ['d', '0'];
+        let output = vi_parse(&input);
+
+        assert_eq!(
+            output,
+            ParsedViSequence {
+                register: None,
+                multiplier: None,
+                command: Some(Command::Delete),
+                count: None,
+                motion: ParseResult::Valid(Motion::Start),
+            }
+        );
+        assert_eq!(output.is_valid(), true);
+        assert_eq!(output.is_complete(), true);
+    }
+
+    #[test]
+    fn test_huge_count_is_capped() {
+        let input = ['9'; 40]
+            .iter()
+            .chain(['x'].iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let output = vi_parse(&input);
+
+        assert_eq!(output.command, Some(Command::DeleteChar));
+        assert_eq!(output.total_multiplier(), MAX_COUNT);
+    }
+
+    #[test]
+    fn test_partial_text_object() {
+        let input = ['d', 'i'];
+        let output = vi_parse(&input);
+
+        assert_eq!(output.motion, ParseResult::Incomplete);
+        assert_eq!(output.is_valid(), true);
+        assert_eq!(output.is_complete(), false);
+
+        let output = vi_parse(&['d', 'i', 'x']);
+        assert_eq!(output.is_valid(), false);
+    }
+
+    #[test]
+    fn test_register_prefix() {
+        let output = vi_parse(&['"', 'b', '2', 'd', 'w']);
+
+        assert_eq!(
+            output,
+            ParsedViSequence {
+                register: Some('b'),
+                multiplier: Some(2),
+                command: Some(Command::Delete),
+                count: None,
+                motion: ParseResult::Valid(Motion::NextWord),
+            }
+        );
+        assert_eq!(output.is_complete(), true);
+
+        let output = vi_parse(&['"']);
+        assert_eq!(output.is_valid(), true);
+        assert_eq!(output.is_complete(), false);
+
+        let output = vi_parse(&['"', '%']);
+        assert_eq!(output.is_valid(), false);
+    }
+
     #[test]
     fn test_find_action() {
         let input = ['d', 't', 'd'];
@@ -286,6 +637,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Delete),
                 count: None,
@@ -304,6 +656,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: Some(Command::Delete),
                 count: None,
@@ -321,6 +674,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::Incomplete),
                 count: None,
@@ -340,6 +694,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: None,
                 count: None,
@@ -358,6 +713,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: None,
                 command: Some(Command::ReplaceChar('k')),
                 count: None,
@@ -377,6 +733,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: None,
                 count: None,
@@ -395,6 +752,7 @@ mod tests {
         assert_eq!(
             output,
             ParsedViSequence {
+                register: None,
                 multiplier: Some(2),
                 command: None,
                 count: None,
@@ -441,21 +799,196 @@ mod tests {
     #[case(&['i'], ReedlineEvent::Multiple(vec![ReedlineEvent::Repaint]))]
     #[case(&['p'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
     #[case(&['2', 'p'], ReedlineEvent::Multiple(vec![
-        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter]),
-        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfterTimes(2)])
+        ]))]
+    #[case(&['3', 'P'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferBeforeTimes(3)])
         ]))]
     #[case(&['u'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Undo])]))]
     #[case(&['2', 'u'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::Undo]),
         ReedlineEvent::Edit(vec![EditCommand::Undo])
         ]))]
+    #[case(&[REDO_KEY], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::Redo])]))]
+    #[case(&['2', REDO_KEY], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::Redo]),
+        ReedlineEvent::Edit(vec![EditCommand::Redo])
+        ]))]
     #[case(&['d', 'd'], ReedlineEvent::Multiple(vec![
         ReedlineEvent::Edit(vec![EditCommand::CutCurrentLine])]))]
+    #[case(&['s'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutChar]),
+        ReedlineEvent::Repaint
+        ]))]
+    #[case(&['c', 'c'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ChangeLine { keep_indent: true }]),
+        ReedlineEvent::Repaint
+        ]))]
+    #[case(&['S'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ChangeLine { keep_indent: true }]),
+        ReedlineEvent::Repaint
+        ]))]
+    #[case(&['C'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::ClearToLineEnd]),
+        ReedlineEvent::Repaint
+        ]))]
+    #[case(&['r', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::ReplaceChar('a')])]))]
+    #[case(&['3', 'r', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::ReplaceCharsInLine { c: 'a', count: 3 }
+        ])]))]
+    #[case(&['g', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveWordLeftEnd { select: false }
+        ])]))]
+    #[case(&['d', 'g', 'E'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::StartInclusiveSelection]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveBigWordLeftEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])
+        ]))]
+    #[case(&['g', 'i'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMark { mark: '^', select: false }
+        ])]))]
+    #[case(&['Y'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyLines(1)])]))]
+    #[case(&['2', 'Y'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyLines(2)])]))]
     #[case(&['d', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext])]))]
     #[case(&['d', 'W'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordRightToNext])]))]
     #[case(&['d', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordRight])]))]
     #[case(&['d', 'b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutWordLeft])]))]
     #[case(&['d', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutBigWordLeft])]))]
+    #[case(&['3', 'x'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutChar]),
+        ReedlineEvent::Edit(vec![EditCommand::CutChar]),
+        ReedlineEvent::Edit(vec![EditCommand::CutChar])
+        ]))]
+    #[case(&['d', '2', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext]),
+        ReedlineEvent::Edit(vec![EditCommand::CutWordRightToNext])
+        ]))]
+    #[case(&['d', 'i', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CutTextObject { text_object: TextObject { around: false, kind: TextObjectKind::Word } }
+        ])]))]
+    #[case(&['c', 'a', '('], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CutTextObject { text_object: TextObject { around: true, kind: TextObjectKind::Parentheses } }
+        ]), ReedlineEvent::Repaint]))]
+    #[case(&['d', 'a', 'p'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CutTextObject { text_object: TextObject { around: true, kind: TextObjectKind::Paragraph } }
+        ])]))]
+    #[case(&['c', 'i', 'B'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CutTextObject { text_object: TextObject { around: false, kind: TextObjectKind::Braces } }
+        ]), ReedlineEvent::Repaint]))]
+    #[case(&['y', 'i', '"'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::CopyTextObject { text_object: TextObject { around: false, kind: TextObjectKind::DoubleQuote } }
+        ])]))]
+    #[case(&['g', 'u', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightStart { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::LowercaseSelection])]))]
+    #[case(&['g', 'U', '2', 'e'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveWordRightEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::UppercaseSelection])]))]
+    #[case(&['g', 'U', 'i', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectTextObject { text_object: TextObject { around: false, kind: TextObjectKind::Word } }]),
+        ReedlineEvent::Edit(vec![EditCommand::UppercaseSelection])]))]
+    #[case(&['g', '~', '$'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::SwitchcaseSelection])]))]
+    #[case(&['g', 'u', 'u'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::LowercaseLine])]))]
+    #[case(&['g', 'U', 'U'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::UppercaseLine])]))]
+    #[case(&['2', 'g', 'u', 'u'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart { select: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::LowercaseSelection])]))]
+    #[case(&['>', '>'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart { select: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::IndentLines("    ".into())])]))]
+    #[case(&['3', '>', '>'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineStart { select: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveToLineEnd { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::IndentLines("    ".into())])]))]
+    #[case(&['<', 'j'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveCursorLineDown { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::DedentLines("    ".into())])]))]
+    #[case(&['o'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::OpenLine { above: false }])]))]
+    #[case(&['O'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::OpenLine { above: true }])]))]
+    #[case(&['J'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::JoinLines { smart: true }])]))]
+    #[case(&['2', 'g', 'J'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::JoinLines { smart: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::JoinLines { smart: false }])]))]
+    #[case(&['g', 'g'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToStart { select: false }])]))]
+    #[case(&['5', 'G'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToLine { line: 5, select: false }])]))]
+    #[case(&['d', 'G'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToEnd { select: true }]), ReedlineEvent::Edit(vec![EditCommand::CutSelectedLines])]))]
+    #[case(&['y', 'g', 'g'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToStart { select: true }]), ReedlineEvent::Edit(vec![EditCommand::YankSelectedLines])]))]
+    #[case(&['%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveToMatchingBracket { select: false }])]))]
+    #[case(&['2', 't', 'x'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveRightUntil { c: 'x', select: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveRightBefore { c: 'x', select: false }])]))]
+    #[case(&['d', '2', 'F', 'x'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveLeftUntil { c: 'x', select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveLeftUntil { c: 'x', select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['d', '%'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutToMatchingBracket])]))]
+    #[case(&['d', 'g', 'i'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutIndentBlock("    ".into())])]))]
+    #[case(&['d', 'a', 'g'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CutAll])]))]
+    #[case(&['y', 'i', 'g'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyAll])]))]
+    #[case(&[')'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::MoveSentenceRight { select: false }])]))]
+    #[case(&['2', '{'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveParagraphLeft { select: false }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveParagraphLeft { select: false }])]))]
+    #[case(&['d', '}'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveParagraphRight { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelection])]))]
+    #[case(&['y', '('], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveSentenceLeft { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::YankSelection])]))]
+    #[case(&['m', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::SetMark('a')])]))]
+    #[case(&['`', 'a'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMark { mark: 'a', select: false }
+        ])]))]
+    #[case(&['`', '`'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMark { mark: '`', select: false }
+        ])]))]
+    #[case(&['\'', '\''], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![
+        EditCommand::MoveToMarkLine { mark: '\'', select: false }
+        ])]))]
+    #[case(&['d', '\'', 'a'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveToMarkLine { mark: 'a', select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::CutSelectedLines])
+        ]))]
+    #[case(&['"', 'a', 'y', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('a')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['"', '3', 'p'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('3')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferAfter])]))]
+    #[case(&['"', '+', 'y', 'w'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('+')]),
+        ReedlineEvent::Edit(vec![EditCommand::CopyWordRightToNext])]))]
+    #[case(&['"', '*', 'P'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::SelectRegister('*')]),
+        ReedlineEvent::Edit(vec![EditCommand::PasteCutBufferBefore])]))]
+    #[case(&['y', 'y'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::CopyCurrentLine])]))]
+    #[case(&['y', 'j'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveCursorLineDown { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::YankSelectedLines])]))]
+    #[case(&['2', 'y', 'k'], ReedlineEvent::Multiple(vec![
+        ReedlineEvent::Edit(vec![EditCommand::MoveCursorLineUp { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::MoveCursorLineUp { select: true }]),
+        ReedlineEvent::Edit(vec![EditCommand::YankSelectedLines])]))]
+    #[case(&['y', 'w'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyWordRightToNext])]))]
+    #[case(&['y', 'e'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyWordRight])]))]
+    #[case(&['y', 'b'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyWordLeft])]))]
+    #[case(&['y', '$'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyToLineEnd])]))]
+    #[case(&['y', 't', 'x'], ReedlineEvent::Multiple(vec![ReedlineEvent::Edit(vec![EditCommand::CopyRightBefore('x')])]))]
     fn test_reedline_move(#[case] input: &[char], #[case] expected: ReedlineEvent) {
         let mut vi = Vi::default();
         let res = vi_parse(input);
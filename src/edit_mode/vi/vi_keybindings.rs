@@ -8,7 +8,7 @@ use crate::{
         },
         Keybindings,
     },
-    EditCommand,
+    EditCommand, ReedlineEvent,
 };
 
 /// Default Vi normal keybindings
@@ -21,6 +21,9 @@ pub fn default_vi_normal_keybindings() -> Keybindings {
     add_common_control_bindings(&mut kb);
     add_common_navigation_bindings(&mut kb);
     add_common_selection_bindings(&mut kb);
+    // Leave Ctrl-R unbound so the vi mode can use it to redo, the history search moves to Alt-R
+    kb.remove_binding(KM::CONTROL, KC::Char('r'));
+    kb.add_binding(KM::ALT, KC::Char('r'), ReedlineEvent::SearchHistory);
     // Replicate vi's default behavior for Backspace and delete
     kb.add_binding(
         KM::NONE,
@@ -35,11 +38,17 @@ pub fn default_vi_normal_keybindings() -> Keybindings {
 /// Default Vi insert keybindings
 pub fn default_vi_insert_keybindings() -> Keybindings {
     let mut kb = Keybindings::new();
+    use KeyCode as KC;
+    use KeyModifiers as KM;
 
     add_common_control_bindings(&mut kb);
     add_common_navigation_bindings(&mut kb);
     add_common_edit_bindings(&mut kb);
     add_common_selection_bindings(&mut kb);
+    // Leave Ctrl-R unbound so the vi mode can use it to insert a register, the history search
+    // moves to Alt-R
+    kb.remove_binding(KM::CONTROL, KC::Char('r'));
+    kb.add_binding(KM::ALT, KC::Char('r'), ReedlineEvent::SearchHistory);
 
     kb
 }
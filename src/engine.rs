@@ -18,7 +18,9 @@ use {
 use {
     crate::{
         completion::{Completer, DefaultCompleter},
-        core_editor::Editor,
+        core_editor::{
+            CallbackClipboard, ClipboardError, Editor, FileBackedClipboard, PasteSanitizer,
+        },
         edit_mode::{EditMode, Emacs},
         enums::{EventStatus, ReedlineEvent},
         highlighter::SimpleMatchHighlighter,
@@ -52,6 +54,8 @@ use {
 // arrives. This doesn't allow for the possibility of more than 1 event
 // happening at the same time.
 const POLL_WAIT: u64 = 10;
+// How often to check for the results of history searches finishing in the background
+const LATE_RESULTS_WAIT: u64 = 10;
 // Since a paste event is multiple Event::Key events happening at the same time, we specify
 // how many events should be in the crossterm_events vector before it is considered
 // a paste. 10 events in 10 milliseconds is conservative enough (unlikely somebody
@@ -244,7 +248,8 @@ impl Reedline {
     /// This currently alters the behavior for multiline pastes as pasting of regular text will
     /// execute after every complete new line as determined by the [`Validator`]. With enabled
     /// bracketed paste all lines will appear in the buffer and can then be submitted with a
-    /// separate enter.
+    /// separate enter. The pasted text is inserted literally in every edit mode, see
+    /// [`ReedlineEvent::Paste`].
     ///
     /// At this point most terminals should support it or ignore the setting of the necessary
     /// flags. For full compatibility, keep it disabled.
@@ -425,6 +430,101 @@ impl Reedline {
         self
     }
 
+    /// A builder that keeps cut and copied text in a file so it survives restarts
+    ///
+    /// Replaces the default clipboard, including the system clipboard if enabled.
+    /// A missing or unreadable file starts with an empty clipboard.
+    /// # Example
+    /// ```rust,no_run
+    /// use reedline::Reedline;
+    ///
+    /// let mut line_editor = Reedline::create().with_persistent_clipboard("clipboard.txt".into());
+    /// ```
+    #[must_use]
+    pub fn with_persistent_clipboard(mut self, file: PathBuf) -> Self {
+        self.editor
+            .set_clipboard(Box::new(FileBackedClipboard::with_file(file)));
+        self
+    }
+
+    /// A builder that truncates cut and copied text larger than `max_size` bytes, on a
+    /// character boundary, before it is stored in the registers and the clipboard
+    ///
+    /// Applies to whichever clipboard is set, before or after this builder.
+    /// # Example
+    /// ```rust
+    /// use reedline::{EditCommand, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_clipboard_max_size(4);
+    /// line_editor.run_edit_commands(&[
+    ///     EditCommand::InsertString("aé日本".into()),
+    ///     EditCommand::CutFromStart,
+    ///     EditCommand::PasteCutBufferBefore,
+    /// ]);
+    /// assert_eq!(line_editor.current_buffer_contents(), "aé");
+    /// ```
+    #[must_use]
+    pub fn with_clipboard_max_size(mut self, max_size: usize) -> Self {
+        self.editor.set_clipboard_max_size(Some(max_size));
+        self
+    }
+
+    /// A builder that routes cut and copied text through the closures of a [`CallbackClipboard`]
+    ///
+    /// Replaces the default clipboard, including the system clipboard if enabled.
+    /// # Example
+    /// ```rust
+    /// use reedline::{CallbackClipboard, ClipboardMode, Reedline};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let yanks = Arc::new(Mutex::new(Vec::<String>::new()));
+    /// let clipboard = CallbackClipboard::new(
+    ///     {
+    ///         let yanks = Arc::clone(&yanks);
+    ///         move |content, _mode| yanks.lock().unwrap().push(content.to_owned())
+    ///     },
+    ///     {
+    ///         let yanks = Arc::clone(&yanks);
+    ///         move || {
+    ///             let last = yanks.lock().unwrap().last().cloned().unwrap_or_default();
+    ///             (last, ClipboardMode::Normal)
+    ///         }
+    ///     },
+    /// );
+    ///
+    /// let mut line_editor = Reedline::create().with_callback_clipboard(clipboard);
+    /// ```
+    #[must_use]
+    pub fn with_callback_clipboard(mut self, clipboard: CallbackClipboard) -> Self {
+        self.editor.set_clipboard(Box::new(clipboard));
+        self
+    }
+
+    /// A builder that sets how line breaks and control characters in text pasted from the
+    /// clipboard or through the terminal's bracketed paste are handled, see [`PasteSanitizer`]
+    ///
+    /// Defaults to [`PasteSanitizer::Keep`].
+    /// # Example
+    /// ```rust
+    /// use reedline::{PasteSanitizer, Reedline};
+    ///
+    /// let mut line_editor = Reedline::create().with_paste_sanitizer(PasteSanitizer::Replace(' '));
+    /// ```
+    #[must_use]
+    pub fn with_paste_sanitizer(mut self, paste_sanitizer: PasteSanitizer) -> Self {
+        self.editor.set_paste_sanitizer(paste_sanitizer);
+        self
+    }
+
+    /// A builder that sets how many cut or copied entries `YankPop` cycles through
+    ///
+    /// Defaults to 60 entries.
+    #[must_use]
+    pub fn with_kill_ring_size(mut self, size: usize) -> Self {
+        self.editor.set_kill_ring_size(size);
+        self
+    }
+
     /// A builder that configures the validator for your instance of the Reedline engine
     /// # Example
     /// ```rust
@@ -491,6 +591,7 @@ impl Reedline {
     /// A builder which configures the edit mode for your instance of the Reedline engine
     #[must_use]
     pub fn with_edit_mode(mut self, edit_mode: Box<dyn EditMode>) -> Self {
+        self.editor.set_word_characters(edit_mode.word_characters());
         self.edit_mode = edit_mode;
         self
     }
@@ -592,6 +693,22 @@ impl Reedline {
         self.history.sync()
     }
 
+    /// Delete all items of the underlying [`History`], see [`History::clear`]
+    ///
+    /// Also forgets the history entry browsed to and the last command run, so nothing of the
+    /// old history is shown or updated afterwards.
+    pub fn clear_history(&mut self) -> crate::Result<()> {
+        self.history.clear()?;
+        self.history_cursor = HistoryCursor::new(
+            HistoryNavigationQuery::Normal(LineBuffer::default()),
+            self.get_history_session_id(),
+        );
+        self.history_last_run_id = None;
+        self.history_excluded_item = None;
+        self.history_cursor_on_excluded = false;
+        Ok(())
+    }
+
     /// Check if any commands have been run.
     ///
     /// When no commands have been run, calling [`Self::update_last_command_context`]
@@ -617,6 +734,31 @@ impl Reedline {
         }
     }
 
+    /// record the duration and exit status of the last command run once it finished, see
+    /// [`History::update_outcome`]. Fails with a "No command run" error like
+    /// [`Self::update_last_command_context`]
+    pub fn update_last_command_outcome(
+        &mut self,
+        duration: Option<Duration>,
+        exit_status: Option<i64>,
+    ) -> crate::Result<()> {
+        match &self.history_last_run_id {
+            Some(Self::FILTERED_ITEM_ID) => {
+                let item = self.history_excluded_item.take().unwrap();
+                self.history_excluded_item = Some(HistoryItem {
+                    duration: duration.or(item.duration),
+                    exit_status: exit_status.or(item.exit_status),
+                    ..item
+                });
+                Ok(())
+            }
+            Some(r) => self.history.update_outcome(*r, duration, exit_status),
+            None => Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "No command run",
+            ))),
+        }
+    }
+
     /// Wait for input and provide the user with a specified [`Prompt`].
     ///
     /// Returns a [`std::io::Result`] in which the `Err` type is [`std::io::Result`]
@@ -644,6 +786,16 @@ impl Reedline {
         self.editor.get_buffer()
     }
 
+    /// Returns why the clipboard failed to store the latest cut or copy, if it did, and
+    /// forgets the error
+    ///
+    /// The text stays available to paste within the session, e.g. when the file of
+    /// [`Reedline::with_persistent_clipboard`] can't be written. Check it after
+    /// [`Reedline::read_line`] returns to tell the user.
+    pub fn take_clipboard_error(&mut self) -> Option<ClipboardError> {
+        self.editor.take_clipboard_error()
+    }
+
     /// Writes `msg` to the terminal with a following carriage return and newline
     fn print_line(&mut self, msg: &str) -> Result<()> {
         self.painter.paint_line(msg)
@@ -695,6 +847,23 @@ impl Reedline {
 
             let mut latest_resize = None;
             loop {
+                // A background history answers before its searches finish, the active menu
+                // shows their results once they come in
+                if self.history.awaits_late_results()
+                    && !event::poll(Duration::from_millis(LATE_RESULTS_WAIT))?
+                {
+                    if self.history.take_late_results() {
+                        if let Some(menu) = self.menus.iter_mut().find(|menu| menu.is_active()) {
+                            menu.update_values(
+                                &mut self.editor,
+                                self.completer.as_mut(),
+                                self.history.as_ref(),
+                            );
+                        }
+                        self.repaint(prompt)?;
+                    }
+                    continue;
+                }
                 match event::read()? {
                     Event::Resize(x, y) => {
                         latest_resize = Some((x, y));
@@ -763,7 +932,14 @@ impl Reedline {
             }
 
             for event in reedline_events.drain(..) {
-                match self.handle_event(prompt, event)? {
+                let mut status = self.handle_event(prompt, event)?;
+                if self.editor.take_missed_text_object() {
+                    let event = self.edit_mode.handle_missed_text_object();
+                    if let EventStatus::Handled = self.handle_event(prompt, event)? {
+                        status = EventStatus::Handled;
+                    }
+                }
+                match status {
                     EventStatus::Exits(signal) => {
                         // Move the cursor below the input area, for external commands or new read_line call
                         self.painter.move_cursor_to_end()?;
@@ -848,12 +1024,16 @@ impl Reedline {
                 self.run_history_commands(&commands);
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::Paste(text) => {
+                self.run_history_commands(&[self.editor.insert_pasted(&text)]);
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Mouse => Ok(EventStatus::Handled),
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Inapplicable)
             }
-            ReedlineEvent::Repaint => {
+            ReedlineEvent::Repaint | ReedlineEvent::OperatorPending(_) => {
                 // A handled Event causes a repaint
                 Ok(EventStatus::Handled)
             }
@@ -884,6 +1064,7 @@ impl Reedline {
             | ReedlineEvent::Left
             | ReedlineEvent::Multiple(_)
             | ReedlineEvent::None
+            | ReedlineEvent::RepeatHistorySearch { .. }
             | ReedlineEvent::HistoryHintWordComplete
             | ReedlineEvent::OpenEditor
             | ReedlineEvent::Menu(_)
@@ -1057,15 +1238,21 @@ impl Reedline {
             ReedlineEvent::Enter | ReedlineEvent::Submit | ReedlineEvent::SubmitOrNewline
                 if self.menus.iter().any(|menu| menu.is_active()) =>
             {
+                let mut accept = false;
                 for menu in self.menus.iter_mut() {
                     if menu.is_active() {
+                        accept = matches!(menu.selected_value(), Some(value) if value.accept);
                         menu.replace_in_buffer(&mut self.editor);
                         menu.menu_event(MenuEvent::Deactivate);
-
-                        return Ok(EventStatus::Handled);
+                        break;
                     }
                 }
-                unreachable!()
+                if accept {
+                    // With the menu closed the line goes through the regular validation
+                    self.handle_editor_event(prompt, ReedlineEvent::Enter)
+                } else {
+                    Ok(EventStatus::Handled)
+                }
             }
             ReedlineEvent::Enter => {
                 #[cfg(feature = "bashisms")]
@@ -1157,12 +1344,21 @@ impl Reedline {
                 }
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::Paste(text) => {
+                self.run_edit_commands(&[self.editor.insert_pasted(&text)]);
+                // Pasted text isn't typed input to complete, so an open menu is closed
+                // instead of searching again
+                if let Some(menu) = self.menus.iter_mut().find(|men| men.is_active()) {
+                    menu.menu_event(MenuEvent::Deactivate);
+                }
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::OpenEditor => self.open_editor().map(|_| EventStatus::Handled),
             ReedlineEvent::Resize(width, height) => {
                 self.painter.handle_resize(width, height);
                 Ok(EventStatus::Inapplicable)
             }
-            ReedlineEvent::Repaint => {
+            ReedlineEvent::Repaint | ReedlineEvent::OperatorPending(_) => {
                 // A handled Event causes a repaint
                 Ok(EventStatus::Handled)
             }
@@ -1194,6 +1390,10 @@ impl Reedline {
                 self.enter_history_search();
                 Ok(EventStatus::Handled)
             }
+            ReedlineEvent::RepeatHistorySearch { term, backward } => {
+                self.repeat_history_search(term, backward);
+                Ok(EventStatus::Handled)
+            }
             ReedlineEvent::Multiple(events) => {
                 let mut latest_signal = EventStatus::Inapplicable;
                 for event in events {
@@ -1339,6 +1539,37 @@ impl Reedline {
         self.input_mode = InputMode::HistorySearch;
     }
 
+    /// Move to the next history entry containing `term`, continuing from the last match if
+    /// the previous search was for the same term
+    fn repeat_history_search(&mut self, term: String, backward: bool) {
+        let navigation = HistoryNavigationQuery::SubstringSearch(term);
+        if self.history_cursor.get_navigation() != navigation {
+            self.history_cursor = HistoryCursor::new(navigation, self.get_history_session_id());
+        }
+        let searched = if backward {
+            self.history_cursor.back(self.history.as_ref())
+        } else {
+            self.history_cursor
+                .forward(self.history.as_ref())
+                .and_then(|()| {
+                    // Stay on the newest match instead of falling off the end of the history
+                    if self.history_cursor.string_at_cursor().is_none() {
+                        self.history_cursor.back(self.history.as_ref())
+                    } else {
+                        Ok(())
+                    }
+                })
+        };
+        // A failing history backend counts as no match and leaves the buffer alone
+        if searched.is_err() {
+            return;
+        }
+        if let Some(string) = self.history_cursor.string_at_cursor() {
+            self.editor
+                .set_buffer(string, UndoBehavior::CreateUndoPoint);
+        }
+    }
+
     /// Dispatches the applicable [`EditCommand`] actions for editing the history search string.
     ///
     /// Only modifies internal state, does not perform regular output!
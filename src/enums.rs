@@ -14,6 +14,51 @@ pub enum Signal {
     CtrlD, // End terminal session
 }
 
+/// Kinds of vi-style text objects, see [`TextObject`]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub enum TextObjectKind {
+    /// A word (`w`)
+    #[default]
+    Word,
+    /// A WORD, delimited by whitespace only (`W`)
+    BigWord,
+    /// A `(...)` block (`(`, `)` or `b`)
+    Parentheses,
+    /// A `[...]` block (`[` or `]`)
+    Brackets,
+    /// A `{...}` block (`{`, `}` or `B`)
+    Braces,
+    /// A `"..."` string (`"`)
+    DoubleQuote,
+    /// A `'...'` string (`'`)
+    SingleQuote,
+    /// A `` `...` `` string (`` ` ``)
+    Backtick,
+    /// A paragraph, the lines up to the surrounding blank lines (`p`)
+    Paragraph,
+}
+
+/// A vi-style text object like `iw` or `a(`, describing a span around the cursor
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub struct TextObject {
+    /// Include the surrounding delimiters or whitespace (`a`) instead of only the inner part (`i`)
+    pub around: bool,
+    /// The kind of object to operate on
+    pub kind: TextObjectKind,
+}
+
+/// Characters moving the word boundaries of the word motions, like vim's `iskeyword`
+///
+/// By default words follow the Unicode word boundaries, where `_` joins words and `-` or `/`
+/// separate them. WORDs are delimited by whitespace only and unaffected.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub struct WordCharacters {
+    /// Characters joining the words around them, e.g. `-` to treat `foo-bar` as one word
+    pub word: String,
+    /// Characters separating words, e.g. `_` to treat `foo_bar` as three words
+    pub separators: String,
+}
+
 /// Editing actions which can be mapped to key bindings.
 ///
 /// Executed by `Reedline::run_edit_commands()`
@@ -97,6 +142,18 @@ pub enum EditCommand {
         select: bool,
     },
 
+    /// Move to the end of the previous word (vi `ge`)
+    MoveWordLeftEnd {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the end of the previous WORD (vi `gE`)
+    MoveBigWordLeftEnd {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
     /// Move to position
     MoveToPosition {
         /// Position to move to
@@ -105,6 +162,85 @@ pub enum EditCommand {
         select: bool,
     },
 
+    /// Move to the position stored in the mark
+    ///
+    /// The mark `'` (or `` ` ``) holds the position before the last jump to the start, end or
+    /// a line of the buffer, a matching bracket or a mark
+    MoveToMark {
+        /// Name of the mark
+        mark: char,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move the cursor one line up
+    MoveCursorLineUp {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move the cursor one line down
+    MoveCursorLineDown {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the start of the next sentence
+    MoveSentenceRight {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the start of the previous sentence
+    MoveSentenceLeft {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the empty line after the paragraph
+    MoveParagraphRight {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the empty line before the paragraph
+    MoveParagraphLeft {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the start of the given line, counting from 1 and clamped to the last line
+    MoveToLine {
+        /// Number of the line
+        line: usize,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the bracket matching the next `()`, `[]` or `{}` on the line
+    MoveToMatchingBracket {
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Move to the start of the line containing the position stored in the mark
+    MoveToMarkLine {
+        /// Name of the mark
+        mark: char,
+        /// Select the text between the current cursor position and destination
+        select: bool,
+    },
+
+    /// Store the current insertion point in the named mark
+    SetMark(char),
+
+    /// Use the named register for the following cut, copy or paste (vi `"a`)
+    SelectRegister(char),
+
+    /// Insert the content of the named register at the cursor, `"` being the unnamed
+    /// register (vi insert mode `Ctrl-R`)
+    InsertRegister(char),
+
     /// Insert a character at the current insertion point
     InsertChar(char),
 
@@ -117,12 +253,46 @@ pub enum EditCommand {
     /// - On Windows CRLF (`"\r\n"`)
     InsertNewline,
 
+    /// Open a new line below the current one, or `above` it, with the same indentation and
+    /// move onto it (vi `o` and `O`)
+    OpenLine {
+        /// Open the line above the current one
+        above: bool,
+    },
+
+    /// Insert the given number of copies of the current line below it, at least one, and move
+    /// onto the first copy at the same column
+    DuplicateLine(usize),
+
+    /// Join the next line onto the current one, replacing the line break and indentation
+    /// with a single space if `smart` (vi `J` and `gJ`)
+    JoinLines {
+        /// Collapse the whitespace at the join into a single space
+        smart: bool,
+    },
+
     /// Replace a character
     ReplaceChar(char),
 
     /// Replace characters with string
     ReplaceChars(usize, String),
 
+    /// Replace the `count` characters right of the insertion point with `c`, doing nothing if
+    /// the line ends before (vi `3ra`)
+    ReplaceCharsInLine {
+        /// The replacement
+        c: char,
+        /// How many characters to replace
+        count: usize,
+    },
+
+    /// Overwrite the character under the cursor and move behind it, appending at the
+    /// end of a line (vi replace mode)
+    OverwriteChar(char),
+
+    /// Move left and restore the character replaced by the last [`EditCommand::OverwriteChar`]
+    RestoreOverwrittenChar,
+
     /// Backspace delete from the current insertion point
     Backspace,
 
@@ -147,13 +317,24 @@ pub enum EditCommand {
     /// Insert completion: entire completion if there is only one possibility, or else up to shared prefix.
     Complete,
 
-    /// Cut the current line
+    /// Cut the current line with its line break and move to the first non-blank of the line
+    /// taking its place
     CutCurrentLine,
 
-    /// Cut from the start of the buffer to the insertion point
+    /// Cut the text of the current line, keeping its indentation if `keep_indent`, and move
+    /// behind what remains (vi `S` and `cc`)
+    ChangeLine {
+        /// Leave the leading whitespace of the line in place
+        keep_indent: bool,
+    },
+
+    /// Cut from the insertion point back to the start of the buffer, across lines (Emacs
+    /// `Ctrl-U`), leaving the cursor at the start
     CutFromStart,
 
-    /// Cut from the start of the current line to the insertion point
+    /// Cut from the insertion point back to the start of the current line, leaving the cursor
+    /// at column zero, like readline's `unix-line-discard` (vi `d0`). The line break ending
+    /// the previous line is kept, so nothing is cut at the start of a line
     CutFromLineStart,
 
     /// Cut from the insertion point to the end of the buffer
@@ -186,6 +367,17 @@ pub enum EditCommand {
     /// Paste the cut buffer in front of the insertion point (vi `p`)
     PasteCutBufferAfter,
 
+    /// Paste the cut buffer the given number of times in front of the insertion point,
+    /// leaving the cursor where vim does (vi `3P`)
+    PasteCutBufferBeforeTimes(usize),
+
+    /// Paste the cut buffer the given number of times after the insertion point, leaving the
+    /// cursor where vim does (vi `3p`)
+    PasteCutBufferAfterTimes(usize),
+
+    /// Replace the text just pasted with the previous entry of the kill ring (Emacs `M-y`)
+    YankPop,
+
     /// Upper case the current word
     UppercaseWord,
 
@@ -195,21 +387,74 @@ pub enum EditCommand {
     /// Capitalize the current character
     CapitalizeChar,
 
+    /// Upper case the current line, keeping the cursor on the same column (vi `gUU`)
+    UppercaseLine,
+
+    /// Lower case the current line, keeping the cursor on the same column (vi `guu`)
+    LowercaseLine,
+
+    /// Capitalize every word of the current line and lower case the rest, keeping the cursor
+    /// on the same column
+    CapitalizeLine,
+
     /// Switch the case of the current character
     SwitchcaseChar,
 
+    /// Indent the lines touched by the selection, or the current line, by the given text
+    IndentLines(String),
+
+    /// Remove up to one level of indentation, given by its text, from the lines touched by
+    /// the selection, or the current line
+    DedentLines(String),
+
+    /// Upper case the selection and move to its start
+    UppercaseSelection,
+
+    /// Lower case the selection and move to its start
+    LowercaseSelection,
+
+    /// Switch the case of the selection and move to its start
+    SwitchcaseSelection,
+
     /// Swap the current word with the word to the right
     SwapWords,
 
     /// Swap the current grapheme/character with the one to the right
     SwapGraphemes,
 
+    /// Move the current line above the previous one, keeping the cursor on the moved line
+    ///
+    /// Not to be confused with [`EditCommand::MoveCursorLineUp`], which only moves the cursor.
+    MoveLineUp,
+
+    /// Move the current line below the next one, keeping the cursor on the moved line
+    ///
+    /// Not to be confused with [`EditCommand::MoveCursorLineDown`], which only moves the cursor.
+    MoveLineDown,
+
+    /// Swap the character left of the insertion point with the one right of it and move
+    /// behind both, or swap the last two characters at the end of a line (Emacs
+    /// `transpose-chars`, vi `xp`)
+    TransposeChars,
+
+    /// Swap the word left of or around the insertion point with the word right of it and
+    /// move behind both, or swap the last two words at the end of a line (Emacs
+    /// `transpose-words`)
+    TransposeWords,
+
     /// Undo the previous edit command
     Undo,
 
     /// Redo an edit command from the undo history
     Redo,
 
+    /// Open an undo group: the edits up to [`EditCommand::EndUndoGroup`] are undone and
+    /// redone in one step. Groups don't nest, opening one while a group is open does nothing
+    BeginUndoGroup,
+
+    /// Close the undo group opened by [`EditCommand::BeginUndoGroup`]
+    EndUndoGroup,
+
     /// CutUntil right until char
     CutRightUntil(char),
 
@@ -257,11 +502,133 @@ pub enum EditCommand {
     /// Select whole input buffer
     SelectAll,
 
+    /// Anchor a selection at the insertion point, unless one is anchored already, that also
+    /// covers the grapheme under the cursor, like vi's visual mode, until it is cleared
+    StartInclusiveSelection,
+
     /// Cut selection
     CutSelection,
 
     /// Copy selection
     CopySelection,
+
+    /// Cut every line touched by the selection
+    CutSelectedLines,
+
+    /// Copy every line touched by the selection
+    CopySelectedLines,
+
+    /// Copy the selection and return to where it was anchored, like a vi yank
+    YankSelection,
+
+    /// Copy every line touched by the selection and return to where it was anchored, like a
+    /// vi yank
+    YankSelectedLines,
+
+    /// Replace the selection with the content of the cut buffer, like vim's `p` in visual
+    /// mode. The replaced text is cut to the unnamed register after the content is taken,
+    /// so pasting swaps it with the selection
+    PasteCutBufferOverSelection,
+
+    /// Replace every line touched by the selection with the content of the cut buffer, like
+    /// vim's `p` in visual line mode, cutting them to the unnamed register
+    PasteCutBufferOverSelectedLines,
+
+    /// Copy the rectangle with the selection anchor and the insertion point in opposite
+    /// corners, to be pasted as a block
+    CopySelectedBlock,
+
+    /// Drop the current selection without changing the buffer
+    ClearSelection,
+
+    /// Copy from the start of the buffer to the insertion point
+    CopyFromStart,
+
+    /// Copy from the start of the current line to the insertion point
+    CopyFromLineStart,
+
+    /// Copy from the insertion point to the end of the buffer
+    CopyToEnd,
+
+    /// Copy from the insertion point to the end of the current line
+    CopyToLineEnd,
+
+    /// Copy the current line
+    CopyCurrentLine,
+
+    /// Copy the current line and the lines following it, as many as given in total
+    CopyLines(usize),
+
+    /// Copy the word left of the insertion point
+    CopyWordLeft,
+
+    /// Copy the WORD left of the insertion point
+    CopyBigWordLeft,
+
+    /// Copy the word right of the insertion point
+    CopyWordRight,
+
+    /// Copy the WORD right of the insertion point
+    CopyBigWordRight,
+
+    /// Copy the word right of the insertion point and any following space
+    CopyWordRightToNext,
+
+    /// Copy the WORD right of the insertion point and any following space
+    CopyBigWordRightToNext,
+
+    /// Copy the grapheme left of the insertion point
+    CopyLeft,
+
+    /// Copy the grapheme right of the insertion point
+    CopyRight,
+
+    /// Cut the text object around the insertion point
+    CutTextObject {
+        /// The text object to cut
+        text_object: TextObject,
+    },
+
+    /// Select the text object around the insertion point
+    SelectTextObject {
+        /// The text object to select
+        text_object: TextObject,
+    },
+
+    /// Copy the text object around the insertion point
+    CopyTextObject {
+        /// The text object to copy
+        text_object: TextObject,
+    },
+
+    /// Cut up to and including the bracket matching the next bracket on the line
+    CutToMatchingBracket,
+
+    /// Copy up to and including the bracket matching the next bracket on the line
+    CopyToMatchingBracket,
+
+    /// Cut the current line together with the following lines indented deeper than it, up to
+    /// the next line at the same or a lesser indentation, a tab counting as one level of the
+    /// given indentation text
+    CutIndentBlock(String),
+
+    /// Cut the whole buffer as lines, leaving it empty
+    CutAll,
+
+    /// Copy the whole buffer as lines and move to its start
+    CopyAll,
+
+    /// Copy right until char
+    CopyRightUntil(char),
+
+    /// Copy right before char
+    CopyRightBefore(char),
+
+    /// Copy left until char
+    CopyLeftUntil(char),
+
+    /// Copy left before char
+    CopyLeftBefore(char),
 }
 
 impl Display for EditCommand {
@@ -290,6 +657,12 @@ impl Display for EditCommand {
             EditCommand::MoveBigWordRightEnd { .. } => {
                 write!(f, "MoveBigWordRightEnd Optional[select: <bool>]")
             }
+            EditCommand::MoveWordLeftEnd { .. } => {
+                write!(f, "MoveWordLeftEnd Optional[select: <bool>]")
+            }
+            EditCommand::MoveBigWordLeftEnd { .. } => {
+                write!(f, "MoveBigWordLeftEnd Optional[select: <bool>]")
+            }
             EditCommand::MoveWordRightStart { .. } => {
                 write!(f, "MoveWordRightStart Optional[select: <bool>]")
             }
@@ -305,11 +678,52 @@ impl Display for EditCommand {
             EditCommand::MoveLeftBefore { .. } => {
                 write!(f, "MoveLeftBefore Value: <char>, Optional[select: <bool>]")
             }
+            EditCommand::MoveCursorLineUp { .. } => {
+                write!(f, "MoveCursorLineUp Optional[select: <bool>]")
+            }
+            EditCommand::MoveCursorLineDown { .. } => {
+                write!(f, "MoveCursorLineDown Optional[select: <bool>]")
+            }
+            EditCommand::MoveSentenceRight { .. } => {
+                write!(f, "MoveSentenceRight Optional[select: <bool>]")
+            }
+            EditCommand::MoveSentenceLeft { .. } => {
+                write!(f, "MoveSentenceLeft Optional[select: <bool>]")
+            }
+            EditCommand::MoveParagraphRight { .. } => {
+                write!(f, "MoveParagraphRight Optional[select: <bool>]")
+            }
+            EditCommand::MoveParagraphLeft { .. } => {
+                write!(f, "MoveParagraphLeft Optional[select: <bool>]")
+            }
+            EditCommand::MoveToLine { .. } => {
+                write!(f, "MoveToLine Value: <int>, Optional[select: <bool>]")
+            }
+            EditCommand::MoveToMatchingBracket { .. } => {
+                write!(f, "MoveToMatchingBracket Optional[select: <bool>]")
+            }
+            EditCommand::MoveToMark { .. } => {
+                write!(f, "MoveToMark Value: <char>, Optional[select: <bool>]")
+            }
+            EditCommand::MoveToMarkLine { .. } => {
+                write!(f, "MoveToMarkLine Value: <char>, Optional[select: <bool>]")
+            }
+            EditCommand::SetMark(_) => write!(f, "SetMark Value: <char>"),
+            EditCommand::SelectRegister(_) => write!(f, "SelectRegister Value: <char>"),
+            EditCommand::InsertRegister(_) => write!(f, "InsertRegister Value: <char>"),
             EditCommand::InsertChar(_) => write!(f, "InsertChar  Value: <char>"),
             EditCommand::InsertString(_) => write!(f, "InsertString Value: <string>"),
             EditCommand::InsertNewline => write!(f, "InsertNewline"),
+            EditCommand::OpenLine { .. } => write!(f, "OpenLine Optional[above: <bool>]"),
+            EditCommand::DuplicateLine(_) => write!(f, "DuplicateLine Value: <int>"),
+            EditCommand::JoinLines { .. } => write!(f, "JoinLines Optional[smart: <bool>]"),
             EditCommand::ReplaceChar(_) => write!(f, "ReplaceChar <char>"),
             EditCommand::ReplaceChars(_, _) => write!(f, "ReplaceChars <int> <string>"),
+            EditCommand::ReplaceCharsInLine { .. } => {
+                write!(f, "ReplaceCharsInLine Value: <char> <int>")
+            }
+            EditCommand::OverwriteChar(_) => write!(f, "OverwriteChar <char>"),
+            EditCommand::RestoreOverwrittenChar => write!(f, "RestoreOverwrittenChar"),
             EditCommand::Backspace => write!(f, "Backspace"),
             EditCommand::Delete => write!(f, "Delete"),
             EditCommand::CutChar => write!(f, "CutChar"),
@@ -319,6 +733,9 @@ impl Display for EditCommand {
             EditCommand::ClearToLineEnd => write!(f, "ClearToLineEnd"),
             EditCommand::Complete => write!(f, "Complete"),
             EditCommand::CutCurrentLine => write!(f, "CutCurrentLine"),
+            EditCommand::ChangeLine { .. } => {
+                write!(f, "ChangeLine Optional[keep_indent: <bool>]")
+            }
             EditCommand::CutFromStart => write!(f, "CutFromStart"),
             EditCommand::CutFromLineStart => write!(f, "CutFromLineStart"),
             EditCommand::CutToEnd => write!(f, "CutToEnd"),
@@ -331,14 +748,35 @@ impl Display for EditCommand {
             EditCommand::CutBigWordRightToNext => write!(f, "CutBigWordRightToNext"),
             EditCommand::PasteCutBufferBefore => write!(f, "PasteCutBufferBefore"),
             EditCommand::PasteCutBufferAfter => write!(f, "PasteCutBufferAfter"),
+            EditCommand::PasteCutBufferBeforeTimes(_) => {
+                write!(f, "PasteCutBufferBeforeTimes Value: <int>")
+            }
+            EditCommand::PasteCutBufferAfterTimes(_) => {
+                write!(f, "PasteCutBufferAfterTimes Value: <int>")
+            }
+            EditCommand::YankPop => write!(f, "YankPop"),
             EditCommand::UppercaseWord => write!(f, "UppercaseWord"),
             EditCommand::LowercaseWord => write!(f, "LowercaseWord"),
             EditCommand::SwitchcaseChar => write!(f, "SwitchcaseChar"),
+            EditCommand::IndentLines(_) => write!(f, "IndentLines Value: <string>"),
+            EditCommand::DedentLines(_) => write!(f, "DedentLines Value: <string>"),
+            EditCommand::UppercaseSelection => write!(f, "UppercaseSelection"),
+            EditCommand::LowercaseSelection => write!(f, "LowercaseSelection"),
+            EditCommand::SwitchcaseSelection => write!(f, "SwitchcaseSelection"),
             EditCommand::CapitalizeChar => write!(f, "CapitalizeChar"),
+            EditCommand::UppercaseLine => write!(f, "UppercaseLine"),
+            EditCommand::LowercaseLine => write!(f, "LowercaseLine"),
+            EditCommand::CapitalizeLine => write!(f, "CapitalizeLine"),
             EditCommand::SwapWords => write!(f, "SwapWords"),
             EditCommand::SwapGraphemes => write!(f, "SwapGraphemes"),
+            EditCommand::MoveLineUp => write!(f, "MoveLineUp"),
+            EditCommand::MoveLineDown => write!(f, "MoveLineDown"),
+            EditCommand::TransposeChars => write!(f, "TransposeChars"),
+            EditCommand::TransposeWords => write!(f, "TransposeWords"),
             EditCommand::Undo => write!(f, "Undo"),
             EditCommand::Redo => write!(f, "Redo"),
+            EditCommand::BeginUndoGroup => write!(f, "BeginUndoGroup"),
+            EditCommand::EndUndoGroup => write!(f, "EndUndoGroup"),
             EditCommand::CutRightUntil(_) => write!(f, "CutRightUntil Value: <char>"),
             EditCommand::CutRightBefore(_) => write!(f, "CutRightBefore Value: <char>"),
             EditCommand::MoveRightUntil { .. } => write!(f, "MoveRightUntil Value: <char>"),
@@ -346,8 +784,49 @@ impl Display for EditCommand {
             EditCommand::CutLeftUntil(_) => write!(f, "CutLeftUntil Value: <char>"),
             EditCommand::CutLeftBefore(_) => write!(f, "CutLeftBefore Value: <char>"),
             EditCommand::SelectAll => write!(f, "SelectAll"),
+            EditCommand::StartInclusiveSelection => write!(f, "StartInclusiveSelection"),
             EditCommand::CutSelection => write!(f, "CutSelection"),
             EditCommand::CopySelection => write!(f, "CopySelection"),
+            EditCommand::YankSelection => write!(f, "YankSelection"),
+            EditCommand::CutSelectedLines => write!(f, "CutSelectedLines"),
+            EditCommand::PasteCutBufferOverSelection => write!(f, "PasteCutBufferOverSelection"),
+            EditCommand::PasteCutBufferOverSelectedLines => {
+                write!(f, "PasteCutBufferOverSelectedLines")
+            }
+            EditCommand::CopySelectedLines => write!(f, "CopySelectedLines"),
+            EditCommand::YankSelectedLines => write!(f, "YankSelectedLines"),
+            EditCommand::CopySelectedBlock => write!(f, "CopySelectedBlock"),
+            EditCommand::ClearSelection => write!(f, "ClearSelection"),
+            EditCommand::CopyFromStart => write!(f, "CopyFromStart"),
+            EditCommand::CopyFromLineStart => write!(f, "CopyFromLineStart"),
+            EditCommand::CopyToEnd => write!(f, "CopyToEnd"),
+            EditCommand::CopyToLineEnd => write!(f, "CopyToLineEnd"),
+            EditCommand::CopyCurrentLine => write!(f, "CopyCurrentLine"),
+            EditCommand::CopyLines(_) => write!(f, "CopyLines Value: <int>"),
+            EditCommand::CopyWordLeft => write!(f, "CopyWordLeft"),
+            EditCommand::CopyBigWordLeft => write!(f, "CopyBigWordLeft"),
+            EditCommand::CopyWordRight => write!(f, "CopyWordRight"),
+            EditCommand::CopyBigWordRight => write!(f, "CopyBigWordRight"),
+            EditCommand::CopyWordRightToNext => write!(f, "CopyWordRightToNext"),
+            EditCommand::CopyBigWordRightToNext => write!(f, "CopyBigWordRightToNext"),
+            EditCommand::CopyLeft => write!(f, "CopyLeft"),
+            EditCommand::CopyRight => write!(f, "CopyRight"),
+            EditCommand::CutTextObject { .. } => write!(f, "CutTextObject Value: <TextObject>"),
+            EditCommand::SelectTextObject { .. } => {
+                write!(f, "SelectTextObject Value: <TextObject>")
+            }
+            EditCommand::CopyTextObject { .. } => {
+                write!(f, "CopyTextObject Value: <TextObject>")
+            }
+            EditCommand::CutToMatchingBracket => write!(f, "CutToMatchingBracket"),
+            EditCommand::CutIndentBlock(_) => write!(f, "CutIndentBlock Value: <string>"),
+            EditCommand::CutAll => write!(f, "CutAll"),
+            EditCommand::CopyAll => write!(f, "CopyAll"),
+            EditCommand::CopyToMatchingBracket => write!(f, "CopyToMatchingBracket"),
+            EditCommand::CopyRightUntil(_) => write!(f, "CopyRightUntil Value: <char>"),
+            EditCommand::CopyRightBefore(_) => write!(f, "CopyRightBefore Value: <char>"),
+            EditCommand::CopyLeftUntil(_) => write!(f, "CopyLeftUntil Value: <char>"),
+            EditCommand::CopyLeftBefore(_) => write!(f, "CopyLeftBefore Value: <char>"),
         }
     }
 }
@@ -363,6 +842,16 @@ impl EditCommand {
             | EditCommand::MoveToLineStart { select, .. }
             | EditCommand::MoveToLineEnd { select, .. }
             | EditCommand::MoveToPosition { select, .. }
+            | EditCommand::MoveCursorLineUp { select, .. }
+            | EditCommand::MoveCursorLineDown { select, .. }
+            | EditCommand::MoveSentenceRight { select, .. }
+            | EditCommand::MoveSentenceLeft { select, .. }
+            | EditCommand::MoveParagraphRight { select, .. }
+            | EditCommand::MoveParagraphLeft { select, .. }
+            | EditCommand::MoveToLine { select, .. }
+            | EditCommand::MoveToMatchingBracket { select, .. }
+            | EditCommand::MoveToMark { select, .. }
+            | EditCommand::MoveToMarkLine { select, .. }
             | EditCommand::MoveLeft { select, .. }
             | EditCommand::MoveRight { select, .. }
             | EditCommand::MoveWordLeft { select, .. }
@@ -372,6 +861,8 @@ impl EditCommand {
             | EditCommand::MoveBigWordRightStart { select, .. }
             | EditCommand::MoveWordRightEnd { select, .. }
             | EditCommand::MoveBigWordRightEnd { select, .. }
+            | EditCommand::MoveWordLeftEnd { select, .. }
+            | EditCommand::MoveBigWordLeftEnd { select, .. }
             | EditCommand::MoveRightUntil { select, .. }
             | EditCommand::MoveRightBefore { select, .. }
             | EditCommand::MoveLeftUntil { select, .. }
@@ -379,7 +870,9 @@ impl EditCommand {
                 EditType::MoveCursor { select: *select }
             }
 
-            EditCommand::SelectAll => EditType::MoveCursor { select: true },
+            EditCommand::SelectAll
+            | EditCommand::StartInclusiveSelection
+            | EditCommand::SelectTextObject { .. } => EditType::MoveCursor { select: true },
 
             // Text edits
             EditCommand::InsertChar(_)
@@ -388,14 +881,22 @@ impl EditCommand {
             | EditCommand::CutChar
             | EditCommand::InsertString(_)
             | EditCommand::InsertNewline
+            | EditCommand::OpenLine { .. }
+            | EditCommand::DuplicateLine(_)
+            | EditCommand::JoinLines { .. }
             | EditCommand::ReplaceChar(_)
             | EditCommand::ReplaceChars(_, _)
+            | EditCommand::ReplaceCharsInLine { .. }
+            | EditCommand::InsertRegister(_)
+            | EditCommand::OverwriteChar(_)
+            | EditCommand::RestoreOverwrittenChar
             | EditCommand::BackspaceWord
             | EditCommand::DeleteWord
             | EditCommand::Clear
             | EditCommand::ClearToLineEnd
             | EditCommand::Complete
             | EditCommand::CutCurrentLine
+            | EditCommand::ChangeLine { .. }
             | EditCommand::CutFromStart
             | EditCommand::CutFromLineStart
             | EditCommand::CutToLineEnd
@@ -408,21 +909,73 @@ impl EditCommand {
             | EditCommand::CutBigWordRightToNext
             | EditCommand::PasteCutBufferBefore
             | EditCommand::PasteCutBufferAfter
+            | EditCommand::PasteCutBufferBeforeTimes(_)
+            | EditCommand::PasteCutBufferAfterTimes(_)
+            | EditCommand::YankPop
             | EditCommand::UppercaseWord
             | EditCommand::LowercaseWord
             | EditCommand::SwitchcaseChar
+            | EditCommand::IndentLines(_)
+            | EditCommand::DedentLines(_)
+            | EditCommand::UppercaseSelection
+            | EditCommand::LowercaseSelection
+            | EditCommand::SwitchcaseSelection
             | EditCommand::CapitalizeChar
+            | EditCommand::UppercaseLine
+            | EditCommand::LowercaseLine
+            | EditCommand::CapitalizeLine
             | EditCommand::SwapWords
             | EditCommand::SwapGraphemes
+            | EditCommand::MoveLineUp
+            | EditCommand::MoveLineDown
+            | EditCommand::TransposeChars
+            | EditCommand::TransposeWords
             | EditCommand::CutRightUntil(_)
             | EditCommand::CutRightBefore(_)
             | EditCommand::CutLeftUntil(_)
             | EditCommand::CutLeftBefore(_)
-            | EditCommand::CutSelection => EditType::EditText,
+            | EditCommand::CutSelection
+            | EditCommand::CutSelectedLines
+            | EditCommand::PasteCutBufferOverSelection
+            | EditCommand::PasteCutBufferOverSelectedLines
+            | EditCommand::CutTextObject { .. }
+            | EditCommand::CutToMatchingBracket
+            | EditCommand::CutIndentBlock(_)
+            | EditCommand::CutAll => EditType::EditText,
 
             EditCommand::Undo | EditCommand::Redo => EditType::UndoRedo,
 
-            EditCommand::CopySelection => EditType::NoOp,
+            EditCommand::SetMark(_)
+            | EditCommand::BeginUndoGroup
+            | EditCommand::EndUndoGroup
+            | EditCommand::SelectRegister(_)
+            | EditCommand::CopySelection
+            | EditCommand::CopySelectedLines
+            | EditCommand::YankSelection
+            | EditCommand::YankSelectedLines
+            | EditCommand::CopySelectedBlock
+            | EditCommand::ClearSelection
+            | EditCommand::CopyFromStart
+            | EditCommand::CopyFromLineStart
+            | EditCommand::CopyToEnd
+            | EditCommand::CopyToLineEnd
+            | EditCommand::CopyCurrentLine
+            | EditCommand::CopyAll
+            | EditCommand::CopyLines(_)
+            | EditCommand::CopyWordLeft
+            | EditCommand::CopyBigWordLeft
+            | EditCommand::CopyWordRight
+            | EditCommand::CopyBigWordRight
+            | EditCommand::CopyWordRightToNext
+            | EditCommand::CopyBigWordRightToNext
+            | EditCommand::CopyLeft
+            | EditCommand::CopyRight
+            | EditCommand::CopyTextObject { .. }
+            | EditCommand::CopyToMatchingBracket
+            | EditCommand::CopyRightUntil(_)
+            | EditCommand::CopyRightBefore(_)
+            | EditCommand::CopyLeftUntil(_)
+            | EditCommand::CopyLeftBefore(_) => EditType::NoOp,
         }
     }
 }
@@ -553,9 +1106,20 @@ pub enum ReedlineEvent {
     /// Run these commands in the editor
     Edit(Vec<EditCommand>),
 
+    /// Insert text the terminal delivered as one bracketed paste
+    ///
+    /// The text goes into the buffer literally whatever the edit mode, with its line breaks
+    /// turned into buffer newlines. It doesn't trigger completions and is undone in one step.
+    Paste(String),
+
     /// Trigger full repaint
     Repaint,
 
+    /// A vi command waits for more keys, like an operator for its motion, holding the keys
+    /// typed so far like vim's `showcmd`. Repaints the prompt, whose indicator then gets
+    /// [`PromptViMode::OperatorPending`](crate::PromptViMode::OperatorPending)
+    OperatorPending(String),
+
     /// Navigate to the previous historic buffer
     PreviousHistory,
 
@@ -577,6 +1141,14 @@ pub enum ReedlineEvent {
     /// Search the history for a string
     SearchHistory,
 
+    /// Move to the next history entry containing a previously searched string
+    RepeatHistorySearch {
+        /// The string to search for
+        term: String,
+        /// Search towards older entries
+        backward: bool,
+    },
+
     /// In vi mode multiple reedline events can be chained while parsing the
     /// command or movement characters
     Multiple(Vec<ReedlineEvent>),
@@ -638,7 +1210,9 @@ impl Display for ReedlineEvent {
                 f,
                 "Edit: <EditCommand> or Edit: <EditCommand> value: <string>"
             ),
+            ReedlineEvent::Paste(_) => write!(f, "Paste <string>"),
             ReedlineEvent::Repaint => write!(f, "Repaint"),
+            ReedlineEvent::OperatorPending(_) => write!(f, "OperatorPending <string>"),
             ReedlineEvent::PreviousHistory => write!(f, "PreviousHistory"),
             ReedlineEvent::Up => write!(f, "Up"),
             ReedlineEvent::Down => write!(f, "Down"),
@@ -646,6 +1220,10 @@ impl Display for ReedlineEvent {
             ReedlineEvent::Left => write!(f, "Left"),
             ReedlineEvent::NextHistory => write!(f, "NextHistory"),
             ReedlineEvent::SearchHistory => write!(f, "SearchHistory"),
+            ReedlineEvent::RepeatHistorySearch { .. } => write!(
+                f,
+                "RepeatHistorySearch Value: <string>, Optional[backward: <bool>]"
+            ),
             ReedlineEvent::Multiple(_) => write!(f, "Multiple[ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::UntilFound(_) => write!(f, "UntilFound [ {{ ReedLineEvents, }} ]"),
             ReedlineEvent::Menu(_) => write!(f, "Menu Name: <string>"),
@@ -673,6 +1251,7 @@ pub(crate) enum EventStatus {
 ///
 /// Which will make sure that the given event doesn't contain [KeyEventKind::Release]
 /// and convert from [KeyEventKind::Repeat] to [KeyEventKind::Press]
+#[derive(Debug, Clone)]
 pub struct ReedlineRawEvent {
     inner: Event,
 }
@@ -0,0 +1,453 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    History, HistoryItemId, HistorySessionId, ImportSummary, SearchDirection, SearchQuery,
+};
+use crate::{HistoryItem, Result};
+
+/// A search or count queued for the worker thread
+type Job<H> = Box<dyn FnOnce(&H) + Send>;
+
+/// State of one kind of query, searches or counts
+type Shared<T> = Arc<Mutex<Results<T>>>;
+
+/// How many finished queries of a kind keep their results, enough for all the queries of
+/// one menu update
+const ANSWERED_QUERIES: usize = 8;
+
+struct Results<T> {
+    /// Number of the latest query, a queued query older than it is skipped
+    generation: u64,
+    /// The last finished queries with their results, dropped whenever the history changes
+    answered: VecDeque<(SearchQuery, T)>,
+    /// Whether the latest query was answered before it finished
+    overdue: bool,
+}
+
+impl<T> Default for Results<T> {
+    fn default() -> Self {
+        Self {
+            generation: 0,
+            answered: VecDeque::new(),
+            overdue: false,
+        }
+    }
+}
+
+impl<T: Clone> Results<T> {
+    /// The results of the `query` if it finished since the history last changed
+    fn answer(&self, query: &SearchQuery) -> Option<T> {
+        self.answered
+            .iter()
+            .find(|(answered, _)| answered == query)
+            .map(|(_, result)| result.clone())
+    }
+
+    fn store(&mut self, query: SearchQuery, result: T) {
+        self.answered.retain(|(answered, _)| *answered != query);
+        if self.answered.len() == ANSWERED_QUERIES {
+            self.answered.pop_front();
+        }
+        self.answered.push_back((query, result));
+    }
+}
+
+/// Wraps a [`History`] too slow to search on the editor thread, like one backed by a remote
+/// service, to run the searches and counts of the history menus on a background thread
+///
+/// A [`History::try_search`] or [`History::try_count`] not finished within the timeout
+/// answers `None` instead of stalling the keystroke handling, and the menu keeps showing its
+/// last results. The query keeps running and its results are kept for when it is asked
+/// again. Once it finishes, [`History::take_late_results`] tells the editor to update the
+/// active menu and repaint. A query superseded by a newer one of its kind before it got to
+/// run is skipped.
+///
+/// [`History::search`], [`History::count`] and the other methods run on the calling thread,
+/// after a running search has finished, and always answer their own query.
+///
+/// ```rust
+/// use reedline::{BackgroundHistory, FileBackedHistory, Reedline};
+/// use std::time::Duration;
+///
+/// let history = BackgroundHistory::new(FileBackedHistory::default())
+///     .with_timeout(Duration::from_millis(20));
+/// let mut line_editor = Reedline::create().with_history(Box::new(history));
+/// ```
+pub struct BackgroundHistory<H> {
+    history: Arc<Mutex<H>>,
+    session: Option<HistorySessionId>,
+    timeout: Duration,
+    jobs: mpsc::Sender<Job<H>>,
+    searches: Shared<Vec<HistoryItem>>,
+    counts: Shared<i64>,
+    late: Arc<AtomicBool>,
+}
+
+impl<H: History + 'static> BackgroundHistory<H> {
+    /// Run the searches of `history` in the background, waiting up to 50 ms for them
+    pub fn new(history: H) -> Self {
+        let session = history.session();
+        let history = Arc::new(Mutex::new(history));
+        let (jobs, queue) = mpsc::channel::<Job<H>>();
+        let worker_history = Arc::clone(&history);
+        // Ends once the sender is dropped with the `BackgroundHistory`
+        thread::spawn(move || {
+            for job in queue {
+                job(&lock(&worker_history));
+            }
+        });
+        Self {
+            history,
+            session,
+            timeout: Duration::from_millis(50),
+            jobs,
+            searches: Arc::default(),
+            counts: Arc::default(),
+            late: Arc::default(),
+        }
+    }
+
+    /// Wait up to `timeout` for a search before answering that it hasn't finished
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Lock the history for a change, so the cached results no longer answer their query
+    fn change(&self) -> MutexGuard<'_, H> {
+        let history = lock(&self.history);
+        lock(&self.searches).answered.clear();
+        lock(&self.counts).answered.clear();
+        history
+    }
+
+    /// Run `job` on the `query` on the worker thread and wait for it up to the timeout,
+    /// `None` if it didn't finish in time
+    fn run_in_background<T: Clone + Send + 'static>(
+        &self,
+        query: SearchQuery,
+        shared: &Shared<T>,
+        job: fn(&H, SearchQuery) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let generation = {
+            let mut results = lock(shared);
+            if let Some(result) = results.answer(&query) {
+                return Ok(Some(result));
+            }
+            results.generation += 1;
+            results.generation
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let worker_shared = Arc::clone(shared);
+        let late = Arc::clone(&self.late);
+        let job: Job<H> = Box::new(move |history: &H| {
+            if lock(&worker_shared).generation != generation {
+                return;
+            }
+            let result = job(history, query.clone());
+            // Stored before the worker releases the history, so a change can't be missed
+            let mut results = lock(&worker_shared);
+            if let Ok(result) = &result {
+                results.store(query, result.clone());
+            }
+            // A newer query is still coming, and a failed one isn't retried on every repaint
+            let latest = results.generation == generation;
+            if latest && std::mem::take(&mut results.overdue) && result.is_ok() {
+                late.store(true, Ordering::SeqCst);
+            }
+            // Sent while holding the results, so the caller can't give up on them unnoticed.
+            // The receiver is gone if the query took too long
+            let _ = sender.send(result);
+        });
+        if let Err(mpsc::SendError(job)) = self.jobs.send(job) {
+            // The worker is gone after a panic in a query
+            job(&lock(&self.history));
+        }
+
+        if let Ok(result) = receiver.recv_timeout(self.timeout) {
+            return result.map(Some);
+        }
+        let mut results = lock(shared);
+        if let Ok(result) = receiver.try_recv() {
+            return result.map(Some);
+        }
+        results.overdue = true;
+        Ok(None)
+    }
+}
+
+/// A panic in a search leaves the history usable
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl<H: History + 'static> History for BackgroundHistory<H> {
+    fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
+        self.change().save(h)
+    }
+
+    fn load(&self, id: HistoryItemId) -> Result<HistoryItem> {
+        lock(&self.history).load(id)
+    }
+
+    fn count(&self, query: SearchQuery) -> Result<i64> {
+        // The results are released before waiting for the history, the worker holding it
+        let answer = lock(&self.counts).answer(&query);
+        match answer {
+            Some(count) => Ok(count),
+            None => lock(&self.history).count(query),
+        }
+    }
+
+    fn try_count(&self, query: SearchQuery) -> Result<Option<i64>> {
+        self.run_in_background(query, &self.counts, |history, query| history.count(query))
+    }
+
+    fn count_all(&self) -> Result<i64> {
+        lock(&self.history).count_all()
+    }
+
+    fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        // The results are released before waiting for the history, the worker holding it
+        let answer = lock(&self.searches).answer(&query);
+        match answer {
+            Some(items) => Ok(items),
+            None => lock(&self.history).search(query),
+        }
+    }
+
+    fn try_search(&self, query: SearchQuery) -> Result<Option<Vec<HistoryItem>>> {
+        self.run_in_background(query, &self.searches, |history, query| {
+            history.search(query)
+        })
+    }
+
+    /// Collects the items first, as the history can't stay locked while the iterator lives
+    fn iter(
+        &self,
+        direction: SearchDirection,
+    ) -> Result<Box<dyn Iterator<Item = HistoryItem> + '_>> {
+        let items = lock(&self.history).iter(direction)?.collect::<Vec<_>>();
+        Ok(Box::new(items.into_iter()))
+    }
+
+    fn update(
+        &mut self,
+        id: HistoryItemId,
+        updater: &dyn Fn(HistoryItem) -> HistoryItem,
+    ) -> Result<()> {
+        self.change().update(id, updater)
+    }
+
+    fn update_outcome(
+        &mut self,
+        id: HistoryItemId,
+        duration: Option<Duration>,
+        exit_status: Option<i64>,
+    ) -> Result<()> {
+        self.change().update_outcome(id, duration, exit_status)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.change().clear()
+    }
+
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        self.change().delete(h)
+    }
+
+    fn sync(&mut self) -> std::io::Result<()> {
+        // Picks up the entries of the other sessions
+        self.change().sync()
+    }
+
+    fn session(&self) -> Option<HistorySessionId> {
+        self.session
+    }
+
+    fn awaits_late_results(&self) -> bool {
+        lock(&self.searches).overdue
+            || lock(&self.counts).overdue
+            || self.late.load(Ordering::SeqCst)
+    }
+
+    fn take_late_results(&self) -> bool {
+        self.late.swap(false, Ordering::SeqCst)
+    }
+
+    fn most_frequent(&self, query: SearchQuery) -> Result<Vec<(String, i64)>> {
+        lock(&self.history).most_frequent(query)
+    }
+
+    fn export(&self, writer: &mut dyn Write) -> Result<usize> {
+        lock(&self.history).export(writer)
+    }
+
+    fn import(&mut self, reader: &mut dyn BufRead) -> Result<ImportSummary> {
+        self.change().import(reader)
+    }
+
+    fn merge(&mut self, source: &dyn History) -> Result<usize> {
+        self.change().merge(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileBackedHistory;
+
+    fn background_history(command_lines: &[&str]) -> BackgroundHistory<FileBackedHistory> {
+        let mut history = FileBackedHistory::default();
+        for command_line in command_lines {
+            history
+                .save(HistoryItem::from_command_line(*command_line))
+                .unwrap();
+        }
+        BackgroundHistory::new(history).with_timeout(Duration::from_secs(5))
+    }
+
+    fn command_lines(items: Vec<HistoryItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.command_line).collect()
+    }
+
+    #[test]
+    fn searches_like_the_wrapped_history() {
+        let history = background_history(&["ls", "cd foo", "ls -l"]);
+
+        let results = history
+            .search(SearchQuery::all_that_contain_rev("ls".to_string()))
+            .unwrap();
+        assert_eq!(command_lines(results), vec!["ls -l", "ls"]);
+        assert_eq!(
+            history
+                .count(SearchQuery::all_that_contain_rev("cd".to_string()))
+                .unwrap(),
+            1
+        );
+    }
+
+    /// Wait for the overdue queries, whether any of them finished late
+    fn wait_for_late_results(history: &BackgroundHistory<FileBackedHistory>) -> bool {
+        let mut late = false;
+        while history.awaits_late_results() {
+            late |= history.take_late_results();
+            thread::sleep(Duration::from_millis(1));
+        }
+        late
+    }
+
+    #[test]
+    fn slow_search_answers_none_until_it_finished() {
+        let history = background_history(&["ls", "cd foo"]).with_timeout(Duration::ZERO);
+        let query = || SearchQuery::all_that_contain_rev("cd".to_string());
+        let busy = lock(&history.history);
+        assert_eq!(history.try_search(query()).unwrap(), None);
+        assert!(history.awaits_late_results());
+
+        drop(busy);
+        assert!(wait_for_late_results(&history));
+        assert_eq!(
+            history.try_search(query()).unwrap().map(command_lines),
+            Some(vec!["cd foo".to_string()])
+        );
+    }
+
+    #[test]
+    fn slow_count_answers_none_until_it_finished() {
+        let history = background_history(&["ls"]).with_timeout(Duration::ZERO);
+        let query = || SearchQuery::all_that_contain_rev("ls".to_string());
+        let busy = lock(&history.history);
+        assert_eq!(history.try_count(query()).unwrap(), None);
+
+        drop(busy);
+        assert!(wait_for_late_results(&history));
+        assert_eq!(history.try_count(query()).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn search_answers_its_own_query_while_another_is_late() {
+        let history = background_history(&["ls", "cd foo"]).with_timeout(Duration::ZERO);
+        let query = |contains: &str| SearchQuery::all_that_contain_rev(contains.to_string());
+        let busy = lock(&history.history);
+        assert_eq!(history.try_search(query("cd")).unwrap(), None);
+        assert_eq!(history.try_count(query("cd")).unwrap(), None);
+
+        drop(busy);
+        assert_eq!(
+            command_lines(history.search(query("ls")).unwrap()),
+            vec!["ls"]
+        );
+        assert_eq!(history.count(query("ls")).unwrap(), 1);
+        wait_for_late_results(&history);
+    }
+
+    #[test]
+    fn keeps_the_results_of_several_queries() {
+        let history = background_history(&["ls", "cd foo"]).with_timeout(Duration::ZERO);
+        let query = |contains: &str| SearchQuery::all_that_contain_rev(contains.to_string());
+        let busy = lock(&history.history);
+        assert_eq!(history.try_search(query("ls")).unwrap(), None);
+        drop(busy);
+        wait_for_late_results(&history);
+        let busy = lock(&history.history);
+        assert_eq!(history.try_search(query("cd")).unwrap(), None);
+        drop(busy);
+        wait_for_late_results(&history);
+
+        let _busy = lock(&history.history);
+        assert_eq!(
+            history.try_search(query("ls")).unwrap().map(command_lines),
+            Some(vec!["ls".to_string()])
+        );
+        assert_eq!(
+            history.try_search(query("cd")).unwrap().map(command_lines),
+            Some(vec!["cd foo".to_string()])
+        );
+    }
+
+    #[test]
+    fn count_does_not_supersede_search() {
+        let history = background_history(&["ls", "cd foo"]).with_timeout(Duration::ZERO);
+        let query = || SearchQuery::everything(SearchDirection::Backward, None);
+        let busy = lock(&history.history);
+        assert_eq!(history.try_search(query()).unwrap(), None);
+        assert_eq!(history.try_count(query()).unwrap(), None);
+
+        drop(busy);
+        assert!(wait_for_late_results(&history));
+        assert_eq!(
+            history.try_search(query()).unwrap().map(command_lines),
+            Some(vec!["cd foo".to_string(), "ls".to_string()])
+        );
+        assert_eq!(history.try_count(query()).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn change_drops_cached_results() {
+        let mut history = background_history(&["ls"]);
+        let query = || SearchQuery::everything(SearchDirection::Backward, None);
+        assert_eq!(command_lines(history.search(query()).unwrap()), vec!["ls"]);
+
+        history
+            .save(HistoryItem::from_command_line("cd foo"))
+            .unwrap();
+
+        assert_eq!(
+            command_lines(history.search(query()).unwrap()),
+            vec!["cd foo", "ls"]
+        );
+    }
+}
@@ -1,6 +1,16 @@
-use super::HistoryItemId;
-use crate::{core_editor::LineBuffer, HistoryItem, HistorySessionId, Result};
+use super::{HistoryItemId, ImportSummary};
+use crate::{
+    core_editor::LineBuffer,
+    result::{ReedlineError, ReedlineErrorVariants},
+    HistoryItem, HistorySessionId, Result,
+};
 use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::{BufRead, Write},
+    time::Duration,
+};
 
 /// Browsing modes for a [`History`]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,6 +27,7 @@ pub enum HistoryNavigationQuery {
 
 /// Ways to search for a particular command line in the [`History`]
 // todo: merge with [HistoryNavigationQuery]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CommandLineSearch {
     /// Command line starts with the same string
     Prefix(String),
@@ -26,10 +37,23 @@ pub enum CommandLineSearch {
     ///
     /// Useful to gather statistics
     Exact(String),
+    /// Command line contains all of the strings, in any order
+    AllTokens(Vec<String>),
+    /// Command line matches the regular expression, anywhere unless it is anchored
+    Regex(String),
+}
+
+/// Compiles the pattern of a [`CommandLineSearch::Regex`], failing with
+/// [`ReedlineErrorVariants::InvalidRegex`] instead of panicking on an invalid one
+pub(crate) fn compile_regex(pattern: &str, case_insensitive: bool) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| ReedlineError(ReedlineErrorVariants::InvalidRegex(e.to_string())))
 }
 
 /// Defines how to traverse the history when executing a [`SearchQuery`]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchDirection {
     /// From the most recent entry backward
     Backward,
@@ -37,10 +61,40 @@ pub enum SearchDirection {
     Forward,
 }
 
+/// Which sessions the entries matching a [`SearchFilter`] may come from, relative to its
+/// [`SearchFilter::session`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SessionFilter {
+    /// Entries from every session
+    #[default]
+    All,
+    /// Only the entries from the given session
+    Only,
+    /// Only the entries from the sessions other than the given one
+    Others,
+}
+
 /// Defines additional filters for querying the [`History`]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchFilter {
     /// Query for the command line content
     pub command_line: Option<CommandLineSearch>,
+    /// Match the `command_line` query ignoring ASCII case, or any case for a
+    /// [`CommandLineSearch::Regex`]
+    pub case_insensitive: bool,
+    /// Return every occurrence of a command line. Otherwise the histories deduplicating
+    /// their results, like the [`FileBackedHistory`](crate::FileBackedHistory), only return
+    /// the first one in the search direction.
+    pub keep_duplicates: bool,
+    /// Only return the first occurrence of every command line in the search direction, the
+    /// most recent one when searching backward. Deduplicated by the history itself, so the
+    /// `limit` and [`History::count`] apply to the distinct command lines. Takes precedence
+    /// over `keep_duplicates`
+    pub distinct: bool,
+    /// Leave out the command lines containing any of these substrings, ignoring ASCII case
+    /// with `case_insensitive`. Applied before the `limit`, so [`History::count`] agrees with
+    /// [`History::search`]
+    pub excluded_substrings: Vec<String>,
     /// Considered implementation detail for now
     pub(crate) not_command_line: Option<String>, // to skip the currently shown value in up-arrow navigation
     /// Filter based on the executing systems hostname
@@ -51,8 +105,14 @@ pub struct SearchFilter {
     pub cwd_prefix: Option<String>,
     /// Filter whether the command completed
     pub exit_successful: Option<bool>,
+    /// Keep the entries without a recorded exit status when filtering on `exit_successful`,
+    /// like the command still running. Otherwise they are left out
+    pub keep_unknown_exit_status: bool,
     /// Filter on the session id
     pub session: Option<HistorySessionId>,
+    /// Restrict the results to the `session` or to the other sessions. Has no effect without
+    /// a `session`
+    pub session_filter: SessionFilter,
 }
 
 impl SearchFilter {
@@ -82,23 +142,32 @@ impl SearchFilter {
     pub fn anything(session: Option<HistorySessionId>) -> SearchFilter {
         SearchFilter {
             command_line: None,
+            case_insensitive: false,
+            keep_duplicates: false,
+            distinct: false,
+            excluded_substrings: Vec::new(),
             not_command_line: None,
             hostname: None,
             cwd_exact: None,
             cwd_prefix: None,
             exit_successful: None,
+            keep_unknown_exit_status: false,
             session,
+            session_filter: SessionFilter::All,
         }
     }
 }
 
 /// Query for search in the potentially rich [`History`]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SearchQuery {
     /// Direction to search in
     pub direction: SearchDirection,
-    /// if given, only get results after/before this time (depending on direction)
+    /// if given, only get results started after/before this time (depending on direction).
+    /// Entries without a start time are left out
     pub start_time: Option<chrono::DateTime<Utc>>,
-    /// if given, only get results after/before this time (depending on direction)
+    /// if given, only get results started at or before/after this time (depending on
+    /// direction). Entries without a start time are left out
     pub end_time: Option<chrono::DateTime<Utc>>,
     /// if given, only get results after/before this id (depending on direction)
     pub start_id: Option<HistoryItemId>,
@@ -125,6 +194,57 @@ impl SearchQuery {
         }
     }
 
+    /// all that contain string in chronological order
+    pub fn all_that_contain(contains: String) -> SearchQuery {
+        SearchQuery {
+            direction: SearchDirection::Forward,
+            ..SearchQuery::all_that_contain_rev(contains)
+        }
+    }
+
+    /// all that contain every one of the `tokens` in reverse chronological order
+    pub fn all_with_tokens_rev(tokens: Vec<String>) -> SearchQuery {
+        SearchQuery {
+            filter: SearchFilter::from_text_search(CommandLineSearch::AllTokens(tokens), None),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        }
+    }
+
+    /// all that start with the `prefix` in reverse chronological order
+    pub fn all_with_prefix_rev(prefix: String) -> SearchQuery {
+        SearchQuery {
+            filter: SearchFilter::from_text_search(CommandLineSearch::Prefix(prefix), None),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        }
+    }
+
+    /// all that were started at or after `start` and before `end`, in reverse chronological
+    /// order
+    pub fn in_range(start: chrono::DateTime<Utc>, end: chrono::DateTime<Utc>) -> SearchQuery {
+        SearchQuery {
+            // Searching backward the start bound is the newest one
+            start_time: Some(end),
+            end_time: Some(start),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        }
+    }
+
+    /// all that were started at or after `start`, in reverse chronological order
+    pub fn since(start: chrono::DateTime<Utc>) -> SearchQuery {
+        SearchQuery {
+            end_time: Some(start),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        }
+    }
+
+    /// all that were started before `end`, in reverse chronological order
+    pub fn before(end: chrono::DateTime<Utc>) -> SearchQuery {
+        SearchQuery {
+            start_time: Some(end),
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        }
+    }
+
     /// Get the most recent entry matching [`SearchFilter`]
     pub const fn last_with_search(filter: SearchFilter) -> SearchQuery {
         SearchQuery {
@@ -203,6 +323,29 @@ pub trait History: Send {
     }
     /// return the results of a query
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>>;
+    /// like [`History::search`], but `None` if the history answers before the query finished,
+    /// like a [`BackgroundHistory`](crate::BackgroundHistory) after its timeout. The query
+    /// keeps running and [`History::take_late_results`] tells once it finished
+    fn try_search(&self, query: SearchQuery) -> Result<Option<Vec<HistoryItem>>> {
+        self.search(query).map(Some)
+    }
+    /// like [`History::count`], but `None` if the history answers before the query finished,
+    /// see [`History::try_search`]
+    fn try_count(&self, query: SearchQuery) -> Result<Option<i64>> {
+        self.count(query).map(Some)
+    }
+    /// iterate over every history item, repeated command lines included, oldest first if
+    /// `direction` is [`SearchDirection::Forward`] and newest first if it is
+    /// [`SearchDirection::Backward`]. The history can't change while the iterator borrows it.
+    /// Histories that can't stream their items collect them with [`History::search`] first
+    fn iter(
+        &self,
+        direction: SearchDirection,
+    ) -> Result<Box<dyn Iterator<Item = HistoryItem> + '_>> {
+        let mut query = SearchQuery::everything(direction, None);
+        query.filter.keep_duplicates = true;
+        Ok(Box::new(self.search(query)?.into_iter()))
+    }
 
     /// update an item atomically
     fn update(
@@ -210,14 +353,89 @@ pub trait History: Send {
         id: HistoryItemId,
         updater: &dyn Fn(HistoryItem) -> HistoryItem,
     ) -> Result<()>;
+    /// record the `duration` and `exit_status` of the command saved as `id` once it finished,
+    /// keeping the recorded values where `None` is given. Keyed on the id, so the entry is
+    /// still found after a new session started. Histories that can't update their entries
+    /// fail with [`ReedlineErrorVariants::HistoryFeatureUnsupported`]
+    fn update_outcome(
+        &mut self,
+        id: HistoryItemId,
+        duration: Option<Duration>,
+        exit_status: Option<i64>,
+    ) -> Result<()> {
+        self.update(id, &|item| HistoryItem {
+            duration: duration.or(item.duration),
+            exit_status: exit_status.or(item.exit_status),
+            ..item
+        })
+    }
     /// delete all history items
     fn clear(&mut self) -> Result<()>;
-    /// remove an item from this history
+    /// remove an item from this history, doing nothing if no item has the id `h`
     fn delete(&mut self, h: HistoryItemId) -> Result<()>;
     /// ensure that this history is written to disk
     fn sync(&mut self) -> std::io::Result<()>;
     /// get the history session id
     fn session(&self) -> Option<HistorySessionId>;
+
+    /// whether a [`History::try_search`] or [`History::try_count`] this history answered
+    /// before it finished is still running, or finished without
+    /// [`History::take_late_results`] being called since. The editor keeps checking for late
+    /// results while this holds
+    fn awaits_late_results(&self) -> bool {
+        false
+    }
+    /// whether a search or count answered before it finished has finished since the last
+    /// call, so the editor updates the active menu and repaints to show its results
+    fn take_late_results(&self) -> bool {
+        false
+    }
+
+    /// return the command lines of the items matching the `query` with how often they were
+    /// run, the most frequent first. Among equally frequent ones the most recently run comes
+    /// first. The `limit` of the query caps the number of command lines, its direction is
+    /// ignored
+    fn most_frequent(&self, mut query: SearchQuery) -> Result<Vec<(String, i64)>> {
+        let limit = query.limit.take();
+        query.direction = SearchDirection::Backward;
+        query.filter.keep_duplicates = true;
+        let mut counts: Vec<(String, i64)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for item in self.search(query)? {
+            match positions.entry(item.command_line) {
+                Entry::Occupied(position) => counts[*position.get()].1 += 1,
+                Entry::Vacant(position) => {
+                    counts.push((position.key().clone(), 1));
+                    position.insert(counts.len() - 1);
+                }
+            }
+        }
+        // The sort is stable, so the most recently run stays first among equal counts
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        if let Some(limit) = limit {
+            counts.truncate(usize::try_from(limit).unwrap_or(0));
+        }
+        Ok(counts)
+    }
+
+    /// write all history items, oldest first, to `writer` as one JSON object per line and
+    /// return how many were written. Missing metadata is left out of the objects
+    fn export(&self, writer: &mut dyn Write) -> Result<usize> {
+        super::import::export_jsonl(self, writer)
+    }
+    /// save the history items read from JSON lines written by [`History::export`], under new
+    /// ids. Malformed lines are skipped
+    fn import(&mut self, reader: &mut dyn BufRead) -> Result<ImportSummary> {
+        super::import::import_jsonl(self, reader)
+    }
+    /// save the history items of `source` this history doesn't hold yet after the existing
+    /// ones, in the order of `source`, and return how many were saved. Items are the same if
+    /// they have the same command line and start time, or the same command line and either of
+    /// them doesn't record its start time, so merging twice saves nothing the second time. The
+    /// existing items keep their ids
+    fn merge(&mut self, source: &dyn History) -> Result<usize> {
+        super::import::merge(self, source)
+    }
 }
 
 #[cfg(test)]
@@ -349,6 +567,44 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn search_prefix_rev() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let res = history.search(SearchQuery::all_with_prefix_rev("cd ".to_string()))?;
+        search_returned(&*history, res, vec![8, 4, 1])?;
+        let res = history.search(SearchQuery::all_with_prefix_rev("foo".to_string()))?;
+        search_returned(&*history, res, vec![])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_tokens() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let tokens = |tokens: &[&str]| tokens.iter().map(|t| t.to_string()).collect();
+        let res = history.search(SearchQuery::all_with_tokens_rev(tokens(&["nginx", "vim"])))?;
+        search_returned(&*history, res, vec![10])?;
+        let res = history.search(SearchQuery::all_with_tokens_rev(tokens(&["zip", "foo"])))?;
+        search_returned(&*history, res, vec![3, 2])?;
+        assert_eq!(
+            history.count(SearchQuery::all_with_tokens_rev(tokens(&["zip", "foo"])))?,
+            2
+        );
+        let res = history.search(SearchQuery {
+            filter: SearchFilter {
+                case_insensitive: true,
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::AllTokens(tokens(&["CONF", "Cat"])),
+                    None,
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Backward, None)
+        })?;
+        search_returned(&*history, res, vec![12])?;
+
+        Ok(())
+    }
+
     #[test]
     fn search_includes() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -363,6 +619,83 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn update_outcome() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        let id = HistoryItemId::new(3);
+        let before = history.load(id)?;
+
+        let res = history.update_outcome(id, Some(Duration::from_millis(250)), None);
+        if IS_FILE_BASED {
+            assert!(matches!(
+                res,
+                Err(ReedlineError(
+                    ReedlineErrorVariants::HistoryFeatureUnsupported { .. }
+                ))
+            ));
+            assert_eq!(history.load(id)?, before);
+        } else {
+            res?;
+            assert_eq!(
+                history.load(id)?,
+                HistoryItem {
+                    duration: Some(Duration::from_millis(250)),
+                    ..before
+                }
+            );
+        }
+
+        assert!(history
+            .update_outcome(HistoryItemId::new(100), None, Some(1))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn iter_in_both_directions() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(create_item(1, "/etc/nginx", "ls -l", 0))?; // 13
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.keep_duplicates = true;
+        let all = history.search(query)?;
+        assert_eq!(all.len(), if IS_FILE_BASED { 14 } else { 13 });
+
+        let forward = history.iter(SearchDirection::Forward)?.collect::<Vec<_>>();
+        assert_eq!(forward, all);
+        let mut backward = history.iter(SearchDirection::Backward)?.collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(backward, all);
+        Ok(())
+    }
+
+    #[test]
+    fn search_distinct() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(create_item(1, "/etc/nginx", "ls", 0))?; // 13
+        history.save(create_item(1, "/etc/nginx", "cd foo", 0))?; // 14
+        history.save(create_item(1, "/etc/nginx", "ls", 0))?; // 15
+        let query = |direction| SearchQuery {
+            filter: SearchFilter {
+                distinct: true,
+                keep_duplicates: true,
+                ..SearchFilter::from_text_search(CommandLineSearch::Prefix("ls".to_string()), None)
+            },
+            ..SearchQuery::everything(direction, None)
+        };
+
+        let res = history.search(query(SearchDirection::Backward))?;
+        search_returned(&*history, res, vec![15, 9, 6])?;
+        let res = history.search(query(SearchDirection::Forward))?;
+        search_returned(&*history, res, vec![5, 6, 9])?;
+        let res = history.search(SearchQuery {
+            limit: Some(2),
+            ..query(SearchDirection::Forward)
+        })?;
+        search_returned(&*history, res, vec![5, 6])?;
+        assert_eq!(history.count(query(SearchDirection::Backward))?, 3);
+        Ok(())
+    }
+
     #[test]
     fn search_includes_limit() -> Result<()> {
         let history = create_filled_example_history()?;
@@ -379,6 +712,365 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn search_case_insensitive() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = |search: CommandLineSearch, case_insensitive| SearchQuery {
+            filter: SearchFilter {
+                case_insensitive,
+                ..SearchFilter::from_text_search(search, None)
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query(CommandLineSearch::Prefix("LS ".to_string()), true))?;
+        search_returned(&*history, res, vec![6, 9])?;
+        let res = history.search(query(
+            CommandLineSearch::Substring("NGINX".to_string()),
+            true,
+        ))?;
+        search_returned(&*history, res, vec![8, 10, 12])?;
+        let res = history.search(query(CommandLineSearch::Exact("Cd Foo".to_string()), true))?;
+        search_returned(&*history, res, vec![4])?;
+        assert_eq!(
+            history.count(query(
+                CommandLineSearch::Substring("NGINX".to_string()),
+                true
+            ))?,
+            3
+        );
+
+        let res = history.search(query(CommandLineSearch::Prefix("LS ".to_string()), false))?;
+        search_returned(&*history, res, vec![])?;
+        assert_eq!(
+            history.count(query(
+                CommandLineSearch::Substring("NGINX".to_string()),
+                false
+            ))?,
+            0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_excludes_substrings() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = |excluded: &[&str], case_insensitive| SearchQuery {
+            filter: SearchFilter {
+                case_insensitive,
+                excluded_substrings: excluded.iter().map(|s| s.to_string()).collect(),
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::Substring("nginx".to_string()),
+                    None,
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query(&["vim"], false))?;
+        search_returned(&*history, res, vec![8, 12])?;
+        assert_eq!(history.count(query(&["vim"], false))?, 2);
+        let res = history.search(query(&["vim", "cd "], false))?;
+        search_returned(&*history, res, vec![12])?;
+        assert_eq!(history.count(query(&["vim", "cd "], false))?, 1);
+
+        let res = history.search(query(&["VIM"], false))?;
+        search_returned(&*history, res, vec![8, 10, 12])?;
+        let res = history.search(query(&["VIM"], true))?;
+        search_returned(&*history, res, vec![8, 12])?;
+        assert_eq!(history.count(query(&["VIM"], true))?, 2);
+
+        // `like` wildcards are taken literally
+        let res = history.search(query(&["c_t"], true))?;
+        search_returned(&*history, res, vec![8, 10, 12])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_regex() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = |pattern: &str, case_insensitive| SearchQuery {
+            filter: SearchFilter {
+                case_insensitive,
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::Regex(pattern.to_string()),
+                    None,
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query("^(ls|cat) ", false))?;
+        search_returned(&*history, res, vec![6, 7, 9, 12])?;
+        assert_eq!(history.count(query("^(ls|cat) ", false))?, 4);
+        let res = history.search(query("^LS ", false))?;
+        search_returned(&*history, res, vec![])?;
+        let res = history.search(query("^LS ", true))?;
+        search_returned(&*history, res, vec![6, 9])?;
+
+        assert!(matches!(
+            history.search(query("(ls", false)),
+            Err(ReedlineError(ReedlineErrorVariants::InvalidRegex(_)))
+        ));
+        assert!(matches!(
+            history.count(query("(ls", false)),
+            Err(ReedlineError(ReedlineErrorVariants::InvalidRegex(_)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn most_frequent_command_lines() -> Result<()> {
+        #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+        let mut history = crate::FileBackedHistory::default();
+        for cmd in ["ls", "cd", "ls", "git", "cd", "ls", "pwd", "git"] {
+            history.save(create_item(1, "/", cmd, 0))?;
+        }
+        let runs = |runs: &[(&str, i64)]| {
+            runs.iter()
+                .map(|(cmd, n)| (cmd.to_string(), *n))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            history.most_frequent(SearchQuery::everything(SearchDirection::Forward, None))?,
+            runs(&[("ls", 3), ("git", 2), ("cd", 2), ("pwd", 1)])
+        );
+        assert_eq!(
+            history.most_frequent(SearchQuery {
+                limit: Some(2),
+                ..SearchQuery::everything(SearchDirection::Backward, None)
+            })?,
+            runs(&[("ls", 3), ("git", 2)])
+        );
+        assert_eq!(
+            history.most_frequent(SearchQuery::all_that_contain_rev("d".to_string()))?,
+            runs(&[("cd", 2), ("pwd", 1)])
+        );
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_by_exit_status() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(HistoryItem {
+            exit_status: None,
+            ..create_item(1, "/", "unzip bar.zip", 0)
+        })?; // 13
+        let query = |exit_successful, keep_unknown_exit_status| SearchQuery {
+            filter: SearchFilter {
+                exit_successful: Some(exit_successful),
+                keep_unknown_exit_status,
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::Substring("zip".to_string()),
+                    None,
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query(true, false))?;
+        search_returned(&*history, res, vec![3])?;
+        let res = history.search(query(false, false))?;
+        search_returned(&*history, res, vec![2])?;
+        let res = history.search(query(true, true))?;
+        search_returned(&*history, res, vec![3, 13])?;
+        assert_eq!(history.count(query(true, true))?, 2);
+        let res = history.search(query(false, true))?;
+        search_returned(&*history, res, vec![2, 13])?;
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+    #[test]
+    fn file_backed_history_has_unknown_exit_statuses() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = |keep_unknown_exit_status| SearchQuery {
+            filter: SearchFilter {
+                exit_successful: Some(true),
+                keep_unknown_exit_status,
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::Substring("zip".to_string()),
+                    None,
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        assert_eq!(history.count(query(false))?, 0);
+        assert_eq!(history.count(query(true))?, 2);
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+    #[test]
+    fn file_backed_history_has_no_entries_on_hosts() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = SearchQuery {
+            filter: SearchFilter {
+                hostname: Some("foohost".to_string()),
+                ..SearchFilter::anything(None)
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        assert_eq!(history.count(query)?, 0);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_by_session() -> Result<()> {
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        history.save(create_item(1, "/", "ls", 0))?; // 1
+        history.save(create_item(2, "/", "ls -l", 0))?; // 2
+        history.save(create_item(1, "/", "ls -a", 0))?; // 3
+        let query = |session_filter| SearchQuery {
+            filter: SearchFilter {
+                session_filter,
+                ..SearchFilter::from_text_search(
+                    CommandLineSearch::Prefix("ls".to_string()),
+                    Some(HistorySessionId::new(1)),
+                )
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query(SessionFilter::All))?;
+        search_returned(&history, res, vec![1, 2, 3])?;
+        let res = history.search(query(SessionFilter::Only))?;
+        search_returned(&history, res, vec![1, 3])?;
+        assert_eq!(history.count(query(SessionFilter::Only))?, 2);
+        let res = history.search(query(SessionFilter::Others))?;
+        search_returned(&history, res, vec![2])?;
+        assert_eq!(history.count(query(SessionFilter::Others))?, 1);
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_by_cwd_prefix() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        history.save(HistoryItem {
+            cwd: None,
+            ..create_item(1, "", "ls -a", 0)
+        })?; // 13
+        history.save(create_item(1, "/home/meX", "ls", 0))?; // 14
+        history.save(create_item(1, "/HOME/me/Downloads", "ls", 0))?; // 15
+        let query = |cwd_prefix: &str| SearchQuery {
+            filter: SearchFilter {
+                cwd_prefix: Some(cwd_prefix.to_string()),
+                ..SearchFilter::from_text_search(CommandLineSearch::Prefix("ls".to_string()), None)
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        let res = history.search(query("/home/me/Downloads"))?;
+        search_returned(&*history, res, vec![5, 6])?;
+        assert_eq!(history.count(query("/home/me/Downloads"))?, 2);
+        let res = history.search(query("/home/me_"))?;
+        search_returned(&*history, res, vec![])?;
+        let res = history.search(query("/"))?;
+        search_returned(&*history, res, vec![5, 6, 9, 14, 15])?;
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn search_by_time_range() -> Result<()> {
+        use chrono::TimeZone;
+        let at = |secs| Utc.timestamp_opt(secs, 0).unwrap();
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        for (i, start_timestamp) in [Some(at(10)), None, Some(at(20)), Some(at(30))]
+            .into_iter()
+            .enumerate()
+        {
+            history.save(HistoryItem {
+                start_timestamp,
+                ..create_item(1, "/", &format!("cmd {i}"), 0)
+            })?; // i + 1
+        }
+
+        let res = history.search(SearchQuery::in_range(at(10), at(30)))?;
+        search_returned(&history, res, vec![3, 1])?;
+        assert_eq!(history.count(SearchQuery::in_range(at(10), at(30)))?, 2);
+        let res = history.search(SearchQuery::since(at(20)))?;
+        search_returned(&history, res, vec![4, 3])?;
+        let res = history.search(SearchQuery::before(at(20)))?;
+        search_returned(&history, res, vec![1])?;
+        let res = history.search(SearchQuery {
+            start_time: Some(at(10)),
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        })?;
+        search_returned(&history, res, vec![3, 4])?;
+
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+    #[test]
+    fn file_backed_history_has_no_entries_in_time_ranges() -> Result<()> {
+        let history = create_filled_example_history()?;
+
+        assert_eq!(history.count(SearchQuery::since(Utc::now()))?, 0);
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+    #[test]
+    fn file_backed_history_has_no_entries_in_directories() -> Result<()> {
+        let history = create_filled_example_history()?;
+        let query = SearchQuery {
+            filter: SearchFilter {
+                cwd_prefix: Some("/".to_string()),
+                ..SearchFilter::anything(None)
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        assert_eq!(history.count(query)?, 0);
+        Ok(())
+    }
+
+    #[cfg(not(any(feature = "sqlite", feature = "sqlite-dynlib")))]
+    #[test]
+    fn file_backed_history_rejects_session_filters() {
+        let history = crate::FileBackedHistory::default();
+        let query = SearchQuery {
+            filter: SearchFilter {
+                session_filter: SessionFilter::Only,
+                ..SearchFilter::anything(Some(HistorySessionId::new(1)))
+            },
+            ..SearchQuery::everything(SearchDirection::Forward, None)
+        };
+
+        assert!(history.search(query).is_err());
+    }
+
+    #[test]
+    fn delete_item() -> Result<()> {
+        let mut history = create_filled_example_history()?;
+        let count = history.count_all()?;
+
+        history.delete(HistoryItemId::new(3))?;
+        assert_eq!(history.count_all()?, count - 1);
+        let found = history.search(SearchQuery::all_that_contain_rev("unzip".to_string()))?;
+        assert!(found.is_empty());
+
+        // A missing item is no error
+        history.delete(HistoryItemId::new(100))?;
+        assert_eq!(history.count_all()?, count - 1);
+
+        Ok(())
+    }
+
     #[test]
     fn clear_history() -> Result<()> {
         let mut history = create_filled_example_history()?;
@@ -0,0 +1,154 @@
+use super::HistoryCipher;
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Key, XChaCha20Poly1305, XNonce,
+};
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// [`HistoryCipher`] sealing the history file with XChaCha20-Poly1305
+///
+/// Every write picks a fresh random nonce and stores it in front of the ciphertext. Built
+/// [`from_passphrase`](ChaChaHistoryCipher::from_passphrase), the key is derived with Argon2
+/// and the salt is stored in front of the nonce, so the same passphrase opens the file again.
+///
+/// ```rust,no_run
+/// use reedline::{ChaChaHistoryCipher, FileBackedHistory};
+///
+/// let cipher = ChaChaHistoryCipher::from_passphrase("correct horse battery staple").unwrap();
+/// let history =
+///     FileBackedHistory::with_encrypted_file(1000, "history.enc".into(), cipher).unwrap();
+/// ```
+pub struct ChaChaHistoryCipher {
+    key: Key,
+    // the passphrase and the salt the key was derived with, if it was
+    passphrase: Option<(String, [u8; SALT_LEN])>,
+}
+
+impl ChaChaHistoryCipher {
+    /// Cipher using the 256 bit `key` directly
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            key: key.into(),
+            passphrase: None,
+        }
+    }
+
+    /// Cipher deriving its key from the `passphrase` with Argon2 and a random salt
+    ///
+    /// Fails if the system offers no randomness or the key can't be derived.
+    pub fn from_passphrase(passphrase: &str) -> std::result::Result<Self, String> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.try_fill_bytes(&mut salt).map_err(|e| e.to_string())?;
+        Ok(Self {
+            key: derive_key(passphrase, &salt)?,
+            passphrase: Some((passphrase.to_string(), salt)),
+        })
+    }
+}
+
+impl HistoryCipher for ChaChaHistoryCipher {
+    fn encrypt(&self, plaintext: &[u8]) -> std::result::Result<Vec<u8>, String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| e.to_string())?;
+        let mut sealed = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+        if let Some((_, salt)) = &self.passphrase {
+            sealed.extend_from_slice(salt);
+        }
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> std::result::Result<Vec<u8>, String> {
+        let (key, ciphertext) = match &self.passphrase {
+            Some((passphrase, salt)) => {
+                if ciphertext.len() < SALT_LEN {
+                    return Err("ciphertext too short".to_string());
+                }
+                let (file_salt, ciphertext) = ciphertext.split_at(SALT_LEN);
+                // A file written by another session was sealed with its own salt
+                let key = if file_salt == salt {
+                    self.key
+                } else {
+                    derive_key(passphrase, file_salt)?
+                };
+                (key, ciphertext)
+            }
+            None => (self.key, ciphertext),
+        };
+        if ciphertext.len() < NONCE_LEN {
+            return Err("ciphertext too short".to_string());
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_LEN);
+        XChaCha20Poly1305::new(&key)
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "wrong key or tampered ciphertext".to_string())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> std::result::Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trips_with_key() {
+        let cipher = ChaChaHistoryCipher::new([7; 32]);
+        let ciphertext = cipher.encrypt(b"login --token=secret").unwrap();
+
+        assert!(!String::from_utf8_lossy(&ciphertext).contains("secret"));
+        assert_eq!(
+            cipher.decrypt(&ciphertext).unwrap(),
+            b"login --token=secret"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let cipher = ChaChaHistoryCipher::new([7; 32]);
+        let mut ciphertext = cipher.encrypt(b"ls").unwrap();
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+
+        assert!(cipher.decrypt(&ciphertext).is_err());
+        assert!(cipher.decrypt(&ciphertext[..NONCE_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let ciphertext = ChaChaHistoryCipher::new([7; 32]).encrypt(b"ls").unwrap();
+
+        assert!(ChaChaHistoryCipher::new([8; 32])
+            .decrypt(&ciphertext)
+            .is_err());
+    }
+
+    #[test]
+    fn passphrase_opens_file_of_another_session() {
+        let ciphertext = ChaChaHistoryCipher::from_passphrase("hunter2")
+            .unwrap()
+            .encrypt(b"ls")
+            .unwrap();
+
+        let cipher = ChaChaHistoryCipher::from_passphrase("hunter2").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), b"ls");
+        assert!(ChaChaHistoryCipher::from_passphrase("hunter3")
+            .unwrap()
+            .decrypt(&ciphertext)
+            .is_err());
+    }
+}
@@ -103,7 +103,7 @@ mod tests {
 
     use pretty_assertions::assert_eq;
 
-    use crate::LineBuffer;
+    use crate::{LineBuffer, ReedlineError, ReedlineErrorVariants};
 
     use super::super::*;
     use super::*;
@@ -474,6 +474,265 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn saves_duplicates_according_to_dedup() -> Result<()> {
+        let entries = ["ls", "cd", "ls", "ls", "pwd", "cd"];
+        let saved = |dedup| {
+            let mut hist = FileBackedHistory::default().with_dedup(dedup);
+            add_text_entries(&mut hist, &entries);
+            let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+            query.filter.keep_duplicates = true;
+            hist.search(query)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.command_line)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            saved(HistoryDedup::Consecutive),
+            vec!["ls", "cd", "ls", "pwd", "cd"]
+        );
+        assert_eq!(saved(HistoryDedup::Ignore), vec!["ls", "cd", "pwd"]);
+        assert_eq!(saved(HistoryDedup::Erase), vec!["ls", "pwd", "cd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_entries_starting_with_space() -> Result<()> {
+        let mut hist = FileBackedHistory::new(2)?.with_ignore_space(true);
+        add_text_entries(&mut hist, &["ls", "cd", " export TOKEN=secret", " ls"]);
+
+        assert_eq!(get_all_entry_texts(&hist), vec!["ls", "cd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_entries_matching_ignore_patterns() -> Result<()> {
+        let mut hist = FileBackedHistory::new(3)?
+            .with_ignore_patterns(vec!["* --password=*".to_string(), "clear".to_string()]);
+        add_text_entries(
+            &mut hist,
+            &[
+                "ls",
+                "login --password=hunter2 --user me",
+                "clear",
+                "clear all",
+                "cd",
+                "db --password=",
+            ],
+        );
+
+        assert_eq!(get_all_entry_texts(&hist), vec!["ls", "clear all", "cd"]);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_entries_longer_than_max_length() -> Result<()> {
+        let mut hist = FileBackedHistory::new(3)?.with_max_command_length(8, LongCommandLine::Skip);
+        add_text_entries(&mut hist, &["ls", "cat ./some/file", "cd ./dir"]);
+
+        assert_eq!(get_all_entry_texts(&hist), vec!["ls", "cd ./dir"]);
+        Ok(())
+    }
+
+    #[test]
+    fn truncates_entries_longer_than_max_length() -> Result<()> {
+        let mut hist =
+            FileBackedHistory::new(3)?.with_max_command_length(10, LongCommandLine::Truncate);
+        add_text_entries(&mut hist, &["ls", "echo 1234567", "éééééé"]);
+
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["ls", "echo <...>", "éé<...>"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn changing_capacity_trims_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = FileBackedHistory::with_file(5, histfile.clone())?;
+            add_text_entries(&mut hist, &["1", "2", "3", "4"]);
+            hist.sync()?;
+            add_text_entries(&mut hist, &["5"]);
+
+            hist.set_capacity(2)?;
+            assert_eq!(get_all_entry_texts(&hist), vec!["4", "5"]);
+            hist.set_capacity(10)?;
+            assert_eq!(get_all_entry_texts(&hist), vec!["4", "5"]);
+            add_text_entries(&mut hist, &["6"]);
+        }
+
+        let (reading_hist, _) = create_history_at(10, &histfile);
+        let actual = get_all_entry_texts(reading_hist.as_ref());
+        assert_eq!(actual, vec!["4", "5", "6"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    /// Toy cipher XORing with the key and prefixing a checksum, not meant to be secure
+    struct XorCipher(u8);
+
+    impl HistoryCipher for XorCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> std::result::Result<Vec<u8>, String> {
+            let checksum = plaintext.iter().fold(self.0, |sum, b| sum.wrapping_add(*b));
+            Ok(std::iter::once(checksum)
+                .chain(plaintext.iter().map(|b| b ^ self.0))
+                .collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> std::result::Result<Vec<u8>, String> {
+            let plaintext: Vec<u8> = ciphertext[1..].iter().map(|b| b ^ self.0).collect();
+            let checksum = plaintext.iter().fold(self.0, |sum, b| sum.wrapping_add(*b));
+            if ciphertext[0] == checksum {
+                Ok(plaintext)
+            } else {
+                Err("checksum mismatch".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn encrypts_history_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist =
+                FileBackedHistory::with_encrypted_file(5, histfile.clone(), XorCipher(42))?;
+            add_text_entries(&mut hist, &["login --token=secret", "ls"]);
+        }
+        let contents = std::fs::read(&histfile).unwrap();
+        assert!(!String::from_utf8_lossy(&contents).contains("secret"));
+
+        {
+            let mut hist =
+                FileBackedHistory::with_encrypted_file(5, histfile.clone(), XorCipher(42))?;
+            assert_eq!(
+                get_all_entry_texts(&hist),
+                vec!["login --token=secret", "ls"]
+            );
+            add_text_entries(&mut hist, &["cd"]);
+        }
+        let hist = FileBackedHistory::with_encrypted_file(5, histfile, XorCipher(42))?;
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["login --token=secret", "ls", "cd"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_history_file_rejects_wrong_key() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist =
+                FileBackedHistory::with_encrypted_file(5, histfile.clone(), XorCipher(42))?;
+            add_text_entries(&mut hist, &["ls"]);
+        }
+        let res = FileBackedHistory::with_encrypted_file(5, histfile.clone(), XorCipher(7));
+        assert!(matches!(
+            res,
+            Err(ReedlineError(
+                ReedlineErrorVariants::HistoryDecryptionFailed(_)
+            ))
+        ));
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[cfg(feature = "encrypted_history")]
+    #[test]
+    fn encrypted_history_file_detects_tampering() -> Result<()> {
+        use crate::ChaChaHistoryCipher;
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let mut hist = FileBackedHistory::with_encrypted_file(
+                5,
+                histfile.clone(),
+                ChaChaHistoryCipher::new([42; 32]),
+            )?;
+            add_text_entries(&mut hist, &["login --token=secret", "ls"]);
+        }
+        let mut contents = std::fs::read(&histfile).unwrap();
+        assert!(!String::from_utf8_lossy(&contents).contains("secret"));
+
+        let hist = FileBackedHistory::with_encrypted_file(
+            5,
+            histfile.clone(),
+            ChaChaHistoryCipher::new([42; 32]),
+        )?;
+        assert_eq!(
+            get_all_entry_texts(&hist),
+            vec!["login --token=secret", "ls"]
+        );
+        drop(hist);
+
+        let last = contents.len() - 1;
+        contents[last] ^= 1;
+        std::fs::write(&histfile, contents).unwrap();
+        let res = FileBackedHistory::with_encrypted_file(
+            5,
+            histfile.clone(),
+            ChaChaHistoryCipher::new([42; 32]),
+        );
+        assert!(matches!(
+            res,
+            Err(ReedlineError(
+                ReedlineErrorVariants::HistoryDecryptionFailed(_)
+            ))
+        ));
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn erases_duplicates_from_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(5, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["ls", "cd", "ls", "pwd"]);
+        }
+        {
+            let mut writing_hist =
+                FileBackedHistory::with_file(5, histfile.clone())?.with_dedup(HistoryDedup::Erase);
+            assert_eq!(get_all_entry_texts(&writing_hist), vec!["cd", "ls", "pwd"]);
+            add_text_entries(&mut writing_hist, &["cd"]);
+        }
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        let actual = get_all_entry_texts(reading_hist.as_ref());
+        assert_eq!(actual, vec!["ls", "pwd", "cd"]);
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
     #[test]
     fn truncates_too_large_file() -> Result<()> {
         use tempfile::tempdir;
@@ -509,6 +768,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn deletes_entry_from_file() -> Result<()> {
+        use tempfile::tempdir;
+
+        let tmp = tempdir().unwrap();
+        let histfile = tmp.path().join(".history");
+
+        {
+            let (mut writing_hist, _) = create_history_at(5, &histfile);
+            add_text_entries(writing_hist.as_mut(), &["ls", "login --token=secret", "cd"]);
+        }
+        {
+            let (mut deleting_hist, _) = create_history_at(5, &histfile);
+            {
+                let (mut other_hist, _) = create_history_at(5, &histfile);
+                add_text_entries(other_hist.as_mut(), &["pwd"]);
+            }
+            deleting_hist.delete(HistoryItemId::new(1))?;
+            assert_eq!(
+                get_all_entry_texts(deleting_hist.as_ref()),
+                vec!["ls", "cd"]
+            );
+
+            // Gone from the file before the history is synced again
+            let contents = std::fs::read_to_string(&histfile).unwrap();
+            assert_eq!(contents, "ls\ncd\npwd\n");
+        }
+
+        let (reading_hist, _) = create_history_at(5, &histfile);
+        assert_eq!(
+            get_all_entry_texts(reading_hist.as_ref()),
+            vec!["ls", "cd", "pwd"]
+        );
+
+        tmp.close().unwrap();
+        Ok(())
+    }
+
     #[test]
     fn concurrent_histories_dont_erase_eachother() -> Result<()> {
         use tempfile::tempdir;
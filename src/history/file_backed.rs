@@ -1,5 +1,6 @@
 use super::{
-    base::CommandLineSearch, History, HistoryItem, HistoryItemId, SearchDirection, SearchQuery,
+    base::{compile_regex, CommandLineSearch},
+    History, HistoryItem, HistoryItemId, SearchDirection, SearchQuery, SessionFilter,
 };
 use crate::{
     result::{ReedlineError, ReedlineErrorVariants},
@@ -9,7 +10,7 @@ use crate::{
 use std::{
     collections::{HashSet, VecDeque},
     fs::OpenOptions,
-    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     ops::{Deref, DerefMut},
     path::PathBuf,
 };
@@ -17,6 +18,55 @@ use std::{
 /// Default size of the [`FileBackedHistory`] used when calling [`FileBackedHistory::default()`]
 pub const HISTORY_SIZE: usize = 1000;
 pub const NEWLINE_ESCAPE: &str = "<\\n>";
+/// Ending of the command lines truncated to the maximum length of a [`FileBackedHistory`]
+pub const TRUNCATION_MARKER: &str = "<...>";
+
+/// How a [`FileBackedHistory`] saves a command line it already holds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HistoryDedup {
+    /// Skip the command line if it repeats the most recent entry, like bash's `ignoredups`
+    #[default]
+    Consecutive,
+    /// Skip the command line if any entry holds it
+    Ignore,
+    /// Remove the entries holding the command line before appending it, like bash's
+    /// `erasedups`
+    Erase,
+}
+
+/// How a [`FileBackedHistory`] saves a command line longer than its maximum length
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LongCommandLine {
+    /// Don't save the command line
+    #[default]
+    Skip,
+    /// Save the start of the command line, ending with the [`TRUNCATION_MARKER`]
+    Truncate,
+}
+
+/// Authenticated cipher encrypting the history file of a [`FileBackedHistory`]
+///
+/// The `encrypted_history` feature provides [`ChaChaHistoryCipher`](crate::ChaChaHistoryCipher),
+/// keyed directly or from the user's passphrase. Without it the plaintext history pulls no
+/// crypto dependency.
+pub trait HistoryCipher: Send {
+    /// Encrypts the whole serialized history
+    fn encrypt(&self, plaintext: &[u8]) -> std::result::Result<Vec<u8>, String>;
+
+    /// Decrypts what [`HistoryCipher::encrypt`] returned.
+    ///
+    /// Must fail rather than return garbage if the key is wrong or the ciphertext was
+    /// tampered with.
+    fn decrypt(&self, ciphertext: &[u8]) -> std::result::Result<Vec<u8>, String>;
+}
+
+struct Cipher(Box<dyn HistoryCipher>);
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cipher")
+    }
+}
 
 /// Stateful history that allows up/down-arrow browsing with an internal cursor.
 ///
@@ -31,6 +81,11 @@ pub struct FileBackedHistory {
     file: Option<PathBuf>,
     len_on_disk: usize, // Keep track what was previously written to disk
     session: Option<HistorySessionId>,
+    dedup: HistoryDedup,
+    ignore_space: bool,
+    ignore_patterns: Vec<String>,
+    cipher: Option<Cipher>,
+    max_command_length: Option<(usize, LongCommandLine)>,
 }
 
 impl Default for FileBackedHistory {
@@ -49,6 +104,70 @@ impl Default for FileBackedHistory {
     }
 }
 
+/// Removes the entries repeating another one according to the `dedup` and returns how many
+/// of the first `len_on_disk` entries were removed
+fn remove_duplicates(
+    entries: &mut VecDeque<String>,
+    len_on_disk: usize,
+    dedup: HistoryDedup,
+) -> usize {
+    let mut seen = HashSet::new();
+    let keep: Vec<bool> = match dedup {
+        HistoryDedup::Consecutive => return 0,
+        // The oldest entry stays
+        HistoryDedup::Ignore => entries.iter().map(|e| seen.insert(e.clone())).collect(),
+        // The newest entry stays
+        HistoryDedup::Erase => {
+            let mut keep: Vec<bool> = entries
+                .iter()
+                .rev()
+                .map(|e| seen.insert(e.clone()))
+                .collect();
+            keep.reverse();
+            keep
+        }
+    };
+    let removed_on_disk = keep.iter().take(len_on_disk).filter(|k| !**k).count();
+    let mut keep = keep.into_iter();
+    entries.retain(|_| keep.next().unwrap_or(true));
+    removed_on_disk
+}
+
+/// Whether `text` matches the glob `pattern` entirely
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if the text following it doesn't match
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                t += 1;
+            }
+            Some(c) if *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the `*` swallow one more character
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 fn encode_entry(s: &str) -> String {
     s.replace('\n', NEWLINE_ESCAPE)
 }
@@ -58,17 +177,48 @@ fn decode_entry(s: &str) -> String {
 }
 
 impl History for FileBackedHistory {
-    /// only saves a value if it's different than the last value
+    /// only saves a value if it's different than the last value, or than all the values
+    /// depending on the [`HistoryDedup`]
     fn save(&mut self, h: HistoryItem) -> Result<HistoryItem> {
-        let entry = h.command_line;
-        // Don't append if the preceding value is identical or the string empty
-        let entry_id = if self
-            .entries
-            .back()
-            .map_or(true, |previous| previous != &entry)
-            && !entry.is_empty()
-            && self.capacity > 0
-        {
+        let mut entry = h.command_line;
+        // Ignored entries neither displace older entries nor count as duplicates
+        if self.is_ignored(&entry) {
+            return Ok(FileBackedHistory::construct_entry(None, entry));
+        }
+        match self.max_command_length {
+            Some((max_length, long_command_line)) if entry.len() > max_length => {
+                match long_command_line {
+                    LongCommandLine::Skip => {
+                        return Ok(FileBackedHistory::construct_entry(None, entry));
+                    }
+                    LongCommandLine::Truncate => {
+                        let mut end = max_length.saturating_sub(TRUNCATION_MARKER.len());
+                        while !entry.is_char_boundary(end) {
+                            end -= 1;
+                        }
+                        entry.truncate(end);
+                        entry.push_str(TRUNCATION_MARKER);
+                    }
+                }
+            }
+            _ => {}
+        }
+        let is_duplicate = match self.dedup {
+            HistoryDedup::Consecutive | HistoryDedup::Erase => self.entries.back() == Some(&entry),
+            HistoryDedup::Ignore => self.entries.contains(&entry),
+        };
+        if self.dedup == HistoryDedup::Erase && !is_duplicate && !entry.is_empty() {
+            // The entries already written stay in the file until the next sync rewrites it
+            let erased_on_disk = self
+                .entries
+                .range(..self.len_on_disk)
+                .filter(|e| **e == entry)
+                .count();
+            self.entries.retain(|e| *e != entry);
+            self.len_on_disk -= erased_on_disk;
+        }
+        // Don't append if the value is a duplicate or the string empty
+        let entry_id = if !is_duplicate && !entry.is_empty() && self.capacity > 0 {
             if self.entries.len() == self.capacity {
                 // History is "full", so we delete the oldest entry first,
                 // before adding a new one.
@@ -101,11 +251,12 @@ impl History for FileBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
-        if query.start_time.is_some() || query.end_time.is_some() {
+        if query.filter.session.is_some() && query.filter.session_filter != SessionFilter::All {
+            // The entries don't record the session they were run in
             return Err(ReedlineError(
                 ReedlineErrorVariants::HistoryFeatureUnsupported {
                     history: "FileBackedHistory",
-                    feature: "filtering by time",
+                    feature: "filtering by session",
                 },
             ));
         }
@@ -113,14 +264,13 @@ impl History for FileBackedHistory {
         if query.filter.hostname.is_some()
             || query.filter.cwd_exact.is_some()
             || query.filter.cwd_prefix.is_some()
-            || query.filter.exit_successful.is_some()
+            || query.start_time.is_some()
+            || query.end_time.is_some()
+            || (query.filter.exit_successful.is_some() && !query.filter.keep_unknown_exit_status)
         {
-            return Err(ReedlineError(
-                ReedlineErrorVariants::HistoryFeatureUnsupported {
-                    history: "FileBackedHistory",
-                    feature: "filtering by extra info",
-                },
-            ));
+            // The entries don't record the host and directory they were run in, their start
+            // time nor their exit status, so none of them is known to match such a filter
+            return Ok(vec![]);
         }
         let (min_id, max_id) = {
             let start = query.start_id.map(|e| e.0);
@@ -146,22 +296,68 @@ impl History for FileBackedHistory {
         } else {
             intrinsic_limit as usize
         };
+        // Only ASCII case is folded, like the `like` operator of the sqlite backend does
+        let fold = |s: &str| -> String {
+            if query.filter.case_insensitive {
+                s.to_ascii_lowercase()
+            } else {
+                s.to_string()
+            }
+        };
+        let command_line = query
+            .filter
+            .command_line
+            .as_ref()
+            .map(|search| match search {
+                CommandLineSearch::Prefix(p) => CommandLineSearch::Prefix(fold(p)),
+                CommandLineSearch::Substring(p) => CommandLineSearch::Substring(fold(p)),
+                CommandLineSearch::Exact(p) => CommandLineSearch::Exact(fold(p)),
+                CommandLineSearch::AllTokens(tokens) => {
+                    CommandLineSearch::AllTokens(tokens.iter().map(|t| fold(t)).collect())
+                }
+                CommandLineSearch::Regex(p) => CommandLineSearch::Regex(p.clone()),
+            });
+        let regex = match &command_line {
+            Some(CommandLineSearch::Regex(p)) => {
+                Some(compile_regex(p, query.filter.case_insensitive)?)
+            }
+            _ => None,
+        };
+        let excluded_substrings = query
+            .filter
+            .excluded_substrings
+            .iter()
+            .map(|s| fold(s))
+            .collect::<Vec<_>>();
         let mut seen_matching_command_lines = HashSet::new();
         let filter = |(idx, cmd): (usize, &String)| {
-            if !match &query.filter.command_line {
-                Some(CommandLineSearch::Prefix(p)) => cmd.starts_with(p),
-                Some(CommandLineSearch::Substring(p)) => cmd.contains(p),
-                Some(CommandLineSearch::Exact(p)) => cmd == p,
+            if !match &command_line {
+                Some(CommandLineSearch::Prefix(p)) => fold(cmd).starts_with(p),
+                Some(CommandLineSearch::Substring(p)) => fold(cmd).contains(p),
+                Some(CommandLineSearch::Exact(p)) => &fold(cmd) == p,
+                Some(CommandLineSearch::AllTokens(tokens)) => {
+                    let cmd = fold(cmd);
+                    tokens.iter().all(|t| cmd.contains(t))
+                }
+                Some(CommandLineSearch::Regex(_)) => matches!(&regex, Some(r) if r.is_match(cmd)),
                 None => true,
             } {
                 return None;
             }
+            if !excluded_substrings.is_empty() {
+                let cmd = fold(cmd);
+                if excluded_substrings.iter().any(|s| cmd.contains(s)) {
+                    return None;
+                }
+            }
             if let Some(str) = &query.filter.not_command_line {
                 if cmd == str {
                     return None;
                 }
             }
-            if !seen_matching_command_lines.insert(cmd.clone()) {
+            if (query.filter.distinct || !query.filter.keep_duplicates)
+                && !seen_matching_command_lines.insert(cmd.clone())
+            {
                 return None;
             }
             Some(FileBackedHistory::construct_entry(
@@ -183,6 +379,20 @@ impl History for FileBackedHistory {
         }
     }
 
+    /// Streams the entries kept in memory, which hold the whole file once it is loaded
+    fn iter(
+        &self,
+        direction: SearchDirection,
+    ) -> Result<Box<dyn Iterator<Item = HistoryItem> + '_>> {
+        let entries = self.entries.iter().enumerate().map(|(idx, cmd)| {
+            FileBackedHistory::construct_entry(Some(HistoryItemId::new(idx as i64)), cmd.clone())
+        });
+        Ok(match direction {
+            SearchDirection::Forward => Box::new(entries),
+            SearchDirection::Backward => Box::new(entries.rev()),
+        })
+    }
+
     fn update(
         &mut self,
         _id: super::HistoryItemId,
@@ -201,21 +411,33 @@ impl History for FileBackedHistory {
         self.len_on_disk = 0;
 
         if let Some(file) = &self.file {
-            if let Err(err) = std::fs::remove_file(file) {
-                return Err(ReedlineError(ReedlineErrorVariants::IOError(err)));
+            match std::fs::remove_file(file) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(ReedlineError(ReedlineErrorVariants::IOError(err)));
+                }
+                _ => {}
             }
         }
 
         Ok(())
     }
 
-    fn delete(&mut self, _h: super::HistoryItemId) -> Result<()> {
-        Err(ReedlineError(
-            ReedlineErrorVariants::HistoryFeatureUnsupported {
-                history: "FileBackedHistory",
-                feature: "removing entries",
-            },
-        ))
+    /// Removes the entry from the file right away, keeping the entries other sessions wrote
+    /// to it since. The ids of the later entries move down by one.
+    fn delete(&mut self, h: HistoryItemId) -> Result<()> {
+        let idx = match usize::try_from(h.0) {
+            Ok(idx) if idx < self.entries.len() => idx,
+            _ => return Ok(()),
+        };
+        let deleted = self.entries.remove(idx).expect("checked to exist");
+        if idx < self.len_on_disk {
+            self.len_on_disk -= 1;
+            if let Some(file) = &self.file {
+                self.remove_from_file(file, idx, &deleted)
+                    .map_err(|err| ReedlineError(ReedlineErrorVariants::IOError(err)))?;
+            }
+        }
+        Ok(())
     }
 
     /// Writes unwritten history contents to disk.
@@ -238,6 +460,19 @@ impl History for FileBackedHistory {
                     .open(fname)?,
             );
             let mut writer_guard = f_lock.write()?;
+            if self.dedup != HistoryDedup::Consecutive || self.cipher.is_some() {
+                // The duplicates may be anywhere in the file, and a ciphertext can't be
+                // appended to, so it is rewritten entirely
+                let mut entries = self.read_entries(writer_guard.deref_mut())?;
+                entries.extend(own_entries.cloned());
+                remove_duplicates(&mut entries, 0, self.dedup);
+                entries.drain(..entries.len().saturating_sub(self.capacity));
+                self.write_entries(writer_guard.deref_mut(), &entries)?;
+
+                self.len_on_disk = entries.len();
+                self.entries = entries;
+                return Ok(());
+            }
             let (mut foreign_entries, truncate) = {
                 let reader = BufReader::new(writer_guard.deref());
                 let mut from_file = reader
@@ -295,6 +530,69 @@ impl History for FileBackedHistory {
 }
 
 impl FileBackedHistory {
+    /// Reads the entries of the locked history `file`, decrypting them if needed
+    fn read_entries(&self, file: &mut std::fs::File) -> std::io::Result<VecDeque<String>> {
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        if let (Some(Cipher(cipher)), false) = (&self.cipher, contents.is_empty()) {
+            contents = cipher.decrypt(&contents).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    ReedlineError(ReedlineErrorVariants::HistoryDecryptionFailed(e)),
+                )
+            })?;
+        }
+        contents
+            .as_slice()
+            .lines()
+            .map(|o| o.map(|i| decode_entry(&i)))
+            .collect()
+    }
+
+    /// Replaces the content of the locked history `file` with the `entries`, encrypting them
+    /// if needed
+    fn write_entries(
+        &self,
+        file: &mut std::fs::File,
+        entries: &VecDeque<String>,
+    ) -> std::io::Result<()> {
+        let mut contents = Vec::new();
+        for line in entries {
+            contents.extend_from_slice(encode_entry(line).as_bytes());
+            contents.push(b'\n');
+        }
+        if let Some(Cipher(cipher)) = &self.cipher {
+            contents = cipher
+                .encrypt(&contents)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        }
+        file.rewind()?;
+        file.write_all(&contents)?;
+        file.set_len(contents.len() as u64)
+    }
+
+    /// Rewrites the history file without the `deleted` entry found at `idx` when last synced,
+    /// or without its latest copy if another session has since truncated the file
+    fn remove_from_file(&self, fname: &PathBuf, idx: usize, deleted: &str) -> std::io::Result<()> {
+        let file = match OpenOptions::new().write(true).read(true).open(fname) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            file => file?,
+        };
+        let mut f_lock = fd_lock::RwLock::new(file);
+        let mut writer_guard = f_lock.write()?;
+        let mut entries = self.read_entries(writer_guard.deref_mut())?;
+        let position = if entries.get(idx).map(String::as_str) == Some(deleted) {
+            Some(idx)
+        } else {
+            entries.iter().rposition(|entry| entry == deleted)
+        };
+        if let Some(position) = position {
+            entries.remove(position);
+            self.write_entries(writer_guard.deref_mut(), &entries)?;
+        }
+        Ok(())
+    }
+
     /// Creates a new in-memory history that remembers `n <= capacity` elements
     ///
     pub fn new(capacity: usize) -> Result<Self> {
@@ -310,9 +608,86 @@ impl FileBackedHistory {
             file: None,
             len_on_disk: 0,
             session: None,
+            dedup: HistoryDedup::Consecutive,
+            ignore_space: false,
+            ignore_patterns: Vec::new(),
+            cipher: None,
+            max_command_length: None,
         })
     }
 
+    /// Changes the most entries the history remembers.
+    ///
+    /// Shrinking it below the current count drops the oldest entries, also from the history
+    /// file if there is one. Growing it doesn't bring back the entries dropped before.
+    pub fn set_capacity(&mut self, capacity: usize) -> Result<()> {
+        if capacity == usize::MAX {
+            return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "History capacity too large to be addressed safely",
+            )));
+        }
+        self.capacity = capacity;
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+        self.len_on_disk = self.len_on_disk.saturating_sub(excess);
+        // Trims the file right away, a later sync would otherwise read the dropped entries
+        // back once the capacity grows again
+        self.sync()?;
+        Ok(())
+    }
+
+    /// Builder skipping the command lines starting with a space, like bash's `ignorespace`
+    #[must_use]
+    pub fn with_ignore_space(mut self, ignore_space: bool) -> Self {
+        self.ignore_space = ignore_space;
+        self
+    }
+
+    /// Builder skipping the command lines matching any of the glob `patterns`, like bash's
+    /// `HISTIGNORE`.
+    ///
+    /// A pattern matches the whole command line, `*` standing for any text and `?` for any
+    /// single character.
+    #[must_use]
+    pub fn with_ignore_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.ignore_patterns = patterns;
+        self
+    }
+
+    /// Builder limiting the length in bytes of the command lines the history saves, the
+    /// `long_command_line` telling what becomes of the longer ones.
+    ///
+    /// A truncated command line ends with the [`TRUNCATION_MARKER`] and is at most
+    /// `max_length` bytes long, unless that is shorter than the marker.
+    #[must_use]
+    pub fn with_max_command_length(
+        mut self,
+        max_length: usize,
+        long_command_line: LongCommandLine,
+    ) -> Self {
+        self.max_command_length = Some((max_length, long_command_line));
+        self
+    }
+
+    fn is_ignored(&self, command_line: &str) -> bool {
+        (self.ignore_space && command_line.starts_with(' '))
+            || self
+                .ignore_patterns
+                .iter()
+                .any(|pattern| glob_match(pattern, command_line))
+    }
+
+    /// Builder setting how the history saves the command lines it already holds.
+    ///
+    /// The duplicates among the current entries are removed right away, and those in the
+    /// history file on the next [`History::sync`]
+    #[must_use]
+    pub fn with_dedup(mut self, dedup: HistoryDedup) -> Self {
+        self.dedup = dedup;
+        self.len_on_disk -= remove_duplicates(&mut self.entries, self.len_on_disk, dedup);
+        self
+    }
+
     /// Creates a new history with an associated history file.
     ///
     /// History file format: commands separated by new lines.
@@ -322,7 +697,32 @@ impl FileBackedHistory {
     /// **Side effects:** creates all nested directories to the file
     ///
     pub fn with_file(capacity: usize, file: PathBuf) -> Result<Self> {
+        Self::with_file_and_cipher(capacity, file, None)
+    }
+
+    /// Creates a new history with an associated history file encrypted by the `cipher`.
+    ///
+    /// The file holds the commands like with [`FileBackedHistory::with_file()`], encrypted
+    /// as a whole. If the file can't be decrypted, e.g. because the key is wrong, fails with
+    /// [`ReedlineErrorVariants::HistoryDecryptionFailed`].
+    ///
+    /// **Side effects:** creates all nested directories to the file
+    ///
+    pub fn with_encrypted_file(
+        capacity: usize,
+        file: PathBuf,
+        cipher: impl HistoryCipher + 'static,
+    ) -> Result<Self> {
+        Self::with_file_and_cipher(capacity, file, Some(Cipher(Box::new(cipher))))
+    }
+
+    fn with_file_and_cipher(
+        capacity: usize,
+        file: PathBuf,
+        cipher: Option<Cipher>,
+    ) -> Result<Self> {
         let mut hist = Self::new(capacity)?;
+        hist.cipher = cipher;
         if let Some(base_dir) = file.parent() {
             std::fs::create_dir_all(base_dir)?;
         }
@@ -0,0 +1,403 @@
+use super::{History, HistoryItem, SearchDirection, SearchQuery};
+use crate::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// Marks a byte of a zsh history file escaped by xoring it with 32
+const ZSH_META: u8 = 0x83;
+
+/// Outcome of importing a shell history with [`import_from_bash`] or [`import_from_zsh`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Entries passed on to the [`History`]
+    pub imported: usize,
+    /// Malformed lines that were skipped
+    pub skipped: usize,
+}
+
+/// Imports the bash history `file` into the `history`, oldest entries first.
+///
+/// The timestamps bash writes as `#<seconds>` lines when `HISTTIMEFORMAT` is set are kept if
+/// the `history` records them. Lines that aren't valid UTF-8 are skipped.
+pub fn import_from_bash(history: &mut dyn History, file: &Path) -> Result<ImportSummary> {
+    let (items, skipped) = parse_bash(&std::fs::read(file)?);
+    save_all(history, items, skipped)
+}
+
+/// Imports the zsh history `file` into the `history`, oldest entries first.
+///
+/// The start time and duration of the `: <start>:<duration>;<command>` lines of the
+/// `EXTENDED_HISTORY` format are kept if the `history` records them. Lines that aren't valid
+/// UTF-8 or don't follow that format while starting with `: ` are skipped.
+pub fn import_from_zsh(history: &mut dyn History, file: &Path) -> Result<ImportSummary> {
+    let (items, skipped) = parse_zsh(&std::fs::read(file)?);
+    save_all(history, items, skipped)
+}
+
+fn save_all(
+    history: &mut dyn History,
+    items: Vec<HistoryItem>,
+    skipped: usize,
+) -> Result<ImportSummary> {
+    let imported = items.len();
+    for item in items {
+        history.save(item)?;
+    }
+    history.sync()?;
+    Ok(ImportSummary { imported, skipped })
+}
+
+/// Writes the items of the `history` as JSON lines, see [`History::export`]
+pub(crate) fn export_jsonl<H: History + ?Sized>(
+    history: &H,
+    writer: &mut dyn Write,
+) -> Result<usize> {
+    let mut written = 0;
+    for item in history.iter(SearchDirection::Forward)? {
+        serde_json::to_writer(&mut *writer, &item).map_err(std::io::Error::from)?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+    writer.flush()?;
+    Ok(written)
+}
+
+/// Saves the items of JSON lines into the `history`, see [`History::import`]
+pub(crate) fn import_jsonl<H: History + ?Sized>(
+    history: &mut H,
+    reader: &mut dyn BufRead,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<HistoryItem>(&line) {
+            Ok(item) => {
+                // The ids of the exporting history mean nothing to this one
+                history.save(HistoryItem { id: None, ..item })?;
+                summary.imported += 1;
+            }
+            Err(_) => summary.skipped += 1,
+        }
+    }
+    history.sync()?;
+    Ok(summary)
+}
+
+/// Saves the items of `source` missing from `history`, see [`History::merge`]
+pub(crate) fn merge<H: History + ?Sized>(history: &mut H, source: &dyn History) -> Result<usize> {
+    let all = || {
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.keep_duplicates = true;
+        query
+    };
+    let mut start_times: HashMap<String, Vec<Option<DateTime<Utc>>>> = HashMap::new();
+    for item in history.search(all())? {
+        start_times
+            .entry(item.command_line)
+            .or_default()
+            .push(item.start_timestamp);
+    }
+    let mut missing = Vec::new();
+    for item in source.search(all())? {
+        let times = start_times.entry(item.command_line.clone()).or_default();
+        let known = times.iter().any(|time| {
+            time.is_none() || item.start_timestamp.is_none() || *time == item.start_timestamp
+        });
+        if !known {
+            // Also keeps the duplicates within the source out
+            times.push(item.start_timestamp);
+            missing.push(HistoryItem { id: None, ..item });
+        }
+    }
+    let merged = missing.len();
+    // Never rebuilds the history, which could lose it halfway and would change the ids of the
+    // existing items
+    for item in missing {
+        history.save(item)?;
+    }
+    history.sync()?;
+    Ok(merged)
+}
+
+/// The entries of a bash history and the number of malformed lines
+fn parse_bash(content: &[u8]) -> (Vec<HistoryItem>, usize) {
+    let mut items = Vec::new();
+    let mut skipped = 0;
+    let mut timestamp = None;
+    for line in content.split(|b| *b == b'\n') {
+        let line = match std::str::from_utf8(line) {
+            Ok(line) => line.trim_end_matches('\r'),
+            Err(_) => {
+                skipped += 1;
+                timestamp = None;
+                continue;
+            }
+        };
+        if let Some(secs) = line
+            .strip_prefix('#')
+            .and_then(|secs| secs.parse::<i64>().ok())
+        {
+            timestamp = Utc.timestamp_opt(secs, 0).single();
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        items.push(HistoryItem {
+            start_timestamp: timestamp.take(),
+            ..HistoryItem::from_command_line(line)
+        });
+    }
+    (items, skipped)
+}
+
+/// The entries of a zsh history and the number of malformed lines
+fn parse_zsh(content: &[u8]) -> (Vec<HistoryItem>, usize) {
+    let content = unmetafy(content);
+    let mut items = Vec::new();
+    let mut skipped = 0;
+    let mut lines = content.split(|b| *b == b'\n');
+    while let Some(line) = lines.next() {
+        // A command spanning several lines ends all but its last one with a backslash
+        let mut entry = line.to_vec();
+        while entry.ends_with(b"\\") {
+            match lines.next() {
+                Some(next) => {
+                    entry.pop();
+                    entry.push(b'\n');
+                    entry.extend_from_slice(next);
+                }
+                None => break,
+            }
+        }
+        let entry = match String::from_utf8(entry) {
+            Ok(entry) => entry,
+            Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let item = match entry.strip_prefix(": ") {
+            Some(extended) => match parse_zsh_extended(extended) {
+                Some(item) => item,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            },
+            None if entry.is_empty() => continue,
+            None => HistoryItem::from_command_line(entry),
+        };
+        items.push(item);
+    }
+    (items, skipped)
+}
+
+/// Parses the `<start>:<duration>;<command>` following the `: ` of an extended zsh entry
+fn parse_zsh_extended(extended: &str) -> Option<HistoryItem> {
+    let (times, command_line) = extended.split_once(';')?;
+    let (start, duration) = times.split_once(':')?;
+    let start = Utc.timestamp_opt(start.trim().parse().ok()?, 0).single()?;
+    let duration = Duration::from_secs(duration.trim().parse().ok()?);
+    if command_line.is_empty() {
+        return None;
+    }
+    Some(HistoryItem {
+        start_timestamp: Some(start),
+        duration: Some(duration),
+        ..HistoryItem::from_command_line(command_line)
+    })
+}
+
+/// Undoes the escaping zsh applies to the bytes of its history file
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut bytes = content.iter();
+    let mut unescaped = Vec::with_capacity(content.len());
+    while let Some(b) = bytes.next() {
+        if *b == ZSH_META {
+            if let Some(escaped) = bytes.next() {
+                unescaped.push(escaped ^ 32);
+            }
+        } else {
+            unescaped.push(*b);
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileBackedHistory, SearchDirection, SearchQuery};
+    use pretty_assertions::assert_eq;
+
+    fn command_lines(items: &[HistoryItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item.command_line.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn parses_bash_history() {
+        let (items, skipped) = parse_bash(b"ls\n#1700000000\ncd /tmp\n\xff\xfe\n# comment\n");
+
+        assert_eq!(command_lines(&items), vec!["ls", "cd /tmp", "# comment"]);
+        assert_eq!(items[0].start_timestamp, None);
+        assert_eq!(
+            items[1].start_timestamp,
+            Utc.timestamp_opt(1_700_000_000, 0).single()
+        );
+        assert_eq!(items[2].start_timestamp, None);
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn parses_zsh_history() {
+        let (items, skipped) = parse_zsh(
+            b": 1700000000:3;make\nls\n: 1700000010:0;echo a\\\nb\n: broken\n: 1700000020:0;caf\xc3\x83\x89\n",
+        );
+
+        assert_eq!(
+            command_lines(&items),
+            vec!["make", "ls", "echo a\nb", "caf\u{e9}"]
+        );
+        assert_eq!(
+            items[0].start_timestamp,
+            Utc.timestamp_opt(1_700_000_000, 0).single()
+        );
+        assert_eq!(items[0].duration, Some(Duration::from_secs(3)));
+        assert_eq!(items[1].start_timestamp, None);
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn imports_in_order() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join(".bash_history");
+        std::fs::write(&file, "ls\ncd /tmp\nls\n").unwrap();
+        let mut history = FileBackedHistory::default();
+
+        let summary = import_from_bash(&mut history, &file)?;
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 3,
+                skipped: 0
+            }
+        );
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.keep_duplicates = true;
+        let items = history.search(query)?;
+        assert_eq!(command_lines(&items), vec!["ls", "cd /tmp", "ls"]);
+        Ok(())
+    }
+
+    #[test]
+    fn exports_and_imports_jsonl() -> Result<()> {
+        let mut source = FileBackedHistory::default();
+        source.save(HistoryItem::from_command_line("ls"))?;
+        source.save(HistoryItem::from_command_line("cd /tmp"))?;
+        let mut exported = Vec::new();
+
+        assert_eq!(source.export(&mut exported)?, 2);
+        let exported = String::from_utf8(exported).unwrap();
+        assert_eq!(
+            exported,
+            "{\"id\":0,\"command_line\":\"ls\"}\n{\"id\":1,\"command_line\":\"cd /tmp\"}\n"
+        );
+
+        let mut target = FileBackedHistory::default();
+        target.save(HistoryItem::from_command_line("pwd"))?;
+        let summary = target.import(&mut format!("{exported}not json\n").as_bytes())?;
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                skipped: 1
+            }
+        );
+        let items = target.search(SearchQuery::everything(SearchDirection::Forward, None))?;
+        assert_eq!(command_lines(&items), vec!["pwd", "ls", "cd /tmp"]);
+        Ok(())
+    }
+
+    #[test]
+    fn jsonl_round_trips_metadata() {
+        let item = HistoryItem {
+            start_timestamp: Utc.timestamp_opt(1_700_000_000, 0).single(),
+            cwd: Some("/tmp".to_string()),
+            exit_status: Some(1),
+            duration: Some(Duration::from_millis(1500)),
+            ..HistoryItem::from_command_line("make")
+        };
+
+        let json = serde_json::to_string(&item).unwrap();
+        assert!(!json.contains("null"));
+        assert!(!json.contains("hostname"));
+        assert_eq!(serde_json::from_str::<HistoryItem>(&json).unwrap(), item);
+    }
+
+    fn all_command_lines(history: &dyn History) -> Vec<String> {
+        let mut query = SearchQuery::everything(SearchDirection::Forward, None);
+        query.filter.keep_duplicates = true;
+        history
+            .search(query)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.command_line)
+            .collect()
+    }
+
+    #[test]
+    fn merges_missing_entries_once() -> Result<()> {
+        let mut source = FileBackedHistory::default();
+        for cmd in ["b", "c", "d"] {
+            source.save(HistoryItem::from_command_line(cmd))?;
+        }
+        let mut history = FileBackedHistory::new(3)?;
+        for cmd in ["a", "b"] {
+            history.save(HistoryItem::from_command_line(cmd))?;
+        }
+
+        assert_eq!(history.merge(&source)?, 2);
+        assert_eq!(all_command_lines(&history), vec!["b", "c", "d"]);
+        assert_eq!(history.merge(&source)?, 0);
+        assert_eq!(all_command_lines(&history), vec!["b", "c", "d"]);
+        Ok(())
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
+    #[test]
+    fn merges_after_the_existing_entries() -> Result<()> {
+        let at = |secs, cmd| HistoryItem {
+            start_timestamp: Utc.timestamp_opt(secs, 0).single(),
+            ..HistoryItem::from_command_line(cmd)
+        };
+        let mut source = crate::SqliteBackedHistory::in_memory()?;
+        for item in [at(10, "ls"), at(20, "cd"), at(40, "ls")] {
+            source.save(item)?;
+        }
+        let mut history = crate::SqliteBackedHistory::in_memory()?;
+        let mut ids = Vec::new();
+        for item in [at(10, "ls"), at(30, "pwd")] {
+            ids.push(history.save(item)?.id.unwrap());
+        }
+
+        assert_eq!(history.merge(&source)?, 2);
+        assert_eq!(all_command_lines(&history), vec!["ls", "pwd", "cd", "ls"]);
+        assert_eq!(history.load(ids[1])?.command_line, "pwd");
+        assert_eq!(history.merge(&source)?, 0);
+        assert_eq!(history.count_all()?, 4);
+        Ok(())
+    }
+}
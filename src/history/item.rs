@@ -81,27 +81,38 @@ impl HistoryItemExtraInfo for IgnoreAllExtraInfo {}
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HistoryItem<ExtraInfo: HistoryItemExtraInfo = IgnoreAllExtraInfo> {
     /// primary key, unique across one history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<HistoryItemId>,
     /// date-time when this command was started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub start_timestamp: Option<chrono::DateTime<Utc>>,
     /// the full command line as text
     pub command_line: String,
     /// a unique id for one shell session.
     /// used so the history can be filtered to a single session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub session_id: Option<HistorySessionId>,
     /// the hostname the commands were run in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hostname: Option<String>,
     /// the current working directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cwd: Option<String>,
     /// the duration the command took to complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub duration: Option<Duration>,
     /// the exit status of the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub exit_status: Option<i64>,
     /// arbitrary additional information that might be interesting
     /// NOTE: this attribute is required because of
     /// <https://github.com/rust-lang/rust/issues/41617>
     ///       (see <https://github.com/serde-rs/serde/issues/1296#issuecomment-394056188> for the fix)
-    #[serde(deserialize_with = "Option::<ExtraInfo>::deserialize")]
+    #[serde(
+        default,
+        deserialize_with = "Option::<ExtraInfo>::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub more_info: Option<ExtraInfo>,
 }
 
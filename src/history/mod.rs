@@ -1,16 +1,29 @@
+mod background;
 mod base;
+#[cfg(feature = "encrypted_history")]
+mod cipher;
 mod cursor;
 mod file_backed;
+mod import;
 mod item;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 mod sqlite_backed;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 pub use sqlite_backed::SqliteBackedHistory;
 
+#[cfg(feature = "encrypted_history")]
+pub use cipher::ChaChaHistoryCipher;
+
+pub use background::BackgroundHistory;
 pub use base::{
     CommandLineSearch, History, HistoryNavigationQuery, SearchDirection, SearchFilter, SearchQuery,
+    SessionFilter,
 };
 pub use cursor::HistoryCursor;
+pub use import::{import_from_bash, import_from_zsh, ImportSummary};
 pub use item::{HistoryItem, HistoryItemId, HistorySessionId};
 
-pub use file_backed::{FileBackedHistory, HISTORY_SIZE};
+pub use file_backed::{
+    FileBackedHistory, HistoryCipher, HistoryDedup, LongCommandLine, HISTORY_SIZE,
+    TRUNCATION_MARKER,
+};
@@ -1,5 +1,5 @@
 use super::{
-    base::{CommandLineSearch, SearchDirection, SearchQuery},
+    base::{compile_regex, CommandLineSearch, SearchDirection, SearchQuery, SessionFilter},
     History, HistoryItem, HistoryItemId, HistorySessionId,
 };
 use crate::{
@@ -7,8 +7,9 @@ use crate::{
     Result,
 };
 use chrono::{TimeZone, Utc};
-use rusqlite::{named_params, params, Connection, ToSql};
-use std::{path::PathBuf, time::Duration};
+use regex::Regex;
+use rusqlite::{functions::FunctionFlags, named_params, params, Connection, ToSql};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 const SQLITE_APPLICATION_ID: i32 = 1151497937;
 
 /// A history that stores the values to an SQLite database.
@@ -108,6 +109,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn count(&self, query: SearchQuery) -> Result<i64> {
+        check_regex(&query)?;
         let (query, params) = self.construct_query(&query, "coalesce(count(*), 0)");
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let result: i64 = self
@@ -120,6 +122,7 @@ impl History for SqliteBackedHistory {
     }
 
     fn search(&self, query: SearchQuery) -> Result<Vec<HistoryItem>> {
+        check_regex(&query)?;
         let (query, params) = self.construct_query(&query, "*");
         let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
         let results: Vec<HistoryItem> = self
@@ -144,6 +147,34 @@ impl History for SqliteBackedHistory {
         Ok(())
     }
 
+    fn update_outcome(
+        &mut self,
+        id: HistoryItemId,
+        duration: Option<Duration>,
+        exit_status: Option<i64>,
+    ) -> Result<()> {
+        let updated = self
+            .db
+            .execute(
+                "update history set
+                    duration_ms = coalesce(:duration_ms, duration_ms),
+                    exit_status = coalesce(:exit_status, exit_status)
+                where id = :id",
+                named_params! {
+                    ":id": id.0,
+                    ":duration_ms": duration.map(|e| e.as_millis() as i64),
+                    ":exit_status": exit_status,
+                },
+            )
+            .map_err(map_sqlite_err)?;
+        if updated == 0 {
+            return Err(ReedlineError(ReedlineErrorVariants::OtherHistoryError(
+                "Item does not exist",
+            )));
+        }
+        Ok(())
+    }
+
     fn clear(&mut self) -> Result<()> {
         self.db
             .execute("delete from history", params![])
@@ -159,15 +190,10 @@ impl History for SqliteBackedHistory {
     }
 
     fn delete(&mut self, h: HistoryItemId) -> Result<()> {
-        let changed = self
-            .db
+        // Deleting an item that doesn't exist (anymore) leaves nothing to do
+        self.db
             .execute("delete from history where id = ?", params![h.0])
             .map_err(map_sqlite_err)?;
-        if changed == 0 {
-            return Err(ReedlineError(ReedlineErrorVariants::HistoryDatabaseError(
-                "Could not find item".to_string(),
-            )));
-        }
         Ok(())
     }
 
@@ -179,6 +205,36 @@ impl History for SqliteBackedHistory {
     fn session(&self) -> Option<HistorySessionId> {
         self.session
     }
+
+    fn most_frequent(&self, mut query: SearchQuery) -> Result<Vec<(String, i64)>> {
+        check_regex(&query)?;
+        let limit = query.limit.take();
+        let (matching, mut params) = self.construct_query(&query, "command_line, id");
+        let limit = match limit {
+            Some(l) => {
+                params.push((":most_frequent_limit", Box::new(l)));
+                "limit :most_frequent_limit"
+            }
+            None => "",
+        };
+        let query = format!(
+            "SELECT command_line, count(*) AS runs \
+             FROM ({matching}) \
+             GROUP BY command_line \
+             ORDER BY runs DESC, max(id) DESC \
+             {limit}"
+        );
+        let params_borrow: Vec<(&str, &dyn ToSql)> = params.iter().map(|e| (e.0, &*e.1)).collect();
+        let results = self
+            .db
+            .prepare(&query)
+            .unwrap()
+            .query_map(&params_borrow[..], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(map_sqlite_err)?
+            .collect::<rusqlite::Result<Vec<(String, i64)>>>()
+            .map_err(map_sqlite_err)?;
+        Ok(results)
+    }
 }
 fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     // TODO: better error mapping
@@ -187,6 +243,24 @@ fn map_sqlite_err(err: rusqlite::Error) -> ReedlineError {
     )))
 }
 
+/// Escape the wildcards of a `like` pattern using a backslash as escape character
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Fails on an invalid [`CommandLineSearch::Regex`] before sqlite gets to report it as an
+/// opaque database error
+fn check_regex(query: &SearchQuery) -> Result<()> {
+    if let Some(CommandLineSearch::Regex(pattern)) = &query.filter.command_line {
+        compile_regex(pattern, query.filter.case_insensitive)?;
+    }
+    Ok(())
+}
+
 type BoxedNamedParams<'a> = Vec<(&'static str, Box<dyn ToSql + 'a>)>;
 
 impl SqliteBackedHistory {
@@ -233,6 +307,21 @@ impl SqliteBackedHistory {
             .map_err(map_sqlite_err)?;
         db.pragma_update(None, "application_id", SQLITE_APPLICATION_ID)
             .map_err(map_sqlite_err)?;
+        // Backs the `regexp` operator, which sqlite leaves undefined. The compiled pattern
+        // is cached for the whole statement
+        db.create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| {
+                let regex: Arc<Regex> =
+                    ctx.get_or_create_aux(0, |pattern| -> std::result::Result<_, BoxError> {
+                        Ok(Regex::new(pattern.as_str()?)?)
+                    })?;
+                Ok(regex.is_match(ctx.get_raw(1).as_str().unwrap_or_default()))
+            },
+        )
+        .map_err(map_sqlite_err)?;
         let db_version: i32 = db
             .query_row(
                 "SELECT user_version FROM pragma_user_version",
@@ -286,19 +375,20 @@ impl SqliteBackedHistory {
         };
         let mut wheres = Vec::new();
         let mut params: BoxedNamedParams = Vec::new();
+        // Entries without a start time never compare, so they are left out
         if let Some(start) = query.start_time {
             wheres.push(if is_asc {
-                "timestamp_start > :start_time"
+                "start_timestamp > :start_time"
             } else {
-                "timestamp_start < :start_time"
+                "start_timestamp < :start_time"
             });
             params.push((":start_time", Box::new(start.timestamp_millis())));
         }
         if let Some(end) = query.end_time {
             wheres.push(if is_asc {
-                ":end_time >= timestamp_start"
+                ":end_time >= start_timestamp"
             } else {
-                ":end_time <= timestamp_start"
+                ":end_time <= start_timestamp"
             });
             params.push((":end_time", Box::new(end.timestamp_millis())));
         }
@@ -326,16 +416,84 @@ impl SqliteBackedHistory {
             None => "",
         };
         if let Some(command_line) = &query.filter.command_line {
-            // TODO: escape %
-            let command_line_like = match command_line {
-                CommandLineSearch::Exact(e) => e.to_string(),
-                CommandLineSearch::Prefix(prefix) => format!("{prefix}%"),
-                CommandLineSearch::Substring(cont) => format!("%{cont}%"),
+            // `like` ignores ASCII case, the other comparisons respect it. The tokens are
+            // passed as a JSON array and all of them have to be found in the command line
+            let (clause, text) = match (command_line, query.filter.case_insensitive) {
+                (CommandLineSearch::Exact(e), true) => (
+                    "command_line like :command_line escape '\\'",
+                    escape_like(e),
+                ),
+                (CommandLineSearch::Prefix(prefix), true) => (
+                    "command_line like :command_line escape '\\'",
+                    format!("{}%", escape_like(prefix)),
+                ),
+                (CommandLineSearch::Substring(cont), true) => (
+                    "command_line like :command_line escape '\\'",
+                    format!("%{}%", escape_like(cont)),
+                ),
+                (CommandLineSearch::AllTokens(tokens), true) => (
+                    "not exists (select 1 from json_each(:command_line) \
+                     where command_line not like '%' || value || '%' escape '\\')",
+                    serde_json::to_string(
+                        &tokens.iter().map(|t| escape_like(t)).collect::<Vec<_>>(),
+                    )
+                    .unwrap(),
+                ),
+                (CommandLineSearch::Exact(e), false) => ("command_line = :command_line", e.clone()),
+                (CommandLineSearch::Prefix(prefix), false) => (
+                    "substr(command_line, 1, length(:command_line)) = :command_line",
+                    prefix.clone(),
+                ),
+                (CommandLineSearch::Substring(cont), false) => {
+                    ("instr(command_line, :command_line) > 0", cont.clone())
+                }
+                // The case is ignored by the pattern itself
+                (CommandLineSearch::Regex(pattern), case_insensitive) => (
+                    "command_line regexp :command_line",
+                    if case_insensitive {
+                        format!("(?i){pattern}")
+                    } else {
+                        pattern.clone()
+                    },
+                ),
+                (CommandLineSearch::AllTokens(tokens), false) => (
+                    "not exists (select 1 from json_each(:command_line) \
+                     where instr(command_line, value) = 0)",
+                    serde_json::to_string(tokens).unwrap(),
+                ),
             };
-            wheres.push("command_line like :command_line");
-            params.push((":command_line", Box::new(command_line_like)));
+            wheres.push(clause);
+            params.push((":command_line", Box::new(text)));
         }
 
+        if !query.filter.excluded_substrings.is_empty() {
+            // Passed as a JSON array like the tokens, none of them may be found
+            if query.filter.case_insensitive {
+                wheres.push(
+                    "not exists (select 1 from json_each(:excluded) \
+                     where command_line like '%' || value || '%' escape '\\')",
+                );
+                let excluded = query
+                    .filter
+                    .excluded_substrings
+                    .iter()
+                    .map(|s| escape_like(s))
+                    .collect::<Vec<_>>();
+                params.push((
+                    ":excluded",
+                    Box::new(serde_json::to_string(&excluded).unwrap()),
+                ));
+            } else {
+                wheres.push(
+                    "not exists (select 1 from json_each(:excluded) \
+                     where instr(command_line, value) > 0)",
+                );
+                params.push((
+                    ":excluded",
+                    Box::new(serde_json::to_string(&query.filter.excluded_substrings).unwrap()),
+                ));
+            }
+        }
         if let Some(str) = &query.filter.not_command_line {
             wheres.push("command_line != :not_cmd");
             params.push((":not_cmd", Box::new(str)));
@@ -349,20 +507,37 @@ impl SqliteBackedHistory {
             params.push((":cwd", Box::new(cwd_exact)));
         }
         if let Some(cwd_prefix) = &query.filter.cwd_prefix {
-            wheres.push("cwd like :cwd_like");
-            let cwd_like = format!("{cwd_prefix}%");
-            params.push((":cwd_like", Box::new(cwd_like)));
+            // Compared literally, as `like` would ignore the case and treat `_` and `%` in
+            // the path as wildcards. Entries without a directory never match
+            wheres.push("substr(cwd, 1, length(:cwd_prefix)) = :cwd_prefix");
+            params.push((":cwd_prefix", Box::new(cwd_prefix)));
         }
         if let Some(exit_successful) = query.filter.exit_successful {
-            if exit_successful {
-                wheres.push("exit_status = 0");
-            } else {
-                wheres.push("exit_status != 0");
+            wheres.push(
+                match (exit_successful, query.filter.keep_unknown_exit_status) {
+                    (true, false) => "exit_status = 0",
+                    (false, false) => "exit_status != 0",
+                    (true, true) => "(exit_status = 0 or exit_status is null)",
+                    (false, true) => "(exit_status != 0 or exit_status is null)",
+                },
+            );
+        }
+        match (query.filter.session, query.filter.session_filter) {
+            (Some(session_id), SessionFilter::Only) => {
+                wheres.push("session_id = :session_id");
+                params.push((":session_id", Box::new(session_id)));
             }
+            (Some(session_id), SessionFilter::Others) => {
+                wheres.push("(session_id is null or session_id != :session_id)");
+                params.push((":session_id", Box::new(session_id)));
+            }
+            _ => {}
         }
-        if let (Some(session_id), Some(session_timestamp)) =
-            (query.filter.session, self.session_timestamp)
-        {
+        if let (Some(session_id), Some(session_timestamp), SessionFilter::All) = (
+            query.filter.session,
+            self.session_timestamp,
+            query.filter.session_filter,
+        ) {
             // Filter so that we get rows:
             // - that have the same session_id, or
             // - were executed before our session started
@@ -377,10 +552,19 @@ impl SqliteBackedHistory {
         if wheres.is_empty() {
             wheres = "true".to_string();
         }
+        // Keep the first of the matching entries in the search direction for every command line
+        let distinct = if query.filter.distinct {
+            let first = if is_asc { "min(id)" } else { "max(id)" };
+            format!(
+                "and id in (select {first} from history where ({wheres}) group by command_line)"
+            )
+        } else {
+            String::new()
+        };
         let query = format!(
             "SELECT {select_expression} \
              FROM history \
-             WHERE ({wheres}) \
+             WHERE ({wheres}) {distinct} \
              ORDER BY id {asc} \
              {limit}"
         );
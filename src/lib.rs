@@ -178,6 +178,7 @@
 //! ## Crate features
 //!
 //! - `clipboard`: Enable support to use the `SystemClipboard`. Enabling this feature will return a `SystemClipboard` instead of a local clipboard when calling `get_default_clipboard()`.
+//! - `osc52_clipboard`: Copy to the clipboard of the terminal emulator with the OSC 52 escape sequence when running in an SSH session, where `SystemClipboard` can't reach the local display.
 //! - `bashisms`: Enable support for special text sequences that recall components from the history. e.g. `!!` and `!$`. For use in shells like `bash` or [`nushell`](https://nushell.sh).
 //! - `sqlite`: Provides the `SqliteBackedHistory` to store richer information in the history. Statically links the required sqlite version.
 //! - `sqlite-dynlib`: Alternative to the feature `sqlite`. Will not statically link. Requires `sqlite >= 3.38` to link dynamically!
@@ -228,9 +229,13 @@
 mod core_editor;
 pub use core_editor::Editor;
 pub use core_editor::LineBuffer;
+pub use core_editor::{CallbackClipboard, ClipboardError, ClipboardMode, PasteSanitizer};
 
 mod enums;
-pub use enums::{EditCommand, ReedlineEvent, ReedlineRawEvent, Signal, UndoBehavior};
+pub use enums::{
+    EditCommand, ReedlineEvent, ReedlineRawEvent, Signal, TextObject, TextObjectKind, UndoBehavior,
+    WordCharacters,
+};
 
 mod painting;
 pub use painting::{Painter, StyledText};
@@ -242,12 +247,15 @@ mod result;
 pub use result::{ReedlineError, ReedlineErrorVariants, Result};
 
 mod history;
+#[cfg(feature = "encrypted_history")]
+pub use history::ChaChaHistoryCipher;
 #[cfg(any(feature = "sqlite", feature = "sqlite-dynlib"))]
 pub use history::SqliteBackedHistory;
 pub use history::{
-    CommandLineSearch, FileBackedHistory, History, HistoryItem, HistoryItemId,
-    HistoryNavigationQuery, HistorySessionId, SearchDirection, SearchFilter, SearchQuery,
-    HISTORY_SIZE,
+    import_from_bash, import_from_zsh, BackgroundHistory, CommandLineSearch, FileBackedHistory,
+    History, HistoryCipher, HistoryDedup, HistoryItem, HistoryItemId, HistoryNavigationQuery,
+    HistorySessionId, ImportSummary, LongCommandLine, SearchDirection, SearchFilter, SearchQuery,
+    SessionFilter, HISTORY_SIZE, TRUNCATION_MARKER,
 };
 
 mod prompt;
@@ -259,14 +267,14 @@ pub use prompt::{
 mod edit_mode;
 pub use edit_mode::{
     default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings,
-    CursorConfig, EditMode, Emacs, Keybindings, Vi,
+    CursorConfig, EditMode, Emacs, Keybindings, Vi, ViCharSearch,
 };
 
 mod highlighter;
 pub use highlighter::{ExampleHighlighter, Highlighter, SimpleMatchHighlighter};
 
 mod completion;
-pub use completion::{Completer, DefaultCompleter, Span, Suggestion};
+pub use completion::{Completer, DefaultCompleter, HistoryMatch, Span, Suggestion};
 
 mod hinter;
 pub use hinter::CwdAwareHinter;
@@ -605,6 +605,10 @@ impl Menu for DescriptionMenu {
         &self.values
     }
 
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.get_value()
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
         let example_lines = self
             .examples
@@ -817,6 +817,10 @@ impl Menu for IdeMenu {
         &self.values
     }
 
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.get_value()
+    }
+
     fn menu_required_lines(&self, _terminal_columns: u16) -> u16 {
         self.get_rows()
     }
@@ -1381,6 +1385,9 @@ mod tests {
             extra: None,
             span: Span { start: 0, end: pos },
             append_whitespace: false,
+            accept: false,
+            match_indices: None,
+            history_match: None,
         }
     }
 
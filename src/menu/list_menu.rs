@@ -11,8 +11,6 @@ use {
     unicode_width::UnicodeWidthStr,
 };
 
-const SELECTION_CHAR: char = '!';
-
 struct Page {
     size: usize,
     full: bool,
@@ -256,6 +254,32 @@ impl ListMenu {
         }
     }
 
+    /// Highlights the characters of the line starting at the `match_indices`
+    fn highlight_matches(&self, line: &str, match_indices: &[usize], index: usize) -> String {
+        let match_style = if index == self.index() {
+            self.settings.color.selected_match_style
+        } else {
+            self.settings.color.match_style
+        };
+
+        line.char_indices()
+            .fold(String::new(), |mut out_string, (offset, c)| {
+                if match_indices.contains(&offset) {
+                    let _ = write!(
+                        out_string,
+                        "{}{}{}{}",
+                        match_style.prefix(),
+                        c,
+                        RESET,
+                        self.text_style(index)
+                    );
+                } else {
+                    out_string.push(c);
+                }
+                out_string
+            })
+    }
+
     /// Creates default string that represents one line from a menu
     fn create_string(
         &self,
@@ -353,7 +377,7 @@ impl Menu for ListMenu {
             self.settings.only_buffer_difference,
         );
 
-        let parsed = parse_selection_char(&input, SELECTION_CHAR);
+        let parsed = parse_selection_char(&input, self.settings.selection_char);
         self.update_row_pos(parsed.index);
 
         // If there are no row selector and the menu has an Edit event, this clears
@@ -406,6 +430,10 @@ impl Menu for ListMenu {
         }
     }
 
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.get_value()
+    }
+
     /// The buffer gets cleared with the actual value
     fn replace_in_buffer(&self, editor: &mut Editor) {
         replace_in_buffer(self.get_value(), editor);
@@ -544,7 +572,12 @@ impl Menu for ListMenu {
                     .enumerate()
                     .map(|(index, suggestion)| {
                         // Final string with colors
-                        let line = &suggestion.value;
+                        let line = match (&suggestion.match_indices, use_ansi_coloring) {
+                            (Some(match_indices), true) => {
+                                self.highlight_matches(&suggestion.value, match_indices, index)
+                            }
+                            _ => suggestion.value.clone(),
+                        };
                         let line = if line.lines().count() > self.max_lines as usize {
                             let lines = line.lines().take(self.max_lines as usize).fold(
                                 String::new(),
@@ -630,6 +663,34 @@ fn count_digits(mut n: usize) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        completion::history::HistoryCompleter, FileBackedHistory, History, HistoryItem,
+        UndoBehavior,
+    };
+
+    #[test]
+    fn highlights_history_matches() {
+        let mut history = FileBackedHistory::new(3).unwrap();
+        history
+            .save(HistoryItem::from_command_line("git push"))
+            .unwrap();
+        let mut completer = HistoryCompleter::new(&history, '!').with_highlight_matches(true);
+        let mut menu = ListMenu::default().with_only_buffer_difference(false);
+        let mut editor = Editor::default();
+        editor.set_buffer("pu".to_string(), UndoBehavior::CreateUndoPoint);
+        menu.update_values(&mut editor, &mut completer);
+        menu.pages.push(Page {
+            size: 1,
+            full: false,
+        });
+
+        let text_style = menu.text_style(0);
+        let match_style = menu.settings.color.selected_match_style.prefix();
+        let highlighted =
+            format!("git {match_style}p{RESET}{text_style}{match_style}u{RESET}{text_style}sh");
+        assert!(menu.menu_string(10, true).contains(&highlighted));
+        assert!(menu.menu_string(10, false).contains(">GIT PUSH"));
+    }
 
     #[test]
     fn number_of_lines_test() {
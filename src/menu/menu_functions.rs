@@ -589,6 +589,9 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                accept: false,
+                match_indices: None,
+                history_match: None,
             })
             .collect();
         let res = find_common_string(&input);
@@ -609,6 +612,9 @@ mod tests {
                 extra: None,
                 span: Span::new(0, s.len()),
                 append_whitespace: false,
+                accept: false,
+                match_indices: None,
+                history_match: None,
             })
             .collect();
         let res = find_common_string(&input);
@@ -664,6 +670,9 @@ mod tests {
                 extra: None,
                 span: Span::new(start, end),
                 append_whitespace: false,
+                accept: false,
+                match_indices: None,
+                history_match: None,
             }),
             &mut editor,
         );
@@ -5,8 +5,12 @@ mod list_menu;
 pub mod menu_functions;
 
 use crate::core_editor::Editor;
-use crate::History;
-use crate::{completion::history::HistoryCompleter, painting::Painter, Completer, Suggestion};
+use crate::{
+    completion::history::{HistoryCompleter, HistoryMenuCompleter, LastHistoryResults},
+    painting::Painter,
+    Completer, Suggestion,
+};
+use crate::{History, SearchDirection, SessionFilter};
 pub use columnar_menu::ColumnarMenu;
 pub use description_menu::DescriptionMenu;
 pub use ide_menu::DescriptionMode;
@@ -144,6 +148,12 @@ pub trait Menu: Send {
 
     /// Gets cached values from menu that will be displayed
     fn get_values(&self) -> &[Suggestion];
+
+    /// The value selected in the menu, to submit the line after replacing it in the buffer
+    /// if it is a [`Suggestion::accept`] one. Menus returning `None` never submit the line
+    fn selected_value(&self) -> Option<Suggestion> {
+        None
+    }
     /// Sets the position of the cursor (currently only required by the IDE menu)
     fn set_cursor_pos(&mut self, _pos: (u16, u16)) {
         // empty implementation to make it optional
@@ -160,6 +170,43 @@ pub struct MenuSettings {
     /// Calls the completer using only the line buffer difference difference
     /// after the menu was activated
     only_buffer_difference: bool,
+    /// Character that selects an entry by its index, as in `text!3`
+    selection_char: char,
+    /// History menus match entries by subsequence instead of substring
+    fuzzy_history: bool,
+    /// Half-life of the frecency ranking of history menus, if any
+    history_frecency: Option<usize>,
+    /// Most entries a history menu pulls from the history
+    history_max_results: Option<usize>,
+    /// History menus highlight the parts of the entries matching the typed text
+    highlight_history_matches: bool,
+    /// History menus show every occurrence of the matching entries
+    history_duplicates: bool,
+    /// History menus describe the entries with the time they were run
+    history_timestamp: bool,
+    /// History menus describe the entries with the directory they were run in
+    history_cwd: bool,
+    /// Sessions history menus pull their entries from, relative to the active one
+    history_session_filter: SessionFilter,
+    /// History menus only pull the entries run in the current directory or below it
+    history_cwd_scope: bool,
+    /// History menus only pull the entries that succeeded, or failed
+    history_exit_successful: Option<bool>,
+    /// History menus also pull the entries without an exit status when filtering on it
+    history_unknown_exit_status: bool,
+    /// History menus only pull the entries run on this host
+    history_hostname: Option<String>,
+    /// History menus leave out the entries containing any of these
+    history_excluded_substrings: Vec<String>,
+    /// History menus list the entries newest first if backward, oldest first if forward
+    history_direction: SearchDirection,
+    /// History menus submit the line right after selecting an entry that is exactly the
+    /// typed text
+    history_accept_exact_matches: bool,
+    /// History menus attach the recorded details of the entries to the suggestions
+    history_match_info: bool,
+    /// Last results of a history menu, shown while a slow history is still searching
+    history_results: LastHistoryResults,
 }
 
 impl Default for MenuSettings {
@@ -169,6 +216,24 @@ impl Default for MenuSettings {
             color: MenuTextStyle::default(),
             marker: "| ".to_string(),
             only_buffer_difference: false,
+            selection_char: '!',
+            fuzzy_history: false,
+            history_frecency: None,
+            history_max_results: None,
+            highlight_history_matches: false,
+            history_duplicates: false,
+            history_timestamp: false,
+            history_cwd: false,
+            history_session_filter: SessionFilter::All,
+            history_cwd_scope: false,
+            history_exit_successful: None,
+            history_unknown_exit_status: false,
+            history_hostname: None,
+            history_excluded_substrings: Vec::new(),
+            history_direction: SearchDirection::Backward,
+            history_accept_exact_matches: false,
+            history_match_info: false,
+            history_results: LastHistoryResults::default(),
         }
     }
 }
@@ -201,6 +266,148 @@ impl MenuSettings {
         self.only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// MenuSettings builder with the character selecting an entry by index, `!` by default
+    ///
+    /// Input where the character is followed by digits, `-`, or nothing is read as a
+    /// selection and the text from the character on is not searched for. Pick a character
+    /// that doesn't show up in this position in regular commands.
+    #[must_use]
+    pub fn with_selection_char(mut self, selection_char: char) -> Self {
+        self.selection_char = selection_char;
+        self
+    }
+
+    /// MenuSettings builder with fuzzy matching of the history entries in a history menu,
+    /// ranking the best matches first
+    #[must_use]
+    pub fn with_fuzzy_history(mut self, fuzzy_history: bool) -> Self {
+        self.fuzzy_history = fuzzy_history;
+        self
+    }
+
+    /// MenuSettings builder ranking the entries of a history menu by how often and how
+    /// recently they were used. The weight of an occurrence halves for every `half_life`
+    /// newer matching entries.
+    #[must_use]
+    pub fn with_history_frecency(mut self, half_life: Option<usize>) -> Self {
+        self.history_frecency = half_life;
+        self
+    }
+
+    /// MenuSettings builder with the most entries a history menu pulls from the history
+    #[must_use]
+    pub fn with_history_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.history_max_results = max_results;
+        self
+    }
+
+    /// MenuSettings builder highlighting the parts of the entries of a history menu matching
+    /// the typed text with the match text style
+    #[must_use]
+    pub fn with_highlight_history_matches(mut self, highlight: bool) -> Self {
+        self.highlight_history_matches = highlight;
+        self
+    }
+
+    /// MenuSettings builder showing every occurrence of the matching entries in a history
+    /// menu, described with the time they were run if the history records it
+    #[must_use]
+    pub fn with_history_duplicates(mut self, duplicates: bool) -> Self {
+        self.history_duplicates = duplicates;
+        self
+    }
+
+    /// MenuSettings builder describing the entries of a history menu with the time they were
+    /// run, if the history records it
+    #[must_use]
+    pub fn with_history_timestamp(mut self, timestamp: bool) -> Self {
+        self.history_timestamp = timestamp;
+        self
+    }
+
+    /// MenuSettings builder describing the entries of a history menu with the directory they
+    /// were run in, if the history records it
+    #[must_use]
+    pub fn with_history_cwd(mut self, cwd: bool) -> Self {
+        self.history_cwd = cwd;
+        self
+    }
+
+    /// MenuSettings builder restricting the entries of a history menu to the active session
+    /// or to the other sessions
+    #[must_use]
+    pub fn with_history_session_filter(mut self, session_filter: SessionFilter) -> Self {
+        self.history_session_filter = session_filter;
+        self
+    }
+
+    /// MenuSettings builder restricting the entries of a history menu to those run in the
+    /// current directory or below it
+    #[must_use]
+    pub fn with_history_cwd_scope(mut self, cwd_scope: bool) -> Self {
+        self.history_cwd_scope = cwd_scope;
+        self
+    }
+
+    /// MenuSettings builder restricting the entries of a history menu to those that exited
+    /// successfully if `Some(true)`, or to those that failed if `Some(false)`
+    #[must_use]
+    pub fn with_history_exit_successful(mut self, exit_successful: Option<bool>) -> Self {
+        self.history_exit_successful = exit_successful;
+        self
+    }
+
+    /// MenuSettings builder keeping the entries of a history menu without a recorded exit
+    /// status when filtering on it
+    #[must_use]
+    pub fn with_history_unknown_exit_status(mut self, unknown_exit_status: bool) -> Self {
+        self.history_unknown_exit_status = unknown_exit_status;
+        self
+    }
+
+    /// MenuSettings builder restricting the entries of a history menu to those run on the
+    /// host `hostname`. Shows the entries of every host if `None`
+    #[must_use]
+    pub fn with_history_hostname(mut self, hostname: Option<String>) -> Self {
+        self.history_hostname = hostname;
+        self
+    }
+
+    /// MenuSettings builder leaving out the entries of a history menu containing any of
+    /// `excluded_substrings`, like `--force`
+    #[must_use]
+    pub fn with_history_excluded_substrings(mut self, excluded_substrings: Vec<String>) -> Self {
+        self.history_excluded_substrings = excluded_substrings;
+        self
+    }
+
+    /// MenuSettings builder listing the entries of a history menu newest first if
+    /// [`SearchDirection::Backward`], the default, or oldest first if
+    /// [`SearchDirection::Forward`]
+    #[must_use]
+    pub fn with_history_direction(mut self, direction: SearchDirection) -> Self {
+        self.history_direction = direction;
+        self
+    }
+
+    /// MenuSettings builder submitting the line right after an entry of a history menu that
+    /// is exactly the typed text is selected
+    #[must_use]
+    pub fn with_history_accept_exact_matches(mut self, accept: bool) -> Self {
+        self.history_accept_exact_matches = accept;
+        self
+    }
+
+    /// MenuSettings builder attaching where the entries of a history menu came from to their
+    /// suggestions as [`Suggestion::history_match`](crate::Suggestion::history_match), for
+    /// menus previewing them. Off by default as counting the occurrences takes an extra
+    /// history query
+    #[must_use]
+    pub fn with_history_match_info(mut self, match_info: bool) -> Self {
+        self.history_match_info = match_info;
+        self
+    }
 }
 
 /// Common builder for all menus
@@ -268,6 +475,146 @@ pub trait MenuBuilder: Menu + Sized {
         self.settings_mut().only_buffer_difference = only_buffer_difference;
         self
     }
+
+    /// Menu builder with the character selecting an entry by index, `!` by default
+    ///
+    /// Input where the character is followed by digits, `-`, or nothing is read as a
+    /// selection and the text from the character on is not searched for. Pick a character
+    /// that doesn't show up in this position in regular commands.
+    #[must_use]
+    fn with_selection_char(mut self, selection_char: char) -> Self {
+        self.settings_mut().selection_char = selection_char;
+        self
+    }
+
+    /// Menu builder with fuzzy matching of the history entries when used as history menu,
+    /// ranking the best matches first
+    #[must_use]
+    fn with_fuzzy_history(mut self, fuzzy_history: bool) -> Self {
+        self.settings_mut().fuzzy_history = fuzzy_history;
+        self
+    }
+
+    /// Menu builder ranking the entries by how often and how recently they were used when
+    /// used as history menu. The weight of an occurrence halves for every `half_life` newer
+    /// matching entries.
+    #[must_use]
+    fn with_history_frecency(mut self, half_life: Option<usize>) -> Self {
+        self.settings_mut().history_frecency = half_life;
+        self
+    }
+
+    /// Menu builder with the most entries pulled from the history when used as history menu
+    #[must_use]
+    fn with_history_max_results(mut self, max_results: Option<usize>) -> Self {
+        self.settings_mut().history_max_results = max_results;
+        self
+    }
+
+    /// Menu builder highlighting the parts of the entries matching the typed text with the
+    /// match text style when used as history menu
+    #[must_use]
+    fn with_highlight_history_matches(mut self, highlight: bool) -> Self {
+        self.settings_mut().highlight_history_matches = highlight;
+        self
+    }
+
+    /// Menu builder showing every occurrence of the matching entries when used as history
+    /// menu, described with the time they were run if the history records it
+    #[must_use]
+    fn with_history_duplicates(mut self, duplicates: bool) -> Self {
+        self.settings_mut().history_duplicates = duplicates;
+        self
+    }
+
+    /// Menu builder describing the entries with the time they were run when used as history
+    /// menu, if the history records it
+    #[must_use]
+    fn with_history_timestamp(mut self, timestamp: bool) -> Self {
+        self.settings_mut().history_timestamp = timestamp;
+        self
+    }
+
+    /// Menu builder describing the entries with the directory they were run in when used as
+    /// history menu, if the history records it
+    #[must_use]
+    fn with_history_cwd(mut self, cwd: bool) -> Self {
+        self.settings_mut().history_cwd = cwd;
+        self
+    }
+
+    /// Menu builder restricting the entries to the active session or to the other sessions
+    /// when used as history menu
+    #[must_use]
+    fn with_history_session_filter(mut self, session_filter: SessionFilter) -> Self {
+        self.settings_mut().history_session_filter = session_filter;
+        self
+    }
+
+    /// Menu builder restricting the entries to those run in the current directory or below
+    /// it when used as history menu
+    #[must_use]
+    fn with_history_cwd_scope(mut self, cwd_scope: bool) -> Self {
+        self.settings_mut().history_cwd_scope = cwd_scope;
+        self
+    }
+
+    /// Menu builder restricting the entries to those that exited successfully if
+    /// `Some(true)`, or to those that failed if `Some(false)`, when used as history menu
+    #[must_use]
+    fn with_history_exit_successful(mut self, exit_successful: Option<bool>) -> Self {
+        self.settings_mut().history_exit_successful = exit_successful;
+        self
+    }
+
+    /// Menu builder keeping the entries without a recorded exit status when filtering on it
+    /// as history menu
+    #[must_use]
+    fn with_history_unknown_exit_status(mut self, unknown_exit_status: bool) -> Self {
+        self.settings_mut().history_unknown_exit_status = unknown_exit_status;
+        self
+    }
+
+    /// Menu builder restricting the entries to those run on the host `hostname` when used as
+    /// history menu. Shows the entries of every host if `None`
+    #[must_use]
+    fn with_history_hostname(mut self, hostname: Option<String>) -> Self {
+        self.settings_mut().history_hostname = hostname;
+        self
+    }
+
+    /// Menu builder leaving out the entries containing any of `excluded_substrings`, like
+    /// `--force`, when used as history menu
+    #[must_use]
+    fn with_history_excluded_substrings(mut self, excluded_substrings: Vec<String>) -> Self {
+        self.settings_mut().history_excluded_substrings = excluded_substrings;
+        self
+    }
+
+    /// Menu builder listing the entries newest first if [`SearchDirection::Backward`], the
+    /// default, or oldest first if [`SearchDirection::Forward`] when used as history menu
+    #[must_use]
+    fn with_history_direction(mut self, direction: SearchDirection) -> Self {
+        self.settings_mut().history_direction = direction;
+        self
+    }
+
+    /// Menu builder submitting the line right after an entry that is exactly the typed text
+    /// is selected when used as history menu
+    #[must_use]
+    fn with_history_accept_exact_matches(mut self, accept: bool) -> Self {
+        self.settings_mut().history_accept_exact_matches = accept;
+        self
+    }
+
+    /// Menu builder attaching where the entries came from to their suggestions as
+    /// [`Suggestion::history_match`](crate::Suggestion::history_match) when used as history
+    /// menu. Off by default as counting the occurrences takes an extra history query
+    #[must_use]
+    fn with_history_match_info(mut self, match_info: bool) -> Self {
+        self.settings_mut().history_match_info = match_info;
+        self
+    }
 }
 
 /// Allowed menus in Reedline
@@ -314,7 +661,7 @@ impl ReedlineMenu {
                 menu.can_partially_complete(values_updated, editor, completer)
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = history_completer(history, menu.settings());
                 menu.can_partially_complete(values_updated, editor, &mut history_completer)
             }
             Self::WithCompleter {
@@ -333,7 +680,7 @@ impl ReedlineMenu {
         match self {
             Self::EngineCompleter(menu) => menu.update_values(editor, completer),
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = history_completer(history, menu.settings());
                 menu.update_values(editor, &mut history_completer);
             }
             Self::WithCompleter {
@@ -357,7 +704,7 @@ impl ReedlineMenu {
                 menu.update_working_details(editor, completer, painter);
             }
             Self::HistoryMenu(menu) => {
-                let mut history_completer = HistoryCompleter::new(history);
+                let mut history_completer = history_completer(history, menu.settings());
                 menu.update_working_details(editor, &mut history_completer, painter);
             }
             Self::WithCompleter {
@@ -370,6 +717,38 @@ impl ReedlineMenu {
     }
 }
 
+/// Completer over the `history` for a history menu with the given `settings`
+fn history_completer<'menu>(
+    history: &'menu dyn History,
+    settings: &MenuSettings,
+) -> HistoryMenuCompleter<'menu> {
+    let completer = HistoryCompleter::new(history, settings.selection_char)
+        .with_fuzzy(settings.fuzzy_history)
+        .with_frecency(settings.history_frecency)
+        .with_max_results(settings.history_max_results)
+        .with_highlight_matches(settings.highlight_history_matches)
+        .with_duplicates(settings.history_duplicates)
+        .with_timestamp(settings.history_timestamp)
+        .with_cwd(settings.history_cwd)
+        .with_session_filter(settings.history_session_filter)
+        .with_exit_successful(settings.history_exit_successful)
+        .with_unknown_exit_status(settings.history_unknown_exit_status)
+        .with_hostname(settings.history_hostname.clone())
+        .with_excluded_substrings(settings.history_excluded_substrings.clone())
+        .with_direction(settings.history_direction)
+        .with_accept_exact_matches(settings.history_accept_exact_matches)
+        .with_match_info(settings.history_match_info)
+        .with_last_results(settings.history_results.clone())
+        .with_cwd_scope(if settings.history_cwd_scope {
+            std::env::current_dir()
+                .ok()
+                .map(|cwd| cwd.to_string_lossy().to_string())
+        } else {
+            None
+        });
+    HistoryMenuCompleter(completer)
+}
+
 impl Menu for ReedlineMenu {
     fn settings(&self) -> &MenuSettings {
         self.as_ref().settings()
@@ -466,6 +845,10 @@ impl Menu for ReedlineMenu {
         self.as_ref().get_values()
     }
 
+    fn selected_value(&self) -> Option<Suggestion> {
+        self.as_ref().selected_value()
+    }
+
     fn set_cursor_pos(&mut self, pos: (u16, u16)) {
         self.as_mut().set_cursor_pos(pos);
     }
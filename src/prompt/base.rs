@@ -60,6 +60,7 @@ pub enum PromptEditMode {
 
 /// The vi-specific modes that the prompt can be in
 #[derive(Serialize, Deserialize, Clone, Debug, EnumIter, Default)]
+#[non_exhaustive]
 pub enum PromptViMode {
     /// The default mode
     #[default]
@@ -67,6 +68,10 @@ pub enum PromptViMode {
 
     /// Insertion mode
     Insert,
+
+    /// Normal mode with a command waiting for more keys, like `d` for its motion, holding
+    /// the keys typed so far
+    OperatorPending(String),
 }
 
 impl Display for PromptEditMode {
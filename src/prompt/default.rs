@@ -63,7 +63,9 @@ impl Prompt for DefaultPrompt {
         match edit_mode {
             PromptEditMode::Default | PromptEditMode::Emacs => DEFAULT_PROMPT_INDICATOR.into(),
             PromptEditMode::Vi(vi_mode) => match vi_mode {
-                PromptViMode::Normal => DEFAULT_VI_NORMAL_PROMPT_INDICATOR.into(),
+                PromptViMode::Normal | PromptViMode::OperatorPending(_) => {
+                    DEFAULT_VI_NORMAL_PROMPT_INDICATOR.into()
+                }
                 PromptViMode::Insert => DEFAULT_VI_INSERT_PROMPT_INDICATOR.into(),
             },
             PromptEditMode::Custom(str) => format!("({str})").into(),
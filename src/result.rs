@@ -24,6 +24,16 @@ pub enum ReedlineErrorVariants {
         feature: &'static str,
     },
 
+    /// A search pattern is not a valid regular expression
+    #[error("invalid regular expression: {0}")]
+    InvalidRegex(String),
+
+    /// The history file could not be decrypted
+    #[error(
+        "could not decrypt the history file, the key may be wrong or the file tampered with: {0}"
+    )]
+    HistoryDecryptionFailed(String),
+
     /// I/O error
     #[error("I/O error: {0}")]
     IOError(std::io::Error),
@@ -35,6 +45,15 @@ pub struct ReedlineError(pub ReedlineErrorVariants);
 
 impl From<std::io::Error> for ReedlineError {
     fn from(err: std::io::Error) -> Self {
+        // Unwraps the errors raised where only I/O errors fit, like in `History::sync`
+        let wraps_reedline_error =
+            matches!(err.get_ref(), Some(inner) if inner.is::<ReedlineError>());
+        if wraps_reedline_error {
+            if let Some(Ok(inner)) = err.into_inner().map(|e| e.downcast::<ReedlineError>()) {
+                return *inner;
+            }
+            unreachable!("the error wraps a ReedlineError");
+        }
         Self(ReedlineErrorVariants::IOError(err))
     }
 }